@@ -0,0 +1,72 @@
+//!
+//! Benchmarks [find_path] on large maps. Run with `cargo bench`.
+//!
+#![feature(test)]
+
+extern crate test;
+
+use bevy::math::IVec2;
+use secrets_of_the_path::game::world::{find_path, Tile, TileMap};
+use test::{black_box, Bencher};
+
+const SIZE: i32 = 512;
+
+///
+/// A [SIZE] square of open path.
+///
+fn open_map() -> TileMap {
+    let mut map = TileMap::new();
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            map.set(IVec2::new(x, y), Tile::Path);
+        }
+    }
+    map
+}
+
+///
+/// A [SIZE] square where every other column is a wall with one gap, at the top and bottom in turn, so the
+/// only way across snakes up and down the whole map.
+///
+fn maze_map() -> TileMap {
+    let mut map = open_map();
+    for x in (1..SIZE).step_by(2) {
+        let gap = if x % 4 == 1 { SIZE - 1 } else { 0 };
+        for y in (0..SIZE).filter(|y| *y != gap) {
+            map.set(IVec2::new(x, y), Tile::Wall);
+        }
+    }
+    map
+}
+
+#[bench]
+fn open_corner_to_corner(b: &mut Bencher) {
+    let map = open_map();
+    let goal = IVec2::splat(SIZE - 1);
+    b.iter(|| find_path(black_box(&map), IVec2::ZERO, goal, |_| false).unwrap());
+}
+
+#[bench]
+fn open_around_a_blocked_line(b: &mut Bencher) {
+    let map = open_map();
+    let goal = IVec2::new(SIZE - 1, 0);
+    // A wall of blocked tiles across the middle with one gap at the far end.
+    let blocked = |tile: IVec2| tile.x == SIZE / 2 && tile.y < SIZE - 1;
+    b.iter(|| find_path(black_box(&map), IVec2::ZERO, goal, blocked).unwrap());
+}
+
+#[bench]
+fn maze_end_to_end(b: &mut Bencher) {
+    let map = maze_map();
+    let goal = IVec2::new(SIZE - 1, 0);
+    b.iter(|| find_path(black_box(&map), IVec2::ZERO, goal, |_| false).unwrap());
+}
+
+#[bench]
+fn maze_unreachable(b: &mut Bencher) {
+    let mut map = maze_map();
+    // Closing the last gap on the way cuts off the far end, so the whole maze is searched before giving up.
+    map.set(IVec2::new(SIZE - 3, SIZE - 1), Tile::Wall);
+    let goal = IVec2::new(SIZE - 2, SIZE - 1);
+    b.iter(|| assert!(find_path(black_box(&map), IVec2::ZERO, goal, |_| false).is_none()));
+}
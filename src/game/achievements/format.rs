@@ -0,0 +1,102 @@
+use crate::utils::ron_asset::RonParseError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+
+///
+/// Version of the achievement format this build reads.
+///
+pub const ACHIEVEMENT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum AchievementError {
+    #[error("could not read achievements: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] RonParseError),
+    #[error("achievements have format version {0}, but this build only supports up to {ACHIEVEMENT_FORMAT_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("achievement id \"{0}\" is used more than once")]
+    DuplicateId(String),
+    #[error("achievement \"{0}\" can never be unlocked, its condition needs a value above 0")]
+    InvalidCondition(String),
+}
+
+///
+/// A set of achievements as written in a `.achievements.ron` file.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AchievementsFile {
+    pub version: u32,
+    pub achievements: Vec<AchievementDef>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AchievementDef {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub condition: AchievementCondition,
+}
+
+///
+/// A counter kept in [super::Statistics], across every save.
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Statistic {
+    StepsWalked,
+    SecretsFound,
+    /// In seconds.
+    TimePlayed,
+    LevelsCompleted,
+    HintsUsed,
+}
+
+///
+/// When an achievement unlocks. A level is completed when every secret in it has been found.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AchievementCondition {
+    /// A statistic reaching `at_least`.
+    Statistic { stat: Statistic, at_least: f64 },
+    /// Completing `level`, or any level if it isn't given.
+    CompleteLevel {
+        #[serde(default)]
+        level: Option<String>,
+    },
+    /// Completing a level without taking a hint there.
+    CompleteWithoutHints {
+        #[serde(default)]
+        level: Option<String>,
+    },
+    /// Completing a level within `seconds` of play time from first entering it.
+    CompleteWithin {
+        #[serde(default)]
+        level: Option<String>,
+        seconds: f64,
+    },
+}
+
+impl AchievementsFile {
+    pub fn validate(&self) -> Result<(), AchievementError> {
+        if self.version > ACHIEVEMENT_FORMAT_VERSION {
+            return Err(AchievementError::UnsupportedVersion(self.version));
+        }
+        let mut ids = HashSet::new();
+        for achievement in &self.achievements {
+            if !ids.insert(achievement.id.as_str()) {
+                return Err(AchievementError::DuplicateId(achievement.id.clone()));
+            }
+            let invalid = match achievement.condition {
+                AchievementCondition::Statistic { at_least, .. } => at_least <= 0.0,
+                AchievementCondition::CompleteWithin { seconds, .. } => seconds <= 0.0,
+                _ => false,
+            };
+            if invalid {
+                return Err(AchievementError::InvalidCondition(achievement.id.clone()));
+            }
+        }
+        Ok(())
+    }
+}
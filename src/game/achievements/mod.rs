@@ -0,0 +1,269 @@
+use crate::game::hints::HintTaken;
+use crate::game::internal::menu_text;
+use crate::game::level::{CurrentLevel, Level};
+use crate::game::outro::ExitFlush;
+use crate::game::player::{GridPosition, Player, PlayerMovementSet};
+use crate::game::primary_logic::{GameData, GameState, InGameScreen};
+use crate::game::saves::SaveGame;
+use crate::game::secrets::SecretDiscovered;
+use crate::utils::position_smoothing::PositionSmoothing;
+use crate::utils::ron_asset::{RonAsset, RonLoader};
+use bevy::asset::LoadedFolder;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod format;
+mod persistence;
+mod records;
+
+pub use format::*;
+pub use records::{level_complete, LevelCompletion, Records};
+
+///
+/// Achievements defined in `.achievements.ron` files in `assets/achievements`, unlocked from what happens
+/// while playing, and the statistics they count. Both are kept in their own file so they last across
+/// saves, and every unlock is announced in the corner of the screen.
+///
+#[derive(Default)]
+pub struct AchievementsPlugin;
+
+///
+/// A validated set of achievements.
+///
+#[derive(Asset, TypePath, Debug)]
+pub struct AchievementSet {
+    pub file: AchievementsFile,
+}
+
+impl RonAsset for AchievementSet {
+    type File = AchievementsFile;
+    type Error = AchievementError;
+
+    const EXTENSION: &'static str = "achievements.ron";
+
+    fn from_file(file: AchievementsFile) -> Result<AchievementSet, AchievementError> {
+        file.validate()?;
+        Ok(AchievementSet { file })
+    }
+}
+
+///
+/// Keeps every achievement set in `assets/achievements` loaded.
+///
+#[derive(Resource)]
+pub struct AchievementCatalog {
+    _folder: Handle<LoadedFolder>,
+}
+
+impl AchievementCatalog {
+    ///
+    /// All loaded achievements, ordered by id.
+    ///
+    pub fn achievements(achievement_sets: &Assets<AchievementSet>) -> Vec<&AchievementDef> {
+        let mut achievements: Vec<&AchievementDef> =
+            achievement_sets.iter().flat_map(|(_, set)| &set.file.achievements).collect();
+        achievements.sort_by(|a, b| a.id.cmp(&b.id));
+        achievements
+    }
+}
+
+///
+/// A notification in the corner of the screen, despawned when the timer runs out.
+///
+#[derive(Component)]
+struct Toast {
+    timer: Timer,
+    /// Place from the top, so toasts shown at once don't overlap.
+    row: usize,
+}
+
+const TOAST_DURATION: f32 = 5.0;
+/// Seconds at the end of a toast in which it fades out.
+const TOAST_FADE: f32 = 1.0;
+/// Distance between toast rows, in percent of the view.
+const TOAST_SPACING: f32 = 8.0;
+const TOAST_TITLE_SIZE: f32 = 20.0;
+const TOAST_TEXT_SIZE: f32 = 14.0;
+const TOAST_TITLE_COLOR: Color = Color::srgb(2.8, 2.4, 1.2);
+const TOAST_TEXT_COLOR: Color = Color::srgb(1.4, 1.4, 1.4);
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        persistence::configure_app(app);
+        app.init_asset::<AchievementSet>();
+        app.init_asset_loader::<RonLoader<AchievementSet>>();
+        app.add_systems(Startup, load_catalog);
+        app.add_systems(Update, count_steps.after(PlayerMovementSet).run_if(in_state(InGameScreen::Playing)));
+        app.add_systems(
+            Update,
+            (count_play_time, count_hints, track_achievements, save_with_game, update_toasts)
+                .chain()
+                .after(PlayerMovementSet)
+                .run_if(in_state(GameState::InGame)),
+        );
+        app.add_systems(Update, flush_records.in_set(ExitFlush));
+        app.add_systems(OnExit(GameState::InGame), flush_records);
+    }
+}
+
+fn load_catalog(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AchievementCatalog {
+        _folder: asset_server.load_folder("achievements"),
+    });
+}
+
+///
+/// Formats a best time as minutes and seconds, e.g. "4:07".
+///
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn count_steps(
+    players: Query<&GridPosition, (With<Player>, Changed<GridPosition>)>,
+    mut records: ResMut<Records>,
+    mut last_position: Local<Option<IVec2>>,
+) {
+    for grid_position in players.iter() {
+        // Only single steps count, not being put somewhere else by a new level or a room reset.
+        if last_position.is_some_and(|last| (grid_position.0 - last).abs().element_sum() == 1) {
+            records.statistics.steps_walked += 1;
+        }
+        *last_position = Some(grid_position.0);
+    }
+}
+
+fn count_play_time(mut records: ResMut<Records>, time: Res<Time>) {
+    records.statistics.time_played += time.delta_seconds_f64();
+}
+
+fn count_hints(mut hints: EventReader<HintTaken>, mut records: ResMut<Records>) {
+    records.statistics.hints_used += hints.read().count() as u64;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn track_achievements(
+    mut commands: Commands,
+    mut records: ResMut<Records>,
+    mut file: ResMut<persistence::RecordsFile>,
+    achievement_sets: Res<Assets<AchievementSet>>,
+    game_data: Res<GameData>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    mut discoveries: EventReader<SecretDiscovered>,
+    toasts: Query<&Toast>,
+) {
+    let secrets = levels
+        .get(&current_level.handle)
+        .map_or(&[][..], |level| level.file.secrets.as_slice());
+    let mut completions = Vec::new();
+    for discovery in discoveries.read() {
+        records.statistics.secrets_found += 1;
+        // The level is completed by the discovery which found its last secret. Secrets found in the same frame
+        // complete it once.
+        let completes = secrets.iter().any(|secret| secret.id == discovery.id) && level_complete(secrets, &game_data);
+        if completes && completions.is_empty() {
+            let entered = game_data.level_entered.get(&game_data.level).copied().unwrap_or_default();
+            completions.push(LevelCompletion {
+                level: game_data.level.clone(),
+                play_time: game_data.play_time - entered,
+                hints: game_data.hints_used.get(&game_data.level).copied().unwrap_or(0),
+            });
+        }
+    }
+
+    let mut rows: Vec<usize> = toasts.iter().map(|toast| toast.row).collect();
+    let mut toast = |title: &str, text: String| {
+        let row = (0..).find(|row| !rows.contains(row)).unwrap_or_default();
+        rows.push(row);
+        spawn_toast(&mut commands, row, title, text);
+    };
+
+    for completion in &completions {
+        if records.complete_level(completion) {
+            info!("New best time for {}: {}", completion.level, format_time(completion.play_time));
+            toast(
+                "New best time",
+                format!("{} in {}", game_data.location_name, format_time(completion.play_time)),
+            );
+        }
+    }
+
+    let achievements = AchievementCatalog::achievements(&achievement_sets);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let unlocked = records.unlock(&achievements, &completions, now);
+    for id in &unlocked {
+        let Some(achievement) = achievements.iter().find(|achievement| achievement.id == *id) else {
+            continue;
+        };
+        info!("Achievement unlocked: {} ({id})", achievement.name);
+        toast(&format!("Achievement: {}", achievement.name), achievement.description.clone());
+    }
+
+    if !completions.is_empty() || !unlocked.is_empty() {
+        persistence::write_records(&mut file, &records);
+    }
+}
+
+///
+/// Writes the records whenever the game is saved, so the statistics are never far behind the save.
+///
+fn save_with_game(mut events: EventReader<SaveGame>, records: Res<Records>, mut file: ResMut<persistence::RecordsFile>) {
+    if events.read().count() > 0 {
+        persistence::write_records(&mut file, &records);
+    }
+}
+
+fn flush_records(records: Res<Records>, mut file: ResMut<persistence::RecordsFile>) {
+    persistence::write_records(&mut file, &records);
+}
+
+fn spawn_toast(commands: &mut Commands, row: usize, title: &str, text: String) {
+    let y = 96.0 - row as f32 * TOAST_SPACING;
+    let sections = [
+        TextSection::new(
+            format!("{title}\n"),
+            TextStyle {
+                color: TOAST_TITLE_COLOR,
+                font_size: TOAST_TITLE_SIZE,
+                ..default()
+            },
+        ),
+        TextSection::new(
+            text,
+            TextStyle {
+                color: TOAST_TEXT_COLOR,
+                font_size: TOAST_TEXT_SIZE,
+                ..default()
+            },
+        ),
+    ];
+    commands
+        .spawn((
+            StateScoped(GameState::InGame),
+            Toast {
+                timer: Timer::from_seconds(TOAST_DURATION, TimerMode::Once),
+                row,
+            },
+            menu_text("", TOAST_TITLE_COLOR, TOAST_TITLE_SIZE, 98.0, y, Anchor::TopRight),
+        ))
+        .insert((Text::from_sections(sections).with_justify(JustifyText::Right), PositionSmoothing::None));
+}
+
+fn update_toasts(mut commands: Commands, mut query: Query<(Entity, &mut Toast, &mut Text)>, time: Res<Time>) {
+    for (entity, mut toast, mut text) in query.iter_mut() {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = (toast.timer.remaining_secs() / TOAST_FADE).min(1.0);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_alpha(alpha);
+        }
+    }
+}
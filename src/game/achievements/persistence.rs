@@ -0,0 +1,107 @@
+use super::Records;
+use crate::utils::files::write_atomic;
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const RECORDS_FILE_NAME: &str = "records.ron";
+
+#[derive(Debug, Error)]
+pub enum RecordsError {
+    #[error("could not access records file {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("records file {path} is malformed: {source}")]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: ron::error::SpannedError,
+    },
+    #[error("could not serialize records: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+///
+/// Where [Records] are stored, next to the save slots but not in any of them.
+///
+#[derive(Resource)]
+pub struct RecordsFile {
+    pub path: PathBuf,
+    /// What the file is known to contain, so unchanged records are never rewritten.
+    last_saved: Option<Records>,
+}
+
+///
+/// The platform data directory (e.g. `~/.local/share/secrets-of-the-path/records.ron` on Linux), or the
+/// working directory if there isn't one.
+///
+pub fn default_records_path() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("secrets-of-the-path"))
+        .unwrap_or_default()
+        .join(RECORDS_FILE_NAME)
+}
+
+///
+/// Reads the records at `path`. Returns `Ok(None)` if the file does not exist yet.
+///
+pub fn load_records(path: &Path) -> Result<Option<Records>, RecordsError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => {
+            return Err(RecordsError::Io {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    ron::from_str(&contents).map(Some).map_err(|source| RecordsError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+pub fn save_records(path: &Path, records: &Records) -> Result<(), RecordsError> {
+    let contents = ron::ser::to_string_pretty(records, PrettyConfig::default())?;
+    write_atomic(path, contents.as_bytes()).map_err(|source| RecordsError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+///
+/// Loads the records, or starts new ones if there are none or they can't be read.
+///
+pub fn configure_app(app: &mut App) {
+    let path = default_records_path();
+    let records = match load_records(&path) {
+        Ok(records) => records.unwrap_or_default(),
+        Err(e) => {
+            error!("{e}, starting new records");
+            Records::default()
+        }
+    };
+
+    app.insert_resource(RecordsFile {
+        path,
+        last_saved: Some(records.clone()),
+    });
+    app.insert_resource(records);
+}
+
+pub fn write_records(file: &mut RecordsFile, records: &Records) {
+    if file.last_saved.as_ref() == Some(records) {
+        return;
+    }
+    match save_records(&file.path, records) {
+        Ok(()) => file.last_saved = Some(records.clone()),
+        Err(e) => error!("{e}"),
+    }
+}
@@ -0,0 +1,236 @@
+use super::{AchievementCondition, AchievementDef, Statistic};
+use crate::game::level::LevelSecret;
+use crate::game::primary_logic::GameData;
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+///
+/// Everything kept across saves: statistics, unlocked achievements and best times. Deleting a save slot
+/// doesn't take any of it away.
+///
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Records {
+    pub statistics: Statistics,
+    /// Achievement id to when it was unlocked, in seconds since the unix epoch.
+    pub unlocked: BTreeMap<String, u64>,
+    /// Level id to the least play time a save has completed it in.
+    pub best_times: BTreeMap<String, f64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Statistics {
+    pub steps_walked: u64,
+    pub secrets_found: u64,
+    /// In seconds.
+    pub time_played: f64,
+    pub levels_completed: u64,
+    pub hints_used: u64,
+}
+
+///
+/// A level whose last secret has just been found.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelCompletion {
+    pub level: String,
+    /// Play time on the save from first entering the level to completing it.
+    pub play_time: f64,
+    /// Hints taken in the level on the save.
+    pub hints: u32,
+}
+
+impl Statistics {
+    pub fn get(&self, stat: Statistic) -> f64 {
+        match stat {
+            Statistic::StepsWalked => self.steps_walked as f64,
+            Statistic::SecretsFound => self.secrets_found as f64,
+            Statistic::TimePlayed => self.time_played,
+            Statistic::LevelsCompleted => self.levels_completed as f64,
+            Statistic::HintsUsed => self.hints_used as f64,
+        }
+    }
+}
+
+///
+/// Whether every secret of a level has been found. Levels without secrets can't be completed.
+///
+pub fn level_complete(secrets: &[LevelSecret], game_data: &GameData) -> bool {
+    !secrets.is_empty() && secrets.iter().all(|secret| game_data.discovered_secrets.contains(&secret.id))
+}
+
+fn completes(level: &Option<String>, completion: &LevelCompletion) -> bool {
+    level.as_ref().is_none_or(|level| *level == completion.level)
+}
+
+impl AchievementCondition {
+    pub fn check(&self, statistics: &Statistics, completions: &[LevelCompletion]) -> bool {
+        match self {
+            AchievementCondition::Statistic { stat, at_least } => statistics.get(*stat) >= *at_least,
+            AchievementCondition::CompleteLevel { level } => completions.iter().any(|c| completes(level, c)),
+            AchievementCondition::CompleteWithoutHints { level } => {
+                completions.iter().any(|c| completes(level, c) && c.hints == 0)
+            }
+            AchievementCondition::CompleteWithin { level, seconds } => {
+                completions.iter().any(|c| completes(level, c) && c.play_time <= *seconds)
+            }
+        }
+    }
+}
+
+impl Records {
+    ///
+    /// Counts a completed level and keeps its time if it is the best so far. Returns whether it was.
+    ///
+    pub fn complete_level(&mut self, completion: &LevelCompletion) -> bool {
+        self.statistics.levels_completed += 1;
+        let best = self.best_times.get(&completion.level).is_none_or(|best| completion.play_time < *best);
+        if best {
+            self.best_times.insert(completion.level.clone(), completion.play_time);
+        }
+        best
+    }
+
+    ///
+    /// Unlocks every achievement whose condition now holds, returning the ids of those which weren't
+    /// unlocked before.
+    ///
+    pub fn unlock(&mut self, achievements: &[&AchievementDef], completions: &[LevelCompletion], now: u64) -> Vec<String> {
+        let mut unlocked = Vec::new();
+        for achievement in achievements {
+            if self.unlocked.contains_key(&achievement.id) {
+                continue;
+            }
+            if achievement.condition.check(&self.statistics, completions) {
+                self.unlocked.insert(achievement.id.clone(), now);
+                unlocked.push(achievement.id.clone());
+            }
+        }
+        unlocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::achievements::{AchievementError, AchievementsFile};
+    use crate::utils::ron_asset::parse_ron;
+
+    fn achievements(text: &str) -> AchievementsFile {
+        parse_ron::<AchievementsFile>(&format!("(version: 1, achievements: [{text}])")).unwrap()
+    }
+
+    fn completion(level: &str, play_time: f64, hints: u32) -> LevelCompletion {
+        LevelCompletion {
+            level: level.to_string(),
+            play_time,
+            hints,
+        }
+    }
+
+    fn secret(id: &str) -> LevelSecret {
+        parse_ron::<LevelSecret>(&format!("(id: \"{id}\", name: \"{id}\", position: (0, 0))")).unwrap()
+    }
+
+    #[test]
+    fn shipped_achievements_are_valid() {
+        let file =
+            parse_ron::<AchievementsFile>(include_str!("../../../assets/achievements/base.achievements.ron")).unwrap();
+        file.validate().unwrap();
+    }
+
+    #[test]
+    fn conditions_which_can_never_hold_are_rejected() {
+        let file =
+            achievements(r#"(id: "lazy", name: "Lazy", condition: Statistic(stat: StepsWalked, at_least: 0))"#);
+        assert!(matches!(file.validate(), Err(AchievementError::InvalidCondition(id)) if id == "lazy"));
+
+        let file = achievements(r#"(id: "instant", name: "Instant", condition: CompleteWithin(seconds: 0))"#);
+        assert!(matches!(file.validate(), Err(AchievementError::InvalidCondition(_))));
+
+        let file = achievements(
+            r#"(id: "done", name: "Done", condition: CompleteLevel()),
+               (id: "done", name: "Done Again", condition: CompleteLevel())"#,
+        );
+        assert!(matches!(file.validate(), Err(AchievementError::DuplicateId(id)) if id == "done"));
+    }
+
+    #[test]
+    fn levels_are_complete_once_every_secret_is_found() {
+        let secrets = [secret("well"), secret("cave")];
+        let mut game_data = GameData::default();
+        game_data.discovered_secrets.insert("well".to_string());
+        assert!(!level_complete(&secrets, &game_data));
+        game_data.discovered_secrets.insert("cave".to_string());
+        assert!(level_complete(&secrets, &game_data));
+        assert!(!level_complete(&[], &game_data), "levels without secrets can't be completed");
+    }
+
+    #[test]
+    fn completions_only_count_for_their_level() {
+        let condition = AchievementCondition::CompleteLevel {
+            level: Some("grove".to_string()),
+        };
+        let statistics = Statistics::default();
+        assert!(!condition.check(&statistics, &[completion("river", 10.0, 0)]));
+        assert!(condition.check(&statistics, &[completion("grove", 10.0, 0)]));
+        let any_level = AchievementCondition::CompleteLevel { level: None };
+        assert!(any_level.check(&statistics, &[completion("river", 10.0, 0)]));
+    }
+
+    #[test]
+    fn taking_a_hint_rules_out_completing_without_hints() {
+        let condition = AchievementCondition::CompleteWithoutHints { level: None };
+        let statistics = Statistics::default();
+        assert!(!condition.check(&statistics, &[completion("grove", 10.0, 1)]));
+        assert!(condition.check(&statistics, &[completion("grove", 10.0, 0)]));
+    }
+
+    #[test]
+    fn statistics_and_times_are_compared_inclusively() {
+        let statistics = Statistics {
+            secrets_found: 5,
+            ..Statistics::default()
+        };
+        let at_least = |at_least| AchievementCondition::Statistic {
+            stat: Statistic::SecretsFound,
+            at_least,
+        };
+        assert!(at_least(5.0).check(&statistics, &[]));
+        assert!(!at_least(6.0).check(&statistics, &[]));
+
+        let within = AchievementCondition::CompleteWithin {
+            level: None,
+            seconds: 60.0,
+        };
+        assert!(within.check(&statistics, &[completion("grove", 60.0, 0)]));
+        assert!(!within.check(&statistics, &[completion("grove", 60.5, 0)]));
+    }
+
+    #[test]
+    fn achievements_are_only_unlocked_once() {
+        let file =
+            achievements(r#"(id: "first", name: "First", condition: Statistic(stat: SecretsFound, at_least: 1))"#);
+        let achievements: Vec<&AchievementDef> = file.achievements.iter().collect();
+        let mut records = Records::default();
+        assert!(records.unlock(&achievements, &[], 100).is_empty());
+
+        records.statistics.secrets_found = 1;
+        assert_eq!(records.unlock(&achievements, &[], 200), vec!["first".to_string()]);
+        assert!(records.unlock(&achievements, &[], 300).is_empty());
+        assert_eq!(records.unlocked["first"], 200);
+    }
+
+    #[test]
+    fn only_faster_times_replace_the_best_time() {
+        let mut records = Records::default();
+        assert!(records.complete_level(&completion("grove", 90.0, 0)));
+        assert!(!records.complete_level(&completion("grove", 120.0, 0)));
+        assert_eq!(records.best_times["grove"], 90.0);
+        assert!(records.complete_level(&completion("grove", 60.0, 2)));
+        assert_eq!(records.best_times["grove"], 60.0);
+        assert_eq!(records.statistics.levels_completed, 3);
+    }
+}
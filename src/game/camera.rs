@@ -0,0 +1,429 @@
+use crate::game::player::PlayerMovementSet;
+use crate::game::primary_logic::{GameState, InGameScreen, MainCamera};
+use crate::game::settings::{Settings, SettingsPreview};
+use crate::game::world::{CurrentMap, TILE_SIZE};
+use crate::utils::position_smoothing::{PSmoothing, PositionSmoothing, TargetPosition};
+use bevy::prelude::*;
+use bevy::render::camera::{CameraProjection, CameraUpdateSystem};
+use bevy::transform::TransformSystem;
+
+///
+/// Moves the [MainCamera]: a slow drift in menus, and following the player in game.
+///
+#[derive(Default)]
+pub struct CameraPlugin;
+
+///
+/// Zoom (projection scale) while playing. Menus are shown at a zoom of 1.
+///
+pub const GAME_ZOOM: f32 = 0.6;
+
+/// Trauma lost per second.
+const TRAUMA_DECAY: f32 = 1.2;
+/// Offset at full trauma, in world units.
+const MAX_SHAKE_OFFSET: f32 = TILE_SIZE * 0.5;
+/// Rotation at full trauma, in radians.
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+/// How quickly the shake wobbles, in radians of noise per second.
+const SHAKE_FREQUENCY: f32 = 25.0;
+/// Rate at which punches and zoom impulses die out, as an exponential decay per second.
+const IMPULSE_DECAY: f32 = 8.0;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub enum CameraMode {
+    /// Sway gently around the origin.
+    #[default]
+    Drift,
+    /// Keep the [CameraTarget] in view, clamped to the bounds of the [CurrentMap].
+    Follow,
+}
+
+///
+/// Drives the camera's [TargetPosition] and zoom. The camera's [PositionSmoothing] does the gliding.
+///
+#[derive(Component)]
+pub struct CameraController {
+    pub mode: CameraMode,
+    /// Half the size of the box around the focus point the target can move in without the camera following.
+    pub dead_zone: Vec2,
+    /// How far ahead of the target, in its direction of movement, the camera looks.
+    pub look_ahead: f32,
+    /// Projection scale to smoothly zoom to.
+    pub zoom: f32,
+    /// Fraction of the remaining zoom covered per second.
+    pub zoom_speed: f32,
+    focus: Vec2,
+    look_offset: Vec2,
+    last_target: Option<Vec2>,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            mode: CameraMode::Drift,
+            dead_zone: Vec2::new(TILE_SIZE * 1.5, TILE_SIZE),
+            look_ahead: TILE_SIZE * 2.0,
+            zoom: 1.0,
+            zoom_speed: 3.0,
+            focus: Vec2::ZERO,
+            look_offset: Vec2::ZERO,
+            last_target: None,
+        }
+    }
+}
+
+///
+/// The entity the camera follows in [CameraMode::Follow].
+///
+#[derive(Default, Component)]
+pub struct CameraTarget;
+
+///
+/// Shakes and nudges the camera. Send [CameraImpulse]s rather than changing this directly.
+///
+/// The offsets are applied after everything else in the frame and taken back off at the start of the next,
+/// so the rest of the game only ever sees the camera's smoothed position.
+///
+#[derive(Component, Default)]
+pub struct CameraShake {
+    /// From 0 to 1. The shake grows with the square of this, so small amounts barely register.
+    trauma: f32,
+    punch: Vec2,
+    zoom: f32,
+    applied_offset: Vec2,
+    applied_angle: f32,
+    applied_zoom: f32,
+}
+
+///
+/// Something happened which should jolt the camera.
+///
+#[derive(Event, Copy, Clone, Debug)]
+pub enum CameraImpulse {
+    /// Adds trauma (0 to 1) for a shake which fades over time.
+    Trauma(f32),
+    /// Knocks the camera by an offset in world units, which springs back.
+    Punch(Vec2),
+    /// Briefly changes the zoom by this much, positive zooming out.
+    Zoom(f32),
+}
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CameraImpulse>();
+        app.add_systems(OnEnter(GameState::InGame), set_mode(CameraMode::Follow, GAME_ZOOM));
+        app.add_systems(OnExit(GameState::InGame), set_mode(CameraMode::Drift, 1.0));
+        // Overlays are laid out for a zoom of 1.
+        app.add_systems(OnEnter(InGameScreen::Codex), set_zoom(1.0));
+        app.add_systems(OnExit(InGameScreen::Codex), set_zoom(GAME_ZOOM));
+        app.add_systems(OnEnter(InGameScreen::Inventory), set_zoom(1.0));
+        app.add_systems(OnExit(InGameScreen::Inventory), set_zoom(GAME_ZOOM));
+        app.add_systems(OnEnter(InGameScreen::Quests), set_zoom(1.0));
+        app.add_systems(OnExit(InGameScreen::Quests), set_zoom(GAME_ZOOM));
+        app.add_systems(Update, (zoom_camera, move_camera).chain().after(PlayerMovementSet));
+        app.add_systems(PreUpdate, remove_shake);
+        app.add_systems(
+            PostUpdate,
+            (receive_impulses, apply_shake)
+                .chain()
+                .before(CameraUpdateSystem)
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+fn set_mode(mode: CameraMode, zoom: f32) -> impl Fn(Query<&mut CameraController>) {
+    move |mut query| {
+        for mut controller in query.iter_mut() {
+            controller.mode = mode;
+            controller.zoom = zoom;
+            controller.last_target = None;
+        }
+    }
+}
+
+fn set_zoom(zoom: f32) -> impl Fn(Query<&mut CameraController>) {
+    move |mut query| {
+        for mut controller in query.iter_mut() {
+            controller.zoom = zoom;
+        }
+    }
+}
+
+///
+/// The smoothing the camera is spawned with, which suits both modes.
+///
+pub fn camera_smoothing() -> PositionSmoothing {
+    PositionSmoothing::P(PSmoothing {
+        modifier: 4.0,
+        end_threshold: 0.1,
+    })
+}
+
+fn zoom_camera(mut query: Query<(&CameraController, &mut OrthographicProjection)>, time: Res<Time>) {
+    for (controller, mut projection) in query.iter_mut() {
+        let t = (controller.zoom_speed * time.delta_seconds()).min(1.0);
+        let scale = projection.scale + (controller.zoom - projection.scale) * t;
+        if (scale - controller.zoom).abs() < 0.001 {
+            if projection.scale != controller.zoom {
+                projection.scale = controller.zoom;
+            }
+        } else {
+            projection.scale = scale;
+        }
+    }
+}
+
+fn move_camera(
+    mut cameras: Query<(&mut CameraController, &OrthographicProjection, &Transform, &mut TargetPosition), With<MainCamera>>,
+    targets: Query<&Transform, (With<CameraTarget>, Without<MainCamera>)>,
+    current_map: Res<CurrentMap>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (mut controller, projection, transform, mut target_position) in cameras.iter_mut() {
+        // The view spans `projection.area` from the camera's position, so its centre is offset from it.
+        let view_centre = projection.area.center();
+
+        let centre = match controller.mode {
+            CameraMode::Drift => {
+                let t = time.elapsed_seconds();
+                Vec2::new((t * 0.13).sin() * 24.0, (t * 0.09).cos() * 16.0) + view_centre
+            }
+            CameraMode::Follow => {
+                let Some(target) = targets.iter().next().map(|target| target.translation.xy()) else {
+                    continue;
+                };
+                let Some(last_target) = controller.last_target.replace(target) else {
+                    // Snap to a new target instead of sweeping across the map to it.
+                    controller.focus = target;
+                    controller.look_offset = Vec2::ZERO;
+                    continue;
+                };
+
+                let offset = target - controller.focus;
+                let dead_zone = controller.dead_zone;
+                controller.focus += offset - offset.clamp(-dead_zone, dead_zone);
+
+                let velocity = (target - last_target) / dt.max(f32::EPSILON);
+                let ahead = if velocity.length() > 1.0 {
+                    velocity.normalize() * controller.look_ahead
+                } else {
+                    Vec2::ZERO
+                };
+                controller.look_offset = controller.look_offset.lerp(ahead, (dt * 1.5).min(1.0));
+
+                let centre = controller.focus + controller.look_offset;
+                if current_map.bounds.is_empty() {
+                    centre
+                } else {
+                    clamp_to_bounds(centre, projection.area.size(), current_map.bounds)
+                }
+            }
+        };
+
+        let position = (centre - view_centre).extend(transform.translation.z);
+        if target_position.0 != position {
+            target_position.0 = position;
+        }
+    }
+}
+
+///
+/// Keeps a view of `view_size` centred on `centre` inside the tile `bounds`, or centres it on them
+/// if it is too big to fit.
+///
+pub fn clamp_to_bounds(centre: Vec2, view_size: Vec2, bounds: IRect) -> Vec2 {
+    let min = bounds.min.as_vec2() * TILE_SIZE;
+    let max = bounds.max.as_vec2() * TILE_SIZE;
+    let half = view_size / 2.0;
+    let clamp_axis = |value: f32, min: f32, max: f32, half: f32| {
+        if max - min <= half * 2.0 {
+            (min + max) / 2.0
+        } else {
+            value.clamp(min + half, max - half)
+        }
+    };
+    Vec2::new(
+        clamp_axis(centre.x, min.x, max.x, half.x),
+        clamp_axis(centre.y, min.y, max.y, half.y),
+    )
+}
+
+///
+/// Smooth noise between -1 and 1, built from a few sine waves. `seed` picks a different wave for each axis.
+///
+pub fn shake_noise(seed: f32, t: f32) -> f32 {
+    ((t + seed).sin() + (t * 2.3 + seed * 1.7).sin() * 0.5 + (t * 5.1 + seed * 2.9).sin() * 0.25) / 1.75
+}
+
+///
+/// The offset and rotation of a shake with the given trauma at time `t`.
+///
+pub fn shake_at(trauma: f32, t: f32) -> (Vec2, f32) {
+    let amount = trauma.clamp(0.0, 1.0).powi(2);
+    let t = t * SHAKE_FREQUENCY;
+    let offset = Vec2::new(shake_noise(0.0, t), shake_noise(10.0, t)) * MAX_SHAKE_OFFSET * amount;
+    (offset, shake_noise(20.0, t) * MAX_SHAKE_ANGLE * amount)
+}
+
+fn remove_shake(mut query: Query<(&mut CameraShake, &mut Transform, &mut OrthographicProjection)>) {
+    for (mut shake, mut transform, mut projection) in query.iter_mut() {
+        if shake.applied_offset == Vec2::ZERO && shake.applied_angle == 0.0 && shake.applied_zoom == 0.0 {
+            continue;
+        }
+        // The offset includes keeping the view's centre in place, so this takes it back exactly.
+        transform.translation -= shake.applied_offset.extend(0.0);
+        transform.rotate_z(-shake.applied_angle);
+        projection.scale -= shake.applied_zoom;
+        shake.applied_offset = Vec2::ZERO;
+        shake.applied_angle = 0.0;
+        shake.applied_zoom = 0.0;
+    }
+}
+
+fn receive_impulses(
+    mut events: EventReader<CameraImpulse>,
+    mut query: Query<&mut CameraShake>,
+    settings: Res<Settings>,
+    preview: Option<Res<SettingsPreview>>,
+) {
+    let reduced_motion = settings.active(preview.as_deref()).accessibility.reduced_motion;
+    for event in events.read() {
+        // Reduced motion turns the camera's reactions off entirely.
+        if reduced_motion {
+            continue;
+        }
+        for mut shake in query.iter_mut() {
+            match *event {
+                CameraImpulse::Trauma(amount) => shake.trauma = (shake.trauma + amount).clamp(0.0, 1.0),
+                CameraImpulse::Punch(offset) => shake.punch += offset,
+                CameraImpulse::Zoom(amount) => shake.zoom += amount,
+            }
+        }
+    }
+}
+
+impl CameraShake {
+    ///
+    /// Fades the shake and springs back the impulses over `dt` seconds.
+    ///
+    fn decay(&mut self, dt: f32) {
+        let impulse_left = (-IMPULSE_DECAY * dt).exp();
+        self.trauma = (self.trauma - TRAUMA_DECAY * dt).max(0.0);
+        self.punch *= impulse_left;
+        self.zoom *= impulse_left;
+        if self.punch.length() < 0.1 {
+            self.punch = Vec2::ZERO;
+        }
+        if self.zoom.abs() < 0.001 {
+            self.zoom = 0.0;
+        }
+    }
+}
+
+///
+/// Where the middle of the view is, relative to the camera's position. The camera's position is the bottom
+/// left corner of the view, and the projection's area is only updated once a frame, so it is brought up to
+/// date with the current scale first.
+///
+fn view_centre(camera: &Camera, transform: &Transform, projection: &mut OrthographicProjection) -> Vec3 {
+    if let Some(size) = camera.logical_viewport_size() {
+        projection.update(size.x, size.y);
+    }
+    transform.rotation * projection.area.center().extend(0.0)
+}
+
+fn apply_shake(
+    mut query: Query<(&Camera, &mut CameraShake, &mut Transform, &mut OrthographicProjection)>,
+    settings: Res<Settings>,
+    preview: Option<Res<SettingsPreview>>,
+    time: Res<Time>,
+) {
+    let reduced_motion = settings.active(preview.as_deref()).accessibility.reduced_motion;
+    for (camera, mut shake, mut transform, mut projection) in query.iter_mut() {
+        if reduced_motion {
+            shake.trauma = 0.0;
+            shake.punch = Vec2::ZERO;
+            shake.zoom = 0.0;
+        }
+        if shake.trauma == 0.0 && shake.punch == Vec2::ZERO && shake.zoom == 0.0 {
+            continue;
+        }
+
+        let (offset, angle) = shake_at(shake.trauma, time.elapsed_seconds());
+        let zoom = shake.zoom;
+        let centre = view_centre(camera, &transform, &mut projection);
+        transform.rotate_z(angle);
+        projection.scale += zoom;
+        // Rotating and zooming turn around the camera's position, so move it to keep the centre in place.
+        let pivot = centre - view_centre(camera, &transform, &mut projection);
+        let offset = offset + shake.punch + pivot.xy();
+        transform.translation += offset.extend(0.0);
+        shake.applied_offset = offset;
+        shake.applied_angle = angle;
+        shake.applied_zoom = zoom;
+
+        shake.decay(time.delta_seconds());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn views_are_kept_inside_the_bounds() {
+        let bounds = IRect::new(0, 0, 20, 10);
+        let view = Vec2::new(8.0, 4.0) * TILE_SIZE;
+        let clamp = |centre: Vec2| clamp_to_bounds(centre * TILE_SIZE, view, bounds) / TILE_SIZE;
+        assert_eq!(clamp(Vec2::new(10.0, 5.0)), Vec2::new(10.0, 5.0));
+        assert_eq!(clamp(Vec2::new(1.0, 1.0)), Vec2::new(4.0, 2.0));
+        assert_eq!(clamp(Vec2::new(30.0, -5.0)), Vec2::new(16.0, 2.0));
+    }
+
+    #[test]
+    fn views_bigger_than_the_bounds_are_centred_on_them() {
+        let bounds = IRect::new(0, 0, 6, 10);
+        let view = Vec2::new(8.0, 4.0) * TILE_SIZE;
+        let centre = clamp_to_bounds(Vec2::new(1.0, 9.0) * TILE_SIZE, view, bounds) / TILE_SIZE;
+        // Centred across, but still clamped up and down where it fits.
+        assert_eq!(centre, Vec2::new(3.0, 8.0));
+    }
+
+    #[test]
+    fn shakes_grow_with_the_square_of_trauma() {
+        for t in [0.0, 0.3, 1.7, 12.5] {
+            assert_eq!(shake_at(0.0, t), (Vec2::ZERO, 0.0));
+            let (full_offset, full_angle) = shake_at(1.0, t);
+            let (offset, angle) = shake_at(0.5, t);
+            assert!((offset * 4.0 - full_offset).length() < 1e-4);
+            assert!((angle * 4.0 - full_angle).abs() < 1e-6);
+            assert!(full_offset.abs().max_element() <= MAX_SHAKE_OFFSET);
+            assert!(full_angle.abs() <= MAX_SHAKE_ANGLE);
+            // Trauma above 1 shakes no harder.
+            assert_eq!(shake_at(3.0, t), shake_at(1.0, t));
+        }
+    }
+
+    #[test]
+    fn shakes_die_out() {
+        let mut shake = CameraShake {
+            trauma: 1.0,
+            punch: Vec2::new(TILE_SIZE, 0.0),
+            zoom: 0.2,
+            ..default()
+        };
+        shake.decay(0.5);
+        assert!((shake.trauma - (1.0 - TRAUMA_DECAY * 0.5)).abs() < 1e-6);
+        assert!(shake.punch.x > 0.0 && shake.punch.x < TILE_SIZE);
+        assert!(shake.zoom > 0.0 && shake.zoom < 0.2);
+
+        for _ in 0..60 {
+            shake.decay(1.0 / 30.0);
+        }
+        assert_eq!(shake.trauma, 0.0);
+        assert_eq!(shake.punch, Vec2::ZERO);
+        assert_eq!(shake.zoom, 0.0);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::game::internal::{brighten, menu_text};
+use crate::game::level::{CurrentLevel, Level, CHECKPOINT};
+use crate::game::player::{GridPosition, Player, PlayerMovementSet};
+use crate::game::primary_logic::{GameData, GameState, InGameScreen};
+use crate::game::saves::{Autosave, Autosaver, SavesSet};
+use crate::game::textcolor_smoothing::{TargetSpriteColor, TargetTextColor, TextColorSmoothing};
+use crate::game::world::{tile_to_world, FogOfWar, MapLoaded, MapSet, TileVisibility, TILE_SIZE};
+use crate::utils::position_smoothing::PositionSmoothing;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+///
+/// Checkpoints placed in levels. Reaching one makes it where the player comes back to after losing all
+/// their health, and autosaves. A small indicator in the corner shows while an autosave is written.
+///
+#[derive(Default)]
+pub struct CheckpointsPlugin;
+
+#[derive(Component)]
+struct Checkpoint {
+    position: IVec2,
+}
+
+///
+/// Shown in the corner of the screen while saving.
+///
+#[derive(Component)]
+struct SavingIndicator;
+
+const CHECKPOINT_Z: f32 = 1.0;
+const CHECKPOINT_COLOR: Color = Color::srgb(0.55, 0.6, 0.7);
+const ACTIVE_COLOR: Color = Color::srgb(1.0, 1.9, 2.4);
+const INDICATOR_COLOR: Color = Color::srgb(1.2, 1.2, 1.3);
+const INDICATOR_FONT_SIZE: f32 = 14.0;
+/// Seconds the indicator stays up at least, so quick saves don't just flicker.
+const INDICATOR_TIME: f32 = 1.2;
+
+impl Plugin for CheckpointsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            spawn_checkpoints
+                .in_set(MapSet::Prepare)
+                .run_if(in_state(GameState::InGame).and_then(on_event::<MapLoaded>())),
+        );
+        app.add_systems(OnEnter(GameState::InGame), spawn_indicator);
+        app.add_systems(
+            Update,
+            reach_checkpoint
+                .after(PlayerMovementSet)
+                .before(SavesSet)
+                .run_if(in_state(InGameScreen::Playing)),
+        );
+        app.add_systems(
+            Update,
+            (update_colors, update_indicator.after(SavesSet)).run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+fn spawn_checkpoints(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    old_checkpoints: Query<Entity, With<Checkpoint>>,
+) {
+    for entity in old_checkpoints.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(level) = levels.get(&current_level.handle) else {
+        return;
+    };
+
+    for entity in level.file.entities.iter().filter(|entity| entity.kind == CHECKPOINT) {
+        let position = IVec2::from(entity.position);
+        commands.spawn((
+            StateScoped(GameState::InGame),
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(Vec2::splat(TILE_SIZE * 0.5)),
+                    ..default()
+                },
+                transform: Transform::from_translation(tile_to_world(position).extend(CHECKPOINT_Z))
+                    .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                ..default()
+            },
+            TargetSpriteColor(Color::NONE),
+            TextColorSmoothing {
+                flat: 1.0,
+                proportional: 4.0,
+            },
+            Checkpoint { position },
+        ));
+    }
+}
+
+fn spawn_indicator(mut commands: Commands) {
+    commands
+        .spawn((
+            StateScoped(GameState::InGame),
+            SavingIndicator,
+            menu_text("Saving...", Color::NONE, INDICATOR_FONT_SIZE, 98.0, 4.0, Anchor::BottomRight),
+            TargetTextColor(Color::NONE),
+            TextColorSmoothing::default(),
+        ))
+        .insert(PositionSmoothing::None);
+}
+
+fn reach_checkpoint(
+    players: Query<&GridPosition, (With<Player>, Changed<GridPosition>)>,
+    checkpoints: Query<&Checkpoint>,
+    mut game_data: ResMut<GameData>,
+    mut autosave: EventWriter<Autosave>,
+) {
+    for grid_position in players.iter() {
+        let Some(checkpoint) = checkpoints.iter().find(|c| c.position == grid_position.0) else {
+            continue;
+        };
+        // Only the first visit counts, so walking back and forth over it doesn't keep saving.
+        if game_data.respawn == Some(checkpoint.position.into()) {
+            continue;
+        }
+        info!("Reached checkpoint at {}", checkpoint.position);
+        game_data.respawn = Some(checkpoint.position.into());
+        autosave.send(Autosave);
+    }
+}
+
+///
+/// Follows the fog, lighting up the checkpoint the player would respawn at.
+///
+fn update_colors(
+    mut query: Query<(&Checkpoint, &mut TargetSpriteColor)>,
+    fog: Res<FogOfWar>,
+    game_data: Res<GameData>,
+) {
+    for (checkpoint, mut target) in query.iter_mut() {
+        let active = game_data.respawn == Some(checkpoint.position.into());
+        let color = if active { ACTIVE_COLOR } else { CHECKPOINT_COLOR };
+        let color = match fog.get(checkpoint.position) {
+            TileVisibility::Unseen => Color::NONE,
+            TileVisibility::Seen => brighten(color, 0.35),
+            TileVisibility::Visible => color,
+        };
+        if target.0 != color {
+            target.0 = color;
+        }
+    }
+}
+
+fn update_indicator(
+    autosaver: Res<Autosaver>,
+    time: Res<Time<Real>>,
+    mut remaining: Local<f32>,
+    mut query: Query<&mut TargetTextColor, With<SavingIndicator>>,
+) {
+    if autosaver.writing() {
+        *remaining = INDICATOR_TIME;
+    } else {
+        *remaining -= time.delta_seconds();
+    }
+    let color = if *remaining > 0.0 { INDICATOR_COLOR } else { Color::NONE };
+    for mut target in query.iter_mut() {
+        if target.0 != color {
+            target.0 = color;
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use crate::game::internal::{menu_button, menu_text, spawn_page_controls, ListPages, ScriptControlled, UI_Z};
+use crate::game::items::{ItemCatalog, ItemSet};
+use crate::game::level::{Level, LevelCatalog, LevelSecret, SecretKind};
+use crate::game::primary_logic::{GameData, InGameScreen};
+use crate::utils::buttoning::{ButtonClickEvent, ButtonClickType, Clickable, Hovered};
+use crate::utils::responsive::{ResponsivePosition, ResponsiveValue};
+use bevy::asset::LoadedFolder;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::Text2dBounds;
+
+///
+/// The journal of secrets, opened with C while playing.
+///
+#[derive(Default)]
+pub struct CodexPlugin;
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Component)]
+pub enum CodexButton {
+    /// Opens the page of a discovered secret.
+    Secret(String),
+    /// Turns the list to this page.
+    Page(usize),
+    Back,
+    Close,
+}
+
+///
+/// The secret whose page is open, or `None` while the list is shown.
+///
+#[derive(Resource, Default)]
+struct CodexPage(Option<String>);
+
+///
+/// The page of the list shown, when there are too many secrets to fit on one.
+///
+#[derive(Resource, Default)]
+struct CodexListPage(usize);
+
+///
+/// A row of the list.
+///
+enum CodexRow<'a> {
+    Region(&'a Level),
+    Secret(&'a Level, &'a LevelSecret),
+}
+
+///
+/// Marks everything which is rebuilt when switching between the list and a page.
+///
+#[derive(Default, Clone, Component)]
+struct CodexContent;
+
+const TITLE_COLOR: Color = Color::srgb(1.4, 2.1, 2.4);
+const REGION_COLOR: Color = Color::srgb(1.4, 1.4, 1.4);
+const SECRET_COLOR: Color = Color::srgb(2.4, 2.1, 1.4);
+const UNREAD_COLOR: Color = Color::srgb(2.8, 2.4, 1.2);
+const UNDISCOVERED_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+const CLOSE_COLOR: Color = Color::srgb(2.1, 1.4, 1.4);
+const BACKDROP_COLOR: Color = Color::srgba(0.02, 0.02, 0.03, 0.9);
+
+impl Plugin for CodexPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CodexPage>();
+        app.init_resource::<CodexListPage>();
+        app.add_systems(Update, open_codex.run_if(in_state(InGameScreen::Playing)));
+        app.add_systems(OnEnter(InGameScreen::Codex), on_enter);
+        app.add_systems(
+            Update,
+            (codex_button_clicks, close_on_key, rebuild_content)
+                .chain()
+                .run_if(in_state(InGameScreen::Codex)),
+        );
+    }
+}
+
+fn kind_label(kind: SecretKind) -> &'static str {
+    match kind {
+        SecretKind::Passage => "Hidden Passage",
+        SecretKind::BuriedItem => "Buried Item",
+        SecretKind::Lore => "Lore",
+    }
+}
+
+fn find_secret<'a>(levels: &[&'a Level], id: &str) -> Option<(&'a Level, &'a LevelSecret)> {
+    levels
+        .iter()
+        .find_map(|&level| Some((level, level.file.secrets.iter().find(|secret| secret.id == id)?)))
+}
+
+fn open_codex(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<InGameScreen>>) {
+    if keys.just_pressed(KeyCode::KeyC) {
+        next_state.set(InGameScreen::Codex);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn on_enter(
+    mut commands: Commands,
+    mut page: ResMut<CodexPage>,
+    list_page: Res<CodexListPage>,
+    catalog: Res<LevelCatalog>,
+    folders: Res<Assets<LoadedFolder>>,
+    levels: Res<Assets<Level>>,
+    item_sets: Res<Assets<ItemSet>>,
+    game_data: Res<GameData>,
+) {
+    if page.0.is_some() {
+        page.0 = None;
+    }
+
+    commands.spawn((
+        StateScoped(InGameScreen::Codex),
+        SpriteBundle {
+            sprite: Sprite {
+                color: BACKDROP_COLOR,
+                custom_size: Some(Vec2::splat(10000.0)),
+                ..default()
+            },
+            ..default()
+        },
+        ResponsivePosition::new(
+            ResponsiveValue::Percentage(50.0),
+            ResponsiveValue::Percentage(50.0),
+            ResponsiveValue::Absolute(UI_Z - 1.0),
+        ),
+    ));
+    let levels = catalog.levels(&folders, &levels);
+    spawn_content(&mut commands, &page, list_page.0, &levels, &item_sets, &game_data);
+}
+
+fn spawn_entry<'a>(commands: &'a mut Commands, bundle: impl Bundle) -> EntityCommands<'a> {
+    commands.spawn((StateScoped(InGameScreen::Codex), CodexContent, bundle))
+}
+
+fn spawn_content(
+    commands: &mut Commands,
+    page: &CodexPage,
+    list_page: usize,
+    levels: &[&Level],
+    item_sets: &Assets<ItemSet>,
+    game_data: &GameData,
+) {
+    match page.0.as_deref().and_then(|id| find_secret(levels, id)) {
+        Some((level, secret)) => spawn_page(commands, level, secret, item_sets),
+        None => spawn_list(commands, list_page, levels, game_data),
+    }
+}
+
+fn spawn_list(commands: &mut Commands, list_page: usize, levels: &[&Level], game_data: &GameData) {
+    let found = |level: &Level| {
+        level
+            .file
+            .secrets
+            .iter()
+            .filter(|secret| game_data.discovered_secrets.contains(&secret.id))
+            .count()
+    };
+    let total_found: usize = levels.iter().map(|level| found(level)).sum();
+    let total: usize = levels.iter().map(|level| level.file.secrets.len()).sum();
+
+    let title = format!("Codex   {total_found}/{total} found");
+    spawn_entry(commands, menu_text(title, TITLE_COLOR, 48.0, 50.0, 90.0, Anchor::Center));
+
+    // Regions are spaced a little further apart than the secrets in them.
+    let rows = levels.iter().flat_map(|level| {
+        let region_gap = if level.file.secrets.is_empty() { 8.0 } else { 6.0 };
+        let last = level.file.secrets.len().saturating_sub(1);
+        std::iter::once((CodexRow::Region(level), region_gap)).chain(
+            level.file.secrets.iter().enumerate().map(move |(i, secret)| {
+                (CodexRow::Secret(level, secret), if i == last { 7.0 } else { 5.0 })
+            }),
+        )
+    });
+    let pages = ListPages::new(rows);
+    let last_page = pages.last_page();
+
+    for (row, y) in pages.into_page(list_page) {
+        match row {
+            CodexRow::Region(level) => {
+                let region =
+                    format!("{}   {}/{} found", level.file.metadata.name, found(level), level.file.secrets.len());
+                spawn_entry(commands, menu_text(region, REGION_COLOR, 28.0, 20.0, y, Anchor::CenterLeft));
+            }
+            CodexRow::Secret(_, secret) if game_data.discovered_secrets.contains(&secret.id) => {
+                let unread = game_data.unread_secrets.contains(&secret.id);
+                let (label, color) = if unread {
+                    (format!("{}   new", secret.name), UNREAD_COLOR)
+                } else {
+                    (secret.name.clone(), SECRET_COLOR)
+                };
+                spawn_entry(
+                    commands,
+                    (
+                        menu_button(label, color, 24.0, 24.0, y, Anchor::CenterLeft),
+                        CodexButton::Secret(secret.id.clone()),
+                    ),
+                );
+            }
+            CodexRow::Secret(level, secret) => {
+                // Hints unlock as the rest of the region is explored.
+                let label = if !secret.hint.is_empty() && found(level) >= secret.hint_after {
+                    format!("???   {}", secret.hint)
+                } else {
+                    "???".to_string()
+                };
+                spawn_entry(commands, menu_text(label, UNDISCOVERED_COLOR, 24.0, 24.0, y, Anchor::CenterLeft));
+            }
+        }
+    }
+
+    spawn_page_controls(
+        commands,
+        (StateScoped(InGameScreen::Codex), CodexContent),
+        list_page,
+        last_page,
+        CodexButton::Page,
+    );
+    spawn_entry(
+        commands,
+        (
+            menu_button("Close", CLOSE_COLOR, 30.0, 50.0, 8.0, Anchor::Center),
+            CodexButton::Close,
+        ),
+    );
+}
+
+fn spawn_page(commands: &mut Commands, level: &Level, secret: &LevelSecret, item_sets: &Assets<ItemSet>) {
+    spawn_entry(commands, menu_text(secret.name.clone(), TITLE_COLOR, 48.0, 50.0, 84.0, Anchor::Center));
+    let subtitle = format!("{}   {}", kind_label(secret.kind), level.file.metadata.name);
+    spawn_entry(commands, menu_text(subtitle, REGION_COLOR, 24.0, 50.0, 76.0, Anchor::Center));
+    if let Some(item) = &secret.item {
+        let item = ItemCatalog::name(item_sets, item);
+        spawn_entry(commands, menu_text(format!("Found {item}"), SECRET_COLOR, 24.0, 50.0, 70.0, Anchor::Center));
+    }
+    if !secret.text.is_empty() {
+        spawn_entry(commands, menu_text(secret.text.clone(), SECRET_COLOR, 26.0, 50.0, 62.0, Anchor::TopCenter))
+            .insert(Text2dBounds {
+                size: Vec2::new(800.0, f32::INFINITY),
+            });
+    }
+
+    spawn_entry(
+        commands,
+        (
+            menu_button("Back", CLOSE_COLOR, 30.0, 50.0, 8.0, Anchor::Center),
+            CodexButton::Back,
+        ),
+    );
+}
+
+#[allow(clippy::type_complexity)]
+fn codex_button_clicks(
+    query: Query<&CodexButton, (With<Hovered>, With<Clickable>, Without<ScriptControlled>)>,
+    mut event_reader: EventReader<ButtonClickEvent>,
+    mut page: ResMut<CodexPage>,
+    mut list_page: ResMut<CodexListPage>,
+    mut game_data: ResMut<GameData>,
+    mut next_state: ResMut<NextState<InGameScreen>>,
+) {
+    for event in event_reader.read() {
+        if event.mouse_button != MouseButton::Left || event.click_type != ButtonClickType::Pressed {
+            continue;
+        }
+
+        if let Ok(button) = query.get(event.entity) {
+            match button {
+                CodexButton::Secret(id) => {
+                    game_data.unread_secrets.remove(id);
+                    page.0 = Some(id.clone());
+                }
+                CodexButton::Page(number) => list_page.0 = *number,
+                CodexButton::Back => page.0 = None,
+                CodexButton::Close => next_state.set(InGameScreen::Playing),
+            }
+        }
+    }
+}
+
+fn close_on_key(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut page: ResMut<CodexPage>,
+    mut next_state: ResMut<NextState<InGameScreen>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) && page.0.is_some() {
+        page.0 = None;
+    } else if keys.any_just_pressed([KeyCode::Escape, KeyCode::KeyC]) {
+        next_state.set(InGameScreen::Playing);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn rebuild_content(
+    mut commands: Commands,
+    page: Res<CodexPage>,
+    list_page: Res<CodexListPage>,
+    catalog: Res<LevelCatalog>,
+    folders: Res<Assets<LoadedFolder>>,
+    levels: Res<Assets<Level>>,
+    item_sets: Res<Assets<ItemSet>>,
+    game_data: Res<GameData>,
+    content: Query<Entity, With<CodexContent>>,
+) {
+    let changed = (page.is_changed() && !page.is_added()) || (list_page.is_changed() && !list_page.is_added());
+    if !changed {
+        return;
+    }
+    for entity in content.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let levels = catalog.levels(&folders, &levels);
+    spawn_content(&mut commands, &page, list_page.0, &levels, &item_sets, &game_data);
+}
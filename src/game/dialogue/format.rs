@@ -0,0 +1,171 @@
+use crate::game::primary_logic::GameData;
+use crate::utils::ron_asset::RonParseError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+///
+/// Version of the dialogue format this build reads.
+///
+pub const DIALOGUE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum DialogueError {
+    #[error("could not read dialogue: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] RonParseError),
+    #[error("dialogue has format version {0}, but this build only supports up to {DIALOGUE_FORMAT_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("{from} refers to \"{target}\", which does not exist")]
+    MissingReference { from: String, target: String },
+}
+
+///
+/// A conversation as written in a `.dialogue.ron` file: a tree (or graph) of nodes starting at `start`.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DialogueFile {
+    pub version: u32,
+    pub id: String,
+    #[serde(default)]
+    pub speakers: BTreeMap<String, Speaker>,
+    pub start: String,
+    pub nodes: BTreeMap<String, DialogueNode>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Speaker {
+    pub name: String,
+    /// Image shown next to the speaker's lines, relative to the assets folder.
+    pub portrait: Option<String>,
+    /// Color of the speaker's name, in linear RGB. May go above 1 to glow.
+    pub color: Option<[f32; 3]>,
+}
+
+///
+/// One line of dialogue and what can be said back to it.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DialogueNode {
+    /// Key into [DialogueFile::speakers], or `None` for narration.
+    #[serde(default)]
+    pub speaker: Option<String>,
+    pub text: String,
+    /// Applied when the node is reached.
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+    /// Where to go when there are no choices to make. `None` ends the conversation.
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DialogueChoice {
+    pub text: String,
+    /// The choice is only offered while this holds.
+    #[serde(default)]
+    pub condition: DialogueCondition,
+    /// Applied when the choice is picked.
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+    /// `None` ends the conversation.
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum DialogueCondition {
+    #[default]
+    Always,
+    Flag(String),
+    NotFlag(String),
+    HasItem(String),
+    All(Vec<DialogueCondition>),
+    Any(Vec<DialogueCondition>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DialogueEffect {
+    SetFlag(String),
+    ClearFlag(String),
+    GiveItem(String),
+    /// Takes one of the item, if the player has any.
+    TakeItem(String),
+}
+
+impl DialogueCondition {
+    pub fn check(&self, game_data: &GameData) -> bool {
+        match self {
+            DialogueCondition::Always => true,
+            DialogueCondition::Flag(flag) => game_data.flags.contains(flag),
+            DialogueCondition::NotFlag(flag) => !game_data.flags.contains(flag),
+            DialogueCondition::HasItem(item) => game_data.has_item(item),
+            DialogueCondition::All(conditions) => conditions.iter().all(|condition| condition.check(game_data)),
+            DialogueCondition::Any(conditions) => conditions.iter().any(|condition| condition.check(game_data)),
+        }
+    }
+}
+
+impl DialogueEffect {
+    pub fn apply(&self, game_data: &mut GameData) {
+        match self {
+            DialogueEffect::SetFlag(flag) => {
+                game_data.flags.insert(flag.clone());
+            }
+            DialogueEffect::ClearFlag(flag) => {
+                game_data.flags.remove(flag);
+            }
+            DialogueEffect::GiveItem(item) => game_data.give_item(item),
+            DialogueEffect::TakeItem(item) => {
+                game_data.take_item(item);
+            }
+        }
+    }
+}
+
+impl DialogueFile {
+    ///
+    /// Checks the version and that every node and speaker referred to exists.
+    ///
+    pub fn validate(&self) -> Result<(), DialogueError> {
+        if self.version > DIALOGUE_FORMAT_VERSION {
+            return Err(DialogueError::UnsupportedVersion(self.version));
+        }
+
+        let node_exists = |from: &dyn Fn() -> String, target: &String| {
+            if self.nodes.contains_key(target) {
+                Ok(())
+            } else {
+                Err(DialogueError::MissingReference {
+                    from: from(),
+                    target: target.clone(),
+                })
+            }
+        };
+
+        node_exists(&|| "start".to_string(), &self.start)?;
+        for (id, node) in &self.nodes {
+            if let Some(speaker) = &node.speaker {
+                if !self.speakers.contains_key(speaker) {
+                    return Err(DialogueError::MissingReference {
+                        from: format!("node \"{id}\""),
+                        target: speaker.clone(),
+                    });
+                }
+            }
+            if let Some(next) = &node.next {
+                node_exists(&|| format!("node \"{id}\""), next)?;
+            }
+            for (i, choice) in node.choices.iter().enumerate() {
+                if let Some(next) = &choice.next {
+                    node_exists(&|| format!("choice {} of node \"{id}\"", i + 1), next)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
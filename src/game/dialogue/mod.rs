@@ -0,0 +1,376 @@
+use crate::game::internal::{menu_button, menu_text, ScriptControlled, UI_Z};
+use crate::game::primary_logic::{GameData, InGameScreen};
+use crate::game::saves::SaveGame;
+use crate::utils::buttoning::{ButtonClickEvent, ButtonClickType, Clickable, Hovered};
+use crate::utils::position_smoothing::PositionSmoothing;
+use crate::utils::responsive::{ResponsivePosition, ResponsiveValue};
+use crate::utils::ron_asset::{RonAsset, RonLoader};
+use bevy::asset::LoadedFolder;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::Text2dBounds;
+
+mod format;
+mod runner;
+
+pub use format::*;
+pub use runner::{typewriter_prefix, DialogueRunner};
+
+///
+/// Conversations loaded from `.dialogue.ron` files in `assets/dialogue`, shown over the world while
+/// [InGameScreen::Dialogue].
+///
+#[derive(Default)]
+pub struct DialoguePlugin;
+
+///
+/// A validated conversation.
+///
+#[derive(Asset, TypePath, Debug)]
+pub struct Dialogue {
+    pub file: DialogueFile,
+}
+
+impl RonAsset for Dialogue {
+    type File = DialogueFile;
+    type Error = DialogueError;
+
+    const EXTENSION: &'static str = "dialogue.ron";
+
+    fn from_file(file: DialogueFile) -> Result<Dialogue, DialogueError> {
+        file.validate()?;
+        Ok(Dialogue { file })
+    }
+}
+
+///
+/// Keeps every dialogue in `assets/dialogue` loaded, so they can be started by id.
+///
+#[derive(Resource)]
+pub struct DialogueCatalog {
+    _folder: Handle<LoadedFolder>,
+}
+
+impl DialogueCatalog {
+    pub fn find<'a>(dialogues: &'a Assets<Dialogue>, id: &str) -> Option<(AssetId<Dialogue>, &'a Dialogue)> {
+        dialogues.iter().find(|(_, dialogue)| dialogue.file.id == id)
+    }
+}
+
+///
+/// Starts the conversation with this id, if nothing else is being shown.
+///
+#[derive(Event, Clone, Debug)]
+pub struct StartDialogue {
+    pub id: String,
+}
+
+///
+/// The conversation being shown.
+///
+#[derive(Resource)]
+struct ActiveDialogue {
+    dialogue: AssetId<Dialogue>,
+    runner: DialogueRunner,
+    /// The node the view was last built for.
+    built: Option<String>,
+    /// Characters of the current line revealed so far.
+    shown: f32,
+    choices_shown: bool,
+}
+
+///
+/// Marks everything which is rebuilt for each node.
+///
+#[derive(Default, Component)]
+struct DialogueContent;
+
+///
+/// The text revealed by the typewriter.
+///
+#[derive(Component)]
+struct DialogueText(String);
+
+///
+/// A choice button, by the choice's index in the node.
+///
+#[derive(Component)]
+struct DialogueChoiceButton(usize);
+
+/// Characters revealed per second.
+const TYPEWRITER_SPEED: f32 = 40.0;
+
+const NARRATOR_COLOR: Color = Color::srgb(1.4, 1.4, 1.4);
+const TEXT_COLOR: Color = Color::srgb(2.1, 2.1, 2.1);
+const CHOICE_COLOR: Color = Color::srgb(1.4, 2.1, 2.4);
+const CONTINUE_COLOR: Color = Color::srgb(0.8, 0.8, 0.85);
+const BACKDROP_COLOR: Color = Color::srgba(0.02, 0.02, 0.03, 0.9);
+const PORTRAIT_SIZE: f32 = 64.0;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Dialogue>();
+        app.init_asset_loader::<RonLoader<Dialogue>>();
+        app.add_event::<StartDialogue>();
+        app.add_systems(Startup, load_catalog);
+        app.add_systems(
+            Update,
+            start_dialogue.run_if(in_state(InGameScreen::Playing).and_then(on_event::<StartDialogue>())),
+        );
+        app.add_systems(OnEnter(InGameScreen::Dialogue), spawn_backdrop);
+        app.add_systems(OnExit(InGameScreen::Dialogue), |mut commands: Commands| {
+            commands.remove_resource::<ActiveDialogue>();
+        });
+        app.add_systems(
+            Update,
+            (dialogue_input, choice_clicks, rebuild_view, run_typewriter, show_choices, end_dialogue)
+                .chain()
+                .run_if(in_state(InGameScreen::Dialogue).and_then(resource_exists::<ActiveDialogue>)),
+        );
+    }
+}
+
+fn load_catalog(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(DialogueCatalog {
+        _folder: asset_server.load_folder("dialogue"),
+    });
+}
+
+fn start_dialogue(
+    mut commands: Commands,
+    mut events: EventReader<StartDialogue>,
+    dialogues: Res<Assets<Dialogue>>,
+    mut game_data: ResMut<GameData>,
+    mut next_state: ResMut<NextState<InGameScreen>>,
+) {
+    // Only one conversation at a time.
+    let Some(event) = events.read().last() else {
+        return;
+    };
+    let Some((id, dialogue)) = DialogueCatalog::find(&dialogues, &event.id) else {
+        warn!("No dialogue with id \"{}\"", event.id);
+        return;
+    };
+
+    commands.insert_resource(ActiveDialogue {
+        dialogue: id,
+        runner: DialogueRunner::new(&dialogue.file, &mut game_data),
+        built: None,
+        shown: 0.0,
+        choices_shown: false,
+    });
+    next_state.set(InGameScreen::Dialogue);
+}
+
+fn spawn_backdrop(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(InGameScreen::Dialogue),
+        SpriteBundle {
+            sprite: Sprite {
+                color: BACKDROP_COLOR,
+                custom_size: Some(Vec2::new(10000.0, 150.0)),
+                ..default()
+            },
+            ..default()
+        },
+        ResponsivePosition::new(
+            ResponsiveValue::Percentage(50.0),
+            ResponsiveValue::Percentage(14.0),
+            ResponsiveValue::Absolute(UI_Z - 1.0),
+        ),
+    ));
+}
+
+///
+/// Spawns a piece of the view which sits still on the screen instead of gliding in like the menus.
+///
+fn spawn_entry<'a>(commands: &'a mut Commands, bundle: impl Bundle) -> EntityCommands<'a> {
+    let mut entity = commands.spawn((StateScoped(InGameScreen::Dialogue), DialogueContent, bundle));
+    entity.insert(PositionSmoothing::None);
+    entity
+}
+
+fn is_typed_out(active: &ActiveDialogue, text: &str) -> bool {
+    active.shown as usize >= text.chars().count()
+}
+
+///
+/// Continue skips the typewriter, then moves on from a line with nothing to choose. Clicking anywhere but
+/// on a choice continues too, choices are picked by [choice_clicks].
+///
+#[allow(clippy::too_many_arguments)]
+fn dialogue_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    hovered_choices: Query<(), (With<DialogueChoiceButton>, With<Hovered>)>,
+    mut active: ResMut<ActiveDialogue>,
+    dialogues: Res<Assets<Dialogue>>,
+    mut game_data: ResMut<GameData>,
+) {
+    let pressed = keys.any_just_pressed([KeyCode::Space, KeyCode::Enter, KeyCode::KeyE])
+        || (mouse_buttons.just_pressed(MouseButton::Left) && hovered_choices.is_empty())
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South)));
+    if !pressed {
+        return;
+    }
+    let Some(dialogue) = dialogues.get(active.dialogue) else {
+        return;
+    };
+    let Some(line) = active.runner.line(&dialogue.file) else {
+        return;
+    };
+
+    if !is_typed_out(&active, line.text) {
+        active.shown = line.text.chars().count() as f32;
+    } else {
+        active.runner.advance(&dialogue.file, &mut game_data);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn choice_clicks(
+    query: Query<&DialogueChoiceButton, (With<Hovered>, With<Clickable>, Without<ScriptControlled>)>,
+    mut event_reader: EventReader<ButtonClickEvent>,
+    mut active: ResMut<ActiveDialogue>,
+    dialogues: Res<Assets<Dialogue>>,
+    mut game_data: ResMut<GameData>,
+) {
+    let Some(dialogue) = dialogues.get(active.dialogue) else {
+        return;
+    };
+    for event in event_reader.read() {
+        if event.mouse_button != MouseButton::Left || event.click_type != ButtonClickType::Pressed {
+            continue;
+        }
+        if let Ok(DialogueChoiceButton(index)) = query.get(event.entity) {
+            active.runner.choose(&dialogue.file, &mut game_data, *index);
+            // One choice per frame, the rest were for buttons which are about to go.
+            break;
+        }
+    }
+}
+
+fn rebuild_view(
+    mut commands: Commands,
+    mut active: ResMut<ActiveDialogue>,
+    dialogues: Res<Assets<Dialogue>>,
+    asset_server: Res<AssetServer>,
+    content: Query<Entity, With<DialogueContent>>,
+) {
+    if active.built.as_deref() == active.runner.node_id() {
+        return;
+    }
+    active.built = active.runner.node_id().map(str::to_string);
+    active.shown = 0.0;
+    active.choices_shown = false;
+    for entity in content.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(line) = dialogues.get(active.dialogue).and_then(|dialogue| active.runner.line(&dialogue.file)) else {
+        return;
+    };
+
+    let mut text_x = 6.0;
+    if let Some(portrait) = line.speaker.and_then(|speaker| speaker.portrait.as_ref()) {
+        spawn_entry(
+            &mut commands,
+            (
+                SpriteBundle {
+                    texture: asset_server.load(portrait.clone()),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(PORTRAIT_SIZE)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ResponsivePosition::new(
+                    ResponsiveValue::Percentage(6.0),
+                    ResponsiveValue::Percentage(16.0),
+                    ResponsiveValue::Absolute(UI_Z),
+                ),
+            ),
+        );
+        text_x = 14.0;
+    }
+
+    if let Some(speaker) = line.speaker {
+        let color = speaker.color.map_or(NARRATOR_COLOR, |[r, g, b]| Color::srgb(r, g, b));
+        spawn_entry(&mut commands, menu_text(speaker.name.clone(), color, 18.0, text_x, 27.0, Anchor::CenterLeft));
+    }
+    spawn_entry(&mut commands, menu_text("", TEXT_COLOR, 14.0, text_x, 23.0, Anchor::TopLeft)).insert((
+        DialogueText(line.text.to_string()),
+        Text2dBounds {
+            size: Vec2::new(380.0, f32::INFINITY),
+        },
+    ));
+}
+
+fn run_typewriter(
+    mut active: ResMut<ActiveDialogue>,
+    mut query: Query<(&DialogueText, &mut Text)>,
+    time: Res<Time>,
+) {
+    for (DialogueText(full), mut text) in query.iter_mut() {
+        if !is_typed_out(&active, full) {
+            active.shown += TYPEWRITER_SPEED * time.delta_seconds();
+        }
+        let shown = typewriter_prefix(full, active.shown as usize);
+        if text.sections[0].value != shown {
+            text.sections[0].value = shown.to_string();
+        }
+    }
+}
+
+fn show_choices(
+    mut commands: Commands,
+    mut active: ResMut<ActiveDialogue>,
+    dialogues: Res<Assets<Dialogue>>,
+    game_data: Res<GameData>,
+) {
+    if active.choices_shown {
+        return;
+    }
+    let Some(dialogue) = dialogues.get(active.dialogue) else {
+        return;
+    };
+    let Some(line) = active.runner.line(&dialogue.file) else {
+        return;
+    };
+    if !is_typed_out(&active, line.text) {
+        return;
+    }
+    active.choices_shown = true;
+
+    let choices = active.runner.choices(&dialogue.file, &game_data);
+    if choices.is_empty() {
+        spawn_entry(&mut commands, menu_text("Continue", CONTINUE_COLOR, 12.0, 94.0, 4.0, Anchor::CenterRight));
+    }
+    for (row, (index, text)) in choices.into_iter().enumerate() {
+        let y = 26.0 - row as f32 * 5.0;
+        spawn_entry(
+            &mut commands,
+            (
+                menu_button(text, CHOICE_COLOR, 14.0, 62.0, y, Anchor::CenterLeft),
+                DialogueChoiceButton(index),
+            ),
+        );
+    }
+}
+
+fn end_dialogue(
+    active: Res<ActiveDialogue>,
+    mut next_state: ResMut<NextState<InGameScreen>>,
+    mut save: EventWriter<SaveGame>,
+) {
+    if active.runner.is_finished() {
+        next_state.set(InGameScreen::Playing);
+        // Effects may have changed the game.
+        save.send(SaveGame);
+    }
+}
@@ -0,0 +1,251 @@
+use super::format::{DialogueFile, DialogueNode, Speaker};
+use crate::game::primary_logic::GameData;
+
+///
+/// Steps through a [DialogueFile], applying effects to [GameData] as nodes are reached and choices made.
+///
+/// This knows nothing about the ECS, so a conversation can be played through by calling
+/// [DialogueRunner::advance] and [DialogueRunner::choose] directly.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialogueRunner {
+    node: Option<String>,
+}
+
+///
+/// What is being said at the current node.
+///
+#[derive(Clone, Debug)]
+pub struct DialogueLine<'a> {
+    pub speaker: Option<&'a Speaker>,
+    pub text: &'a str,
+}
+
+impl DialogueRunner {
+    ///
+    /// Starts the conversation at the file's start node.
+    ///
+    pub fn new(file: &DialogueFile, game_data: &mut GameData) -> DialogueRunner {
+        let mut runner = DialogueRunner { node: None };
+        runner.enter(file, game_data, Some(file.start.clone()));
+        runner
+    }
+
+    fn enter(&mut self, file: &DialogueFile, game_data: &mut GameData, node: Option<String>) {
+        self.node = node.filter(|id| file.nodes.contains_key(id));
+        if let Some(node) = self.node(file) {
+            for effect in &node.effects {
+                effect.apply(game_data);
+            }
+        }
+    }
+
+    pub fn node_id(&self) -> Option<&str> {
+        self.node.as_deref()
+    }
+
+    pub fn node<'a>(&self, file: &'a DialogueFile) -> Option<&'a DialogueNode> {
+        self.node.as_ref().and_then(|id| file.nodes.get(id))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.node.is_none()
+    }
+
+    pub fn line<'a>(&self, file: &'a DialogueFile) -> Option<DialogueLine<'a>> {
+        let node = self.node(file)?;
+        Some(DialogueLine {
+            speaker: node.speaker.as_ref().and_then(|speaker| file.speakers.get(speaker)),
+            text: &node.text,
+        })
+    }
+
+    ///
+    /// The choices on offer, by their index in the node, and their text. Choices whose condition
+    /// doesn't hold are left out.
+    ///
+    pub fn choices<'a>(&self, file: &'a DialogueFile, game_data: &GameData) -> Vec<(usize, &'a str)> {
+        self.node(file)
+            .into_iter()
+            .flat_map(|node| node.choices.iter().enumerate())
+            .filter(|(_, choice)| choice.condition.check(game_data))
+            .map(|(i, choice)| (i, choice.text.as_str()))
+            .collect()
+    }
+
+    ///
+    /// Moves on from a node with nothing to choose. Does nothing while there are choices on offer.
+    ///
+    pub fn advance(&mut self, file: &DialogueFile, game_data: &mut GameData) {
+        if !self.choices(file, game_data).is_empty() {
+            return;
+        }
+        let next = self.node(file).and_then(|node| node.next.clone());
+        self.enter(file, game_data, next);
+    }
+
+    ///
+    /// Picks the choice at `index` in the current node. Returns `false`, changing nothing, if that
+    /// choice isn't on offer.
+    ///
+    pub fn choose(&mut self, file: &DialogueFile, game_data: &mut GameData, index: usize) -> bool {
+        let Some(choice) = self.node(file).and_then(|node| node.choices.get(index)) else {
+            return false;
+        };
+        if !choice.condition.check(game_data) {
+            return false;
+        }
+        for effect in &choice.effects {
+            effect.apply(game_data);
+        }
+        self.enter(file, game_data, choice.next.clone());
+        true
+    }
+}
+
+///
+/// The first `shown` characters of `text`, for revealing it a character at a time.
+///
+pub fn typewriter_prefix(text: &str, shown: usize) -> &str {
+    match text.char_indices().nth(shown) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ron_asset::parse_ron;
+
+    const DIALOGUE: &str = r#"(
+        version: 1,
+        id: "guide",
+        speakers: { "guide": (name: "Guide") },
+        start: "hello",
+        nodes: {
+            "hello": (
+                speaker: Some("guide"),
+                text: "Hello.",
+                effects: [SetFlag("met_guide")],
+                next: Some("ask"),
+            ),
+            "ask": (
+                text: "What now?",
+                choices: [
+                    (
+                        text: "Take the lamp.",
+                        condition: NotFlag("has_lamp"),
+                        effects: [GiveItem("lamp"), SetFlag("has_lamp")],
+                        next: Some("ask"),
+                    ),
+                    (text: "Light the way.", condition: HasItem("lamp"), effects: [TakeItem("lamp")], next: Some("lit")),
+                    (text: "Leave."),
+                ],
+            ),
+            "lit": (
+                text: "The path glows.",
+            ),
+        },
+    )"#;
+
+    fn dialogue() -> DialogueFile {
+        let file = parse_ron::<DialogueFile>(DIALOGUE).unwrap();
+        file.validate().unwrap();
+        file
+    }
+
+    fn texts<'a>(choices: &[(usize, &'a str)]) -> Vec<&'a str> {
+        choices.iter().map(|(_, text)| *text).collect()
+    }
+
+    #[test]
+    fn starting_applies_the_first_nodes_effects() {
+        let file = dialogue();
+        let mut game_data = GameData::default();
+        let runner = DialogueRunner::new(&file, &mut game_data);
+        assert_eq!(runner.node_id(), Some("hello"));
+        assert_eq!(runner.line(&file).unwrap().speaker.unwrap().name, "Guide");
+        assert!(game_data.flags.contains("met_guide"));
+    }
+
+    #[test]
+    fn conditions_hide_choices() {
+        let file = dialogue();
+        let mut game_data = GameData::default();
+        let mut runner = DialogueRunner::new(&file, &mut game_data);
+        runner.advance(&file, &mut game_data);
+        assert_eq!(runner.node_id(), Some("ask"));
+        assert_eq!(texts(&runner.choices(&file, &game_data)), ["Take the lamp.", "Leave."]);
+
+        assert!(runner.choose(&file, &mut game_data, 0));
+        assert_eq!(runner.node_id(), Some("ask"));
+        let choices = runner.choices(&file, &game_data);
+        assert_eq!(texts(&choices), ["Light the way.", "Leave."]);
+        // Indices are those in the node, not in the list on offer.
+        assert_eq!(choices[0].0, 1);
+    }
+
+    #[test]
+    fn choices_apply_their_effects() {
+        let file = dialogue();
+        let mut game_data = GameData::default();
+        let mut runner = DialogueRunner::new(&file, &mut game_data);
+        runner.advance(&file, &mut game_data);
+        runner.choose(&file, &mut game_data, 0);
+        assert!(game_data.has_item("lamp"));
+        assert!(game_data.flags.contains("has_lamp"));
+
+        runner.choose(&file, &mut game_data, 1);
+        assert_eq!(runner.node_id(), Some("lit"));
+        assert!(!game_data.has_item("lamp"));
+    }
+
+    #[test]
+    fn choices_not_on_offer_change_nothing() {
+        let file = dialogue();
+        let mut game_data = GameData::default();
+        let mut runner = DialogueRunner::new(&file, &mut game_data);
+        runner.advance(&file, &mut game_data);
+        let before = game_data.clone();
+
+        // Hidden by its condition, and past the end of the node.
+        assert!(!runner.choose(&file, &mut game_data, 1));
+        assert!(!runner.choose(&file, &mut game_data, 3));
+        assert_eq!(runner.node_id(), Some("ask"));
+        assert_eq!(game_data, before);
+
+        // Advancing does nothing while there is something to choose.
+        runner.advance(&file, &mut game_data);
+        assert_eq!(runner.node_id(), Some("ask"));
+    }
+
+    #[test]
+    fn conversations_finish() {
+        let file = dialogue();
+        let mut game_data = GameData::default();
+
+        let mut runner = DialogueRunner::new(&file, &mut game_data);
+        runner.advance(&file, &mut game_data);
+        assert!(runner.choose(&file, &mut game_data, 2));
+        assert!(runner.is_finished());
+        assert!(runner.line(&file).is_none());
+        assert!(runner.choices(&file, &game_data).is_empty());
+
+        // A node without `next` or choices ends it on the next advance.
+        let mut runner = DialogueRunner::new(&file, &mut game_data);
+        runner.advance(&file, &mut game_data);
+        runner.choose(&file, &mut game_data, 0);
+        runner.choose(&file, &mut game_data, 1);
+        assert!(!runner.is_finished());
+        runner.advance(&file, &mut game_data);
+        assert!(runner.is_finished());
+    }
+
+    #[test]
+    fn typewriter_counts_characters_not_bytes() {
+        assert_eq!(typewriter_prefix("héllo", 2), "hé");
+        assert_eq!(typewriter_prefix("héllo", 0), "");
+        assert_eq!(typewriter_prefix("héllo", 99), "héllo");
+    }
+}
@@ -0,0 +1,64 @@
+use crate::game::player::{GridPosition, Player, PlayerMovementSet};
+use crate::game::primary_logic::{GameData, GameState};
+use crate::game::world::visibility::visible_tiles;
+use crate::game::world::{CurrentMap, FogOfWar, MapLoaded, MapSet};
+use bevy::prelude::*;
+
+///
+/// Uncovers the map around the player as they explore, remembering what they have seen in the save.
+///
+#[derive(Default)]
+pub struct FogPlugin;
+
+///
+/// How far the player can see, in tiles.
+///
+pub const SIGHT_RADIUS: i32 = 6;
+
+impl Plugin for FogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                load_explored.in_set(MapSet::Prepare).run_if(on_event::<MapLoaded>()),
+                update_fog.after(PlayerMovementSet).before(MapSet::Render),
+            )
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+fn load_explored(mut fog: ResMut<FogOfWar>, game_data: Res<GameData>) {
+    let seen = game_data.explored.get(&game_data.level).into_iter().flatten();
+    *fog = FogOfWar {
+        seen: seen.map(|position| IVec2::from(*position)).collect(),
+        visible: default(),
+    };
+}
+
+fn update_fog(
+    players: Query<Ref<GridPosition>, With<Player>>,
+    current_map: Res<CurrentMap>,
+    mut fog: ResMut<FogOfWar>,
+    mut game_data: ResMut<GameData>,
+) {
+    for grid_position in players.iter() {
+        // Sight only changes when the player moves or the map does, e.g. when a passage opens.
+        if !grid_position.is_changed() && !current_map.is_changed() {
+            continue;
+        }
+        let visible = visible_tiles(&current_map.map, grid_position.0, SIGHT_RADIUS);
+        if visible == fog.visible {
+            continue;
+        }
+
+        let level = game_data.level.clone();
+        let explored = game_data.explored.entry(level).or_default();
+        for position in &visible {
+            if fog.seen.insert(*position) {
+                explored.insert((*position).into());
+            }
+        }
+        fog.visible = visible;
+    }
+}
@@ -0,0 +1,439 @@
+use crate::game::world::visibility::line_of_sight;
+use crate::game::world::{find_path, TileMap};
+use bevy::math::IVec2;
+
+/// Seconds a guardian stands startled before it gives chase.
+const ALERT_TIME: f32 = 0.6;
+/// Seconds a guardian looks around where it lost the player, or after catching them.
+const SEARCH_TIME: f32 = 3.0;
+/// Seconds between turns while looking around.
+const LOOK_TIME: f32 = 0.75;
+/// Seconds a guardian waits at each patrol point.
+const PATROL_PAUSE: f32 = 1.0;
+/// Seconds a guardian waits before trying again when its way is blocked.
+const BLOCKED_WAIT: f32 = 0.5;
+
+/// Tiles per second while patrolling.
+pub const PATROL_SPEED: f32 = 2.0;
+/// Tiles per second while chasing, a little slower than the player.
+pub const CHASE_SPEED: f32 = 3.5;
+
+///
+/// Whether `target` is within `sight` tiles ahead of something at `position` looking towards `facing`,
+/// and not behind a wall.
+///
+pub fn in_vision_cone(map: &TileMap, position: IVec2, facing: IVec2, target: IVec2, sight: i32) -> bool {
+    let offset = target - position;
+    if offset == IVec2::ZERO {
+        return true;
+    }
+    // Within 45 degrees to either side of `facing`, worked out without square roots so tiles right on the
+    // edge of the cone are always in it.
+    let ahead = offset.dot(facing);
+    offset.length_squared() <= sight * sight
+        && ahead >= 0
+        && 2 * ahead * ahead >= offset.length_squared()
+        && line_of_sight(map, position, target)
+}
+
+///
+/// Every tile in the vision cone, for drawing it.
+///
+pub fn vision_cone(map: &TileMap, position: IVec2, facing: IVec2, sight: i32) -> Vec<IVec2> {
+    let mut tiles = Vec::new();
+    for y in -sight..=sight {
+        for x in -sight..=sight {
+            let tile = position + IVec2::new(x, y);
+            if map.get(tile).is_some() && in_vision_cone(map, position, facing, tile, sight) {
+                tiles.push(tile);
+            }
+        }
+    }
+    tiles
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GuardianState {
+    /// Walking to patrol point `point`.
+    Patrol { point: usize },
+    /// Has just seen the player, and is about to chase them to where they were last seen.
+    Alerted { time: f32, last_seen: IVec2 },
+    /// Running to where the player was last seen.
+    Chase { last_seen: IVec2 },
+    /// Looking around after losing or catching the player, before going back to patrolling.
+    Search { time: f32 },
+}
+
+///
+/// What a guardian did in an update.
+///
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GuardianAction {
+    Wait,
+    /// Stepped onto a neighbouring tile.
+    Step(IVec2),
+    /// Spotted the player.
+    Spotted,
+    /// Reached the player.
+    Caught,
+}
+
+///
+/// A guardian's state machine. It knows nothing about the ECS, so it runs the same in headless tests.
+///
+#[derive(Clone, Debug)]
+pub struct Guardian {
+    pub state: GuardianState,
+    pub position: IVec2,
+    /// One tile long, in the direction it is looking.
+    pub facing: IVec2,
+    pub patrol: Vec<IVec2>,
+    pub sight: i32,
+    /// Tiles left to walk, the next step first.
+    path: Vec<IVec2>,
+    /// Seconds until it next moves or turns.
+    wait: f32,
+}
+
+impl Guardian {
+    pub fn new(position: IVec2, facing: IVec2, patrol: Vec<IVec2>, sight: i32) -> Self {
+        Self {
+            state: GuardianState::Patrol { point: 0 },
+            position,
+            facing,
+            patrol,
+            sight,
+            path: Vec::new(),
+            wait: 0.0,
+        }
+    }
+
+    pub fn sees(&self, map: &TileMap, player: IVec2) -> bool {
+        in_vision_cone(map, self.position, self.facing, player, self.sight)
+    }
+
+    ///
+    /// Seconds a step takes in the current state.
+    ///
+    pub fn step_time(&self) -> f32 {
+        match self.state {
+            GuardianState::Chase { .. } => 1.0 / CHASE_SPEED,
+            _ => 1.0 / PATROL_SPEED,
+        }
+    }
+
+    ///
+    /// Goes back to patrolling, e.g. after the player was sent back to a checkpoint.
+    ///
+    pub fn reset(&mut self) {
+        self.state = GuardianState::Patrol { point: self.nearest_point() };
+        self.path.clear();
+        self.wait = 0.0;
+    }
+
+    fn nearest_point(&self) -> usize {
+        (0..self.patrol.len())
+            .min_by_key(|i| (self.patrol[*i] - self.position).abs().element_sum())
+            .unwrap_or(0)
+    }
+
+    ///
+    /// Runs the state machine for `delta` seconds. `player` is the player's tile, or `None` if there is
+    /// no player to react to.
+    ///
+    pub fn update(&mut self, map: &TileMap, player: Option<IVec2>, delta: f32) -> GuardianAction {
+        let seen = player.filter(|player| self.sees(map, *player));
+        self.wait -= delta;
+
+        if let (GuardianState::Patrol { .. } | GuardianState::Search { .. }, Some(player)) = (self.state, seen) {
+            self.state = GuardianState::Alerted {
+                time: ALERT_TIME,
+                last_seen: player,
+            };
+            self.path.clear();
+            return GuardianAction::Spotted;
+        }
+
+        match self.state {
+            GuardianState::Alerted { time, last_seen } => {
+                if let Some(player) = seen {
+                    self.face(player);
+                }
+                let time = time - delta;
+                let last_seen = seen.unwrap_or(last_seen);
+                self.state = if time > 0.0 {
+                    GuardianState::Alerted { time, last_seen }
+                } else {
+                    GuardianState::Chase { last_seen }
+                };
+                return GuardianAction::Wait;
+            }
+            GuardianState::Chase { last_seen } => {
+                let last_seen = seen.unwrap_or(last_seen);
+                if let Some(player) = player.filter(|player| (*player - self.position).abs().element_sum() <= 1) {
+                    self.face(player);
+                    self.state = GuardianState::Search { time: SEARCH_TIME };
+                    self.wait = LOOK_TIME;
+                    return GuardianAction::Caught;
+                }
+                self.state = if self.position == last_seen {
+                    GuardianState::Search { time: SEARCH_TIME }
+                } else {
+                    GuardianState::Chase { last_seen }
+                };
+            }
+            GuardianState::Search { time } => {
+                let time = time - delta;
+                self.state = if time > 0.0 {
+                    GuardianState::Search { time }
+                } else {
+                    GuardianState::Patrol { point: self.nearest_point() }
+                };
+            }
+            GuardianState::Patrol { .. } => {}
+        }
+
+        if self.wait > 0.0 {
+            return GuardianAction::Wait;
+        }
+        match self.state {
+            GuardianState::Patrol { point } => {
+                let Some(goal) = self.patrol.get(point).copied() else {
+                    // Without a patrol it stands guard where it is.
+                    return GuardianAction::Wait;
+                };
+                if self.position == goal {
+                    self.state = GuardianState::Patrol {
+                        point: (point + 1) % self.patrol.len(),
+                    };
+                    self.wait = PATROL_PAUSE;
+                    return GuardianAction::Wait;
+                }
+                self.step_towards(map, goal, player)
+            }
+            GuardianState::Chase { last_seen } => self.step_towards(map, last_seen, None),
+            GuardianState::Search { .. } => {
+                // Look around by turning a quarter at a time.
+                self.facing = self.facing.perp();
+                self.wait = LOOK_TIME;
+                GuardianAction::Wait
+            }
+            GuardianState::Alerted { .. } => GuardianAction::Wait,
+        }
+    }
+
+    ///
+    /// Turns towards `target`, along whichever axis it is further away on.
+    ///
+    fn face(&mut self, target: IVec2) {
+        let offset = target - self.position;
+        if offset == IVec2::ZERO {
+            return;
+        }
+        self.facing = if offset.x.abs() > offset.y.abs() {
+            IVec2::new(offset.x.signum(), 0)
+        } else {
+            IVec2::new(0, offset.y.signum())
+        };
+    }
+
+    ///
+    /// Takes the next step towards `goal`, walking around `avoid` if it is given.
+    ///
+    fn step_towards(&mut self, map: &TileMap, goal: IVec2, avoid: Option<IVec2>) -> GuardianAction {
+        let blocked = |tile: IVec2| Some(tile) == avoid;
+        if self.path.last() != Some(&goal) || self.path.first().is_none_or(|next| blocked(*next)) {
+            self.path = find_path(map, self.position, goal, blocked).unwrap_or_default();
+        }
+        if self.path.is_empty() {
+            self.wait = BLOCKED_WAIT;
+            return GuardianAction::Wait;
+        }
+
+        let next = self.path.remove(0);
+        self.face(next);
+        self.position = next;
+        self.wait = self.step_time();
+        GuardianAction::Step(next)
+    }
+}
+
+///
+/// A trap on one tile, which springs when the player steps on it while it is armed.
+///
+#[derive(Clone, Debug)]
+pub struct Trap {
+    pub position: IVec2,
+    /// Seconds it takes to arm again after springing.
+    pub rearm: f32,
+    /// Seconds until it is armed again.
+    timer: f32,
+}
+
+impl Trap {
+    pub fn new(position: IVec2, rearm: f32) -> Self {
+        Self {
+            position,
+            rearm,
+            timer: 0.0,
+        }
+    }
+
+    pub fn armed(&self) -> bool {
+        self.timer <= 0.0
+    }
+
+    ///
+    /// Runs the trap for `delta` seconds, returning whether it sprang on the player.
+    ///
+    pub fn update(&mut self, player: Option<IVec2>, delta: f32) -> bool {
+        if !self.armed() {
+            self.timer -= delta;
+            return false;
+        }
+        if player == Some(self.position) {
+            self.timer = self.rearm;
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::Tile;
+
+    ///
+    /// A map from rows of [Tile::from_char] characters, with the first row at the top.
+    ///
+    fn map(rows: &[&str]) -> TileMap {
+        let mut map = TileMap::new();
+        for (row, line) in rows.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let y = (rows.len() - 1 - row) as i32;
+                map.set(IVec2::new(x as i32, y), Tile::from_char(c).unwrap());
+            }
+        }
+        map
+    }
+
+    fn open_map(size: i32) -> TileMap {
+        let row = ".".repeat(size as usize);
+        map(&vec![row.as_str(); size as usize])
+    }
+
+    ///
+    /// Updates `guardian` a second at a time until it is in a state `done` accepts.
+    ///
+    fn run_until(guardian: &mut Guardian, map: &TileMap, player: Option<IVec2>, done: impl Fn(GuardianState) -> bool) {
+        for _ in 0..100 {
+            guardian.update(map, player, 1.0);
+            if done(guardian.state) {
+                return;
+            }
+        }
+        panic!("guardian got stuck in {:?}", guardian.state);
+    }
+
+    #[test]
+    fn sees_to_the_edges_of_the_cone() {
+        let map = open_map(11);
+        let position = IVec2::splat(5);
+        let sees = |offset: IVec2| in_vision_cone(&map, position, IVec2::X, position + offset, 5);
+        assert!(sees(IVec2::new(3, 3)));
+        assert!(sees(IVec2::new(3, -3)));
+        assert!(!sees(IVec2::new(3, 4)));
+        assert!(!sees(IVec2::new(2, -3)));
+        assert!(!sees(IVec2::new(-1, 0)));
+        assert!(!sees(IVec2::new(0, 1)));
+        // Out to `sight` tiles and no further.
+        assert!(sees(IVec2::new(5, 0)));
+        assert!(!sees(IVec2::new(6, 0)));
+        assert!(!sees(IVec2::new(4, 4)));
+    }
+
+    #[test]
+    fn walls_block_the_cone() {
+        let map = map(&["..#.."]);
+        assert!(!in_vision_cone(&map, IVec2::ZERO, IVec2::X, IVec2::new(4, 0), 5));
+        assert!(in_vision_cone(&map, IVec2::ZERO, IVec2::X, IVec2::new(1, 0), 5));
+        let guardian = Guardian::new(IVec2::ZERO, IVec2::X, Vec::new(), 5);
+        assert!(!guardian.sees(&map, IVec2::new(3, 0)));
+        assert!(!vision_cone(&map, IVec2::ZERO, IVec2::X, 5).contains(&IVec2::new(3, 0)));
+    }
+
+    #[test]
+    fn spots_chases_searches_and_goes_back_to_patrolling() {
+        let map = map(&["........."]);
+        let mut guardian = Guardian::new(IVec2::ZERO, IVec2::X, vec![IVec2::ZERO], 4);
+        let player = IVec2::new(3, 0);
+
+        assert_eq!(guardian.update(&map, Some(player), 0.1), GuardianAction::Spotted);
+        assert!(matches!(guardian.state, GuardianState::Alerted { last_seen, .. } if last_seen == player));
+
+        // It stands still while startled, then runs to where the player was, even though they have gone.
+        assert_eq!(guardian.update(&map, None, 0.1), GuardianAction::Wait);
+        assert_eq!(guardian.position, IVec2::ZERO);
+        run_until(&mut guardian, &map, None, |state| matches!(state, GuardianState::Chase { .. }));
+        assert_eq!(guardian.state, GuardianState::Chase { last_seen: player });
+        run_until(&mut guardian, &map, None, |state| matches!(state, GuardianState::Search { .. }));
+        assert_eq!(guardian.position, player);
+
+        let facing = guardian.facing;
+        guardian.update(&map, None, 1.0);
+        assert_ne!(guardian.facing, facing, "it should look around while searching");
+        run_until(&mut guardian, &map, None, |state| matches!(state, GuardianState::Patrol { .. }));
+        for _ in 0..10 {
+            guardian.update(&map, None, 1.0);
+        }
+        assert_eq!(guardian.position, IVec2::ZERO);
+    }
+
+    #[test]
+    fn chases_where_the_player_was_last_seen_not_where_they_are() {
+        let map = map(&["........."]);
+        let mut guardian = Guardian::new(IVec2::ZERO, IVec2::X, Vec::new(), 4);
+        guardian.update(&map, Some(IVec2::new(2, 0)), 0.1);
+        guardian.update(&map, Some(IVec2::new(3, 0)), 0.1);
+        // Out of sight from here on.
+        let hidden = IVec2::new(8, 0);
+        run_until(&mut guardian, &map, Some(hidden), |state| matches!(state, GuardianState::Chase { .. }));
+        assert_eq!(guardian.state, GuardianState::Chase { last_seen: IVec2::new(3, 0) });
+    }
+
+    #[test]
+    fn catches_the_player_when_next_to_them() {
+        let map = map(&["........."]);
+        let mut guardian = Guardian::new(IVec2::ZERO, IVec2::X, Vec::new(), 8);
+        let player = IVec2::new(4, 0);
+        guardian.update(&map, Some(player), 0.1);
+
+        let mut caught = false;
+        for _ in 0..20 {
+            if guardian.update(&map, Some(player), 1.0) == GuardianAction::Caught {
+                caught = true;
+                break;
+            }
+        }
+        assert!(caught);
+        assert_eq!(guardian.position, IVec2::new(3, 0));
+        assert!(matches!(guardian.state, GuardianState::Search { .. }));
+    }
+
+    #[test]
+    fn traps_rearm_after_springing() {
+        let position = IVec2::new(2, 3);
+        let mut trap = Trap::new(position, 2.0);
+        assert!(!trap.update(Some(IVec2::ZERO), 0.1));
+        assert!(!trap.update(None, 0.1));
+        assert!(trap.update(Some(position), 0.1));
+        assert!(!trap.armed());
+
+        // Standing on it while it rearms does nothing.
+        assert!(!trap.update(Some(position), 1.0));
+        assert!(!trap.update(Some(position), 1.0));
+        assert!(trap.armed());
+        assert!(trap.update(Some(position), 0.1));
+    }
+}
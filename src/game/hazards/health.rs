@@ -0,0 +1,185 @@
+use crate::game::camera::CameraImpulse;
+use crate::game::interactables::spawn_speech;
+use crate::game::internal::menu_text;
+use crate::game::player::{GridPosition, Player, PLAYER_Z};
+use crate::game::primary_logic::{GameData, InGameScreen};
+use crate::game::rewind::Rewind;
+use crate::game::textcolor_smoothing::{TargetTextColor, TextColorSmoothing};
+use crate::game::world::{tile_to_world, CurrentMap, TILE_SIZE};
+use crate::utils::position_smoothing::TargetPosition;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+pub const MAX_HEALTH: u32 = 3;
+/// Seconds after being hurt in which nothing can hurt the player again.
+const INVULNERABLE_TIME: f32 = 1.5;
+/// Times per second the player blinks while they can't be hurt.
+const BLINK_RATE: f32 = 8.0;
+const HEALTH_COLOR: Color = Color::srgb(2.1, 1.2, 1.2);
+const LOW_HEALTH_COLOR: Color = Color::srgb(2.6, 0.5, 0.4);
+const HEALTH_FONT_SIZE: f32 = 20.0;
+
+#[derive(Component, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            current: MAX_HEALTH,
+            max: MAX_HEALTH,
+        }
+    }
+}
+
+///
+/// On the player for a moment after they are hurt.
+///
+#[derive(Component)]
+pub(super) struct Invulnerable(Timer);
+
+///
+/// Send to hurt the player. Ignored while they are still recovering from the last hit.
+///
+#[derive(Event, Copy, Clone, Debug)]
+pub struct PlayerDamaged {
+    pub amount: u32,
+    /// The tile the hit came from, which knocks the camera away from it.
+    pub from: IVec2,
+}
+
+///
+/// Sent when the player has lost all their health and been put back at the last checkpoint.
+///
+#[derive(Event, Default)]
+pub struct PlayerRespawned;
+
+///
+/// The health shown at the bottom left of the screen.
+///
+#[derive(Component)]
+pub(super) struct HealthText;
+
+///
+/// Where the player goes back to when they lose all their health: the last checkpoint reached, or the
+/// level's spawn point if there is none or it is no longer walkable.
+///
+pub fn respawn_point(game_data: &GameData, current_map: &CurrentMap) -> IVec2 {
+    game_data
+        .respawn
+        .map(IVec2::from)
+        .filter(|tile| current_map.map.is_walkable(*tile))
+        .unwrap_or(current_map.spawn)
+}
+
+pub(super) fn add_health(mut commands: Commands, players: Query<Entity, Added<Player>>) {
+    for entity in players.iter() {
+        commands.entity(entity).insert(Health::default());
+    }
+}
+
+pub(super) fn spawn_health_text(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(InGameScreen::Playing),
+        HealthText,
+        menu_text("", HEALTH_COLOR, HEALTH_FONT_SIZE, 2.0, 4.0, Anchor::BottomLeft),
+        TargetTextColor(HEALTH_COLOR),
+        TextColorSmoothing::default(),
+    ));
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(super) fn apply_damage(
+    mut commands: Commands,
+    mut events: EventReader<PlayerDamaged>,
+    mut players: Query<
+        (Entity, &mut GridPosition, &mut Health, &mut TargetPosition, &mut Transform),
+        (With<Player>, Without<Invulnerable>),
+    >,
+    mut game_data: ResMut<GameData>,
+    current_map: Res<CurrentMap>,
+    mut rewind: ResMut<Rewind>,
+    mut impulses: EventWriter<CameraImpulse>,
+    mut respawned: EventWriter<PlayerRespawned>,
+) {
+    // Everything hitting the player at once counts as the hardest of those hits.
+    let Some(event) = events.read().max_by_key(|event| event.amount).copied() else {
+        return;
+    };
+    let Ok((entity, mut grid_position, mut health, mut target, mut transform)) = players.get_single_mut() else {
+        return;
+    };
+
+    health.current = health.current.saturating_sub(event.amount);
+    impulses.send(CameraImpulse::Trauma(0.5));
+    let knock = (grid_position.0 - event.from).as_vec2().normalize_or_zero();
+    impulses.send(CameraImpulse::Punch(knock * TILE_SIZE * 0.3));
+    commands
+        .entity(entity)
+        .insert(Invulnerable(Timer::from_seconds(INVULNERABLE_TIME, TimerMode::Once)));
+
+    if health.current > 0 {
+        spawn_speech(&mut commands, grid_position.0, "Ouch!".to_string());
+        return;
+    }
+
+    let tile = respawn_point(&game_data, &current_map);
+    info!("Player lost all their health, respawning at {tile}");
+    grid_position.0 = tile;
+    target.0 = tile_to_world(tile).extend(PLAYER_Z);
+    // Jump there instead of gliding across the level.
+    transform.translation = target.0;
+    health.current = health.max;
+    game_data.position = Some(tile.into());
+    // Undoing back to before the fall would undo the respawn.
+    rewind.clear();
+    respawned.send(PlayerRespawned);
+    spawn_speech(&mut commands, tile, "Back on the path...".to_string());
+}
+
+pub(super) fn blink(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut players: Query<(Entity, &mut Invulnerable, &mut Visibility), With<Player>>,
+) {
+    for (entity, mut invulnerable, mut visibility) in players.iter_mut() {
+        let shown = if invulnerable.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            true
+        } else {
+            ((invulnerable.0.elapsed_secs() * BLINK_RATE) as u32).is_multiple_of(2)
+        };
+        let wanted = if shown { Visibility::Inherited } else { Visibility::Hidden };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+pub(super) fn update_health_text(
+    players: Query<&Health, With<Player>>,
+    mut texts: Query<(&mut Text, &mut TargetTextColor), With<HealthText>>,
+) {
+    let Some(health) = players.iter().next() else {
+        return;
+    };
+    let color = if health.current <= 1 {
+        LOW_HEALTH_COLOR
+    } else {
+        HEALTH_COLOR
+    };
+    for (mut text, mut target_color) in texts.iter_mut() {
+        let value = format!("Health {}/{}", health.current, health.max);
+        if let Some(section) = text.sections.first_mut() {
+            if section.value != value {
+                section.value = value;
+            }
+        }
+        if target_color.0 != color {
+            target_color.0 = color;
+        }
+    }
+}
+
@@ -0,0 +1,293 @@
+use crate::game::interactables::spawn_speech;
+use crate::game::internal::brighten;
+use crate::game::level::{CurrentLevel, HazardKind, Level};
+use crate::game::player::{GridPosition, Player, PlayerMovementSet};
+use crate::game::primary_logic::{GameState, InGameScreen};
+use crate::game::textcolor_smoothing::{TargetSpriteColor, TextColorSmoothing};
+use crate::game::world::{tile_to_world, CurrentMap, FogOfWar, MapLoaded, MapSet, TileVisibility, TILE_SIZE};
+use crate::utils::position_smoothing::{PFPSmoothing, PositionSmoothing, TargetPosition};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+mod ai;
+mod health;
+
+use ai::{Guardian, GuardianAction, GuardianState, Trap};
+use health::{PlayerDamaged, PlayerRespawned};
+
+///
+/// Guardians and traps from the level's hazards. Guardians patrol until the player walks into their
+/// vision cone, then give chase; traps spring when stepped on. Either costs the player health, and losing
+/// all of it sends them back to the last checkpoint.
+///
+/// All the decisions are made by [Guardian] and [Trap], which don't touch the ECS.
+///
+#[derive(Default)]
+pub struct HazardsPlugin;
+
+///
+/// Something from [crate::game::level::LevelHazard] which hurts the player.
+///
+#[derive(Component)]
+struct Hazard {
+    damage: u32,
+}
+
+#[derive(Component)]
+struct GuardianAi(Guardian);
+
+#[derive(Component)]
+struct TrapAi(Trap);
+
+///
+/// One tile of a guardian's vision cone.
+///
+#[derive(Component)]
+struct ConeTile {
+    guardian: Entity,
+    position: IVec2,
+}
+
+///
+/// The tiles the cone of the guardian it is on was last drawn over.
+///
+#[derive(Component, Default)]
+struct DrawnCone(Vec<IVec2>);
+
+const HAZARD_Z: f32 = 4.5;
+const TRAP_Z: f32 = 1.0;
+const CONE_Z: f32 = 0.5;
+const GUARDIAN_COLOR: Color = Color::srgb(2.4, 0.6, 0.5);
+const TRAP_COLOR: Color = Color::srgb(1.6, 0.7, 0.4);
+const SPRUNG_TRAP_COLOR: Color = Color::srgb(0.5, 0.3, 0.25);
+const CONE_COLOR: Color = Color::srgba(1.6, 1.4, 0.6, 0.12);
+const ALERT_CONE_COLOR: Color = Color::srgba(2.0, 0.5, 0.3, 0.2);
+
+impl Plugin for HazardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerDamaged>();
+        app.add_event::<PlayerRespawned>();
+        app.add_systems(
+            Update,
+            spawn_hazards
+                .in_set(MapSet::Prepare)
+                .run_if(in_state(GameState::InGame).and_then(on_event::<MapLoaded>())),
+        );
+        app.add_systems(OnEnter(InGameScreen::Playing), health::spawn_health_text);
+        app.add_systems(
+            Update,
+            (update_guardians, update_traps, health::apply_damage, reset_hazards)
+                .chain()
+                .after(PlayerMovementSet)
+                .run_if(in_state(InGameScreen::Playing)),
+        );
+        app.add_systems(
+            Update,
+            (health::add_health, health::blink, health::update_health_text, update_cones, update_colors)
+                .chain()
+                .after(PlayerMovementSet)
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+fn sprite(size: f32, z: f32, position: IVec2) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color: Color::NONE,
+            custom_size: Some(Vec2::splat(size)),
+            ..default()
+        },
+        transform: Transform::from_translation(tile_to_world(position).extend(z)),
+        ..default()
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn spawn_hazards(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    old_hazards: Query<Entity, Or<(With<Hazard>, With<ConeTile>)>>,
+) {
+    for entity in old_hazards.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(level) = levels.get(&current_level.handle) else {
+        return;
+    };
+
+    for hazard in &level.file.hazards {
+        let position = IVec2::from(hazard.position);
+        // Start hidden, the fog shows them once the player can see their tile.
+        let mut entity = commands.spawn((
+            StateScoped(GameState::InGame),
+            Hazard { damage: hazard.damage },
+            TargetSpriteColor(Color::NONE),
+            TextColorSmoothing {
+                flat: 1.0,
+                proportional: 4.0,
+            },
+        ));
+        match &hazard.kind {
+            HazardKind::Guardian { patrol, sight, facing } => {
+                let patrol = patrol.iter().copied().map(IVec2::from).collect();
+                let guardian = Guardian::new(position, facing.to_ivec2(), patrol, *sight);
+                entity.insert((
+                    sprite(TILE_SIZE * 0.7, HAZARD_Z, position),
+                    TargetPosition(tile_to_world(position).extend(HAZARD_Z)),
+                    PositionSmoothing::PFP(PFPSmoothing {
+                        smoothing_speed: TILE_SIZE / guardian.step_time(),
+                        near_slowdown: false,
+                        end_threshold: 0.5,
+                        ..default()
+                    }),
+                    GuardianAi(guardian),
+                    DrawnCone::default(),
+                ));
+            }
+            HazardKind::Trap { rearm } => {
+                entity.insert((sprite(TILE_SIZE * 0.4, TRAP_Z, position), TrapAi(Trap::new(position, *rearm))));
+            }
+        }
+    }
+}
+
+fn update_guardians(
+    mut commands: Commands,
+    time: Res<Time>,
+    current_map: Res<CurrentMap>,
+    players: Query<&GridPosition, With<Player>>,
+    mut guardians: Query<(&Hazard, &mut GuardianAi, &mut TargetPosition, &mut PositionSmoothing)>,
+    mut damaged: EventWriter<PlayerDamaged>,
+) {
+    let player = players.iter().next().map(|grid_position| grid_position.0);
+    for (hazard, mut ai, mut target, mut smoothing) in guardians.iter_mut() {
+        let guardian = &mut ai.0;
+        match guardian.update(&current_map.map, player, time.delta_seconds()) {
+            GuardianAction::Wait => {}
+            GuardianAction::Step(next) => {
+                target.0 = tile_to_world(next).extend(HAZARD_Z);
+                if let PositionSmoothing::PFP(pfp) = smoothing.as_mut() {
+                    pfp.smoothing_speed = TILE_SIZE / guardian.step_time();
+                }
+            }
+            GuardianAction::Spotted => spawn_speech(&mut commands, guardian.position, "!".to_string()),
+            GuardianAction::Caught => {
+                damaged.send(PlayerDamaged {
+                    amount: hazard.damage,
+                    from: guardian.position,
+                });
+            }
+        }
+    }
+}
+
+fn update_traps(
+    time: Res<Time>,
+    players: Query<&GridPosition, With<Player>>,
+    mut traps: Query<(&Hazard, &mut TrapAi)>,
+    mut damaged: EventWriter<PlayerDamaged>,
+) {
+    let player = players.iter().next().map(|grid_position| grid_position.0);
+    for (hazard, mut ai) in traps.iter_mut() {
+        if ai.0.update(player, time.delta_seconds()) {
+            damaged.send(PlayerDamaged {
+                amount: hazard.damage,
+                from: ai.0.position,
+            });
+        }
+    }
+}
+
+///
+/// Calls the guardians off once the player is back at a checkpoint.
+///
+fn reset_hazards(mut events: EventReader<PlayerRespawned>, mut guardians: Query<&mut GuardianAi>) {
+    if events.read().count() == 0 {
+        return;
+    }
+    for mut ai in guardians.iter_mut() {
+        ai.0.reset();
+    }
+}
+
+///
+/// Redraws a guardian's vision cone whenever it moves or turns.
+///
+fn update_cones(
+    mut commands: Commands,
+    current_map: Res<CurrentMap>,
+    mut guardians: Query<(Entity, &GuardianAi, &mut DrawnCone)>,
+    tiles: Query<(Entity, &ConeTile)>,
+) {
+    for (guardian_entity, ai, mut drawn) in guardians.iter_mut() {
+        let guardian = &ai.0;
+        let cone = ai::vision_cone(&current_map.map, guardian.position, guardian.facing, guardian.sight);
+        if cone == drawn.0 {
+            continue;
+        }
+        for (entity, tile) in tiles.iter() {
+            if tile.guardian == guardian_entity {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        for position in &cone {
+            commands.spawn((
+                StateScoped(GameState::InGame),
+                sprite(TILE_SIZE, CONE_Z, *position),
+                TargetSpriteColor(Color::NONE),
+                TextColorSmoothing::default(),
+                ConeTile {
+                    guardian: guardian_entity,
+                    position: *position,
+                },
+            ));
+        }
+        drawn.0 = cone;
+    }
+}
+
+///
+/// Follows the fog and what the hazards are doing. Guardians and their cones only show while in sight,
+/// since where they were last seen is no use.
+///
+#[allow(clippy::type_complexity)]
+fn update_colors(
+    fog: Res<FogOfWar>,
+    mut guardians: Query<(Entity, &GuardianAi, &mut TargetSpriteColor), Without<ConeTile>>,
+    mut traps: Query<(&TrapAi, &mut TargetSpriteColor), (Without<GuardianAi>, Without<ConeTile>)>,
+    mut tiles: Query<(&ConeTile, &mut TargetSpriteColor)>,
+) {
+    let set = |target: &mut TargetSpriteColor, color: Color| {
+        if target.0 != color {
+            target.0 = color;
+        }
+    };
+    let in_sight = |position: IVec2, color: Color| match fog.get(position) {
+        TileVisibility::Visible => color,
+        _ => Color::NONE,
+    };
+
+    let mut alerted = HashMap::new();
+    for (entity, ai, mut target) in guardians.iter_mut() {
+        alerted.insert(entity, !matches!(ai.0.state, GuardianState::Patrol { .. }));
+        set(&mut target, in_sight(ai.0.position, GUARDIAN_COLOR));
+    }
+    for (tile, mut target) in tiles.iter_mut() {
+        let color = match alerted.get(&tile.guardian) {
+            Some(true) => ALERT_CONE_COLOR,
+            _ => CONE_COLOR,
+        };
+        set(&mut target, in_sight(tile.position, color));
+    }
+    for (ai, mut target) in traps.iter_mut() {
+        let color = if ai.0.armed() { TRAP_COLOR } else { SPRUNG_TRAP_COLOR };
+        let color = match fog.get(ai.0.position) {
+            TileVisibility::Unseen => Color::NONE,
+            TileVisibility::Seen => brighten(color, 0.35),
+            TileVisibility::Visible => color,
+        };
+        set(&mut target, color);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::game::interactables::spawn_speech;
+use crate::game::internal::{brighten, menu_text};
+use crate::game::level::{CurrentLevel, Level, LevelSecret};
+use crate::game::player::{GridPosition, Player, PlayerMovementSet};
+use crate::game::primary_logic::{GameData, InGameScreen};
+use crate::game::saves::SaveGame;
+use crate::game::textcolor_smoothing::{TargetTextColor, TextColorSmoothing};
+use crate::utils::position_smoothing::PositionSmoothing;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::Text2dBounds;
+
+///
+/// Hints for undiscovered secrets in the current level, taken with H. Every secret has a few, each more
+/// detailed than the last, and the latest one given stays on screen, glowing brighter the closer the
+/// player gets to its secret.
+///
+#[derive(Default)]
+pub struct HintsPlugin;
+
+///
+/// Sent when the player takes a hint.
+///
+#[derive(Event, Default)]
+pub struct HintTaken;
+
+///
+/// The hint shown at the bottom of the screen.
+///
+#[derive(Component)]
+struct HintText;
+
+/// Seconds of play time between hints.
+const HINT_COOLDOWN: f64 = 45.0;
+/// Tiles from a secret at which its hint starts getting warmer.
+const WARM_RANGE: f32 = 12.0;
+const HINT_COLOR: Color = Color::srgb(0.9, 0.8, 1.2);
+const HINT_FONT_SIZE: f32 = 18.0;
+
+impl Plugin for HintsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HintTaken>();
+        app.add_systems(OnEnter(InGameScreen::Playing), spawn_hint_text);
+        app.add_systems(
+            Update,
+            (take_hint, update_hint_text)
+                .chain()
+                .after(PlayerMovementSet)
+                .run_if(in_state(InGameScreen::Playing)),
+        );
+    }
+}
+
+///
+/// Every hint a secret has, least detailed first.
+///
+fn hint_tiers(secret: &LevelSecret) -> Vec<&str> {
+    let first = (!secret.hint.is_empty()).then_some(secret.hint.as_str());
+    first.into_iter().chain(secret.hints.iter().map(String::as_str)).collect()
+}
+
+///
+/// Undiscovered secrets of a level which hints can be given for, the same ones the codex hints at.
+///
+fn hintable<'a>(secrets: &'a [LevelSecret], game_data: &'a GameData) -> impl Iterator<Item = &'a LevelSecret> {
+    let found = secrets
+        .iter()
+        .filter(|secret| game_data.discovered_secrets.contains(&secret.id))
+        .count();
+    secrets
+        .iter()
+        .filter(move |secret| !game_data.discovered_secrets.contains(&secret.id) && found >= secret.hint_after)
+}
+
+fn taken(secret: &LevelSecret, game_data: &GameData) -> usize {
+    game_data.hints_taken.get(&secret.id).copied().unwrap_or(0)
+}
+
+fn distance(secret: &LevelSecret, player: IVec2) -> i32 {
+    (IVec2::from(secret.position) - player).abs().element_sum()
+}
+
+///
+/// The secret the next hint is for and which of its hints that is. Secrets which already have a hint
+/// get a more detailed one before anything new is hinted at, the nearest first.
+///
+fn next_hint<'a>(secrets: &'a [LevelSecret], game_data: &'a GameData, player: IVec2) -> Option<(&'a LevelSecret, usize)> {
+    hintable(secrets, game_data)
+        .filter(|secret| taken(secret, game_data) < hint_tiers(secret).len())
+        .min_by_key(|secret| (taken(secret, game_data) == 0, distance(secret, player)))
+        .map(|secret| (secret, taken(secret, game_data)))
+}
+
+///
+/// The nearest secret which has been hinted at, and the most detailed hint given for it.
+///
+fn shown_hint<'a>(secrets: &'a [LevelSecret], game_data: &'a GameData, player: IVec2) -> Option<(&'a LevelSecret, &'a str)> {
+    hintable(secrets, game_data)
+        .filter(|secret| taken(secret, game_data) > 0)
+        .min_by_key(|secret| distance(secret, player))
+        .and_then(|secret| Some((secret, *hint_tiers(secret).get(taken(secret, game_data) - 1)?)))
+}
+
+///
+/// Seconds of play time left until the next hint, or `None` if one can be taken now.
+///
+fn cooldown_remaining(game_data: &GameData) -> Option<f64> {
+    let remaining = HINT_COOLDOWN - (game_data.play_time - game_data.last_hint?);
+    (remaining > 0.0).then_some(remaining)
+}
+
+///
+/// How close the player is to a secret, from 0 outside [WARM_RANGE] to 1 on top of it.
+///
+fn warmth(distance: i32) -> f32 {
+    1.0 - (distance as f32 / WARM_RANGE).min(1.0)
+}
+
+fn spawn_hint_text(mut commands: Commands) {
+    commands
+        .spawn((
+            StateScoped(InGameScreen::Playing),
+            HintText,
+            menu_text("", HINT_COLOR, HINT_FONT_SIZE, 50.0, 4.0, Anchor::BottomCenter),
+            TargetTextColor(HINT_COLOR),
+            TextColorSmoothing::default(),
+        ))
+        .insert((
+            PositionSmoothing::None,
+            Text2dBounds {
+                size: Vec2::new(700.0, f32::INFINITY),
+            },
+        ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn take_hint(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut game_data: ResMut<GameData>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    players: Query<&GridPosition, With<Player>>,
+    mut hints: EventWriter<HintTaken>,
+    mut save: EventWriter<SaveGame>,
+) {
+    if !keys.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+    let (Some(level), Some(player)) = (levels.get(&current_level.handle), players.iter().next()) else {
+        return;
+    };
+
+    if let Some(remaining) = cooldown_remaining(&game_data) {
+        spawn_speech(&mut commands, player.0, format!("Another hint in {}s.", remaining.ceil()));
+        return;
+    }
+    let Some((secret, tier)) = next_hint(&level.file.secrets, &game_data, player.0) else {
+        spawn_speech(&mut commands, player.0, "There's nothing more to hint at here.".to_string());
+        return;
+    };
+
+    let secret = secret.id.clone();
+    let level_id = game_data.level.clone();
+    game_data.hints_taken.insert(secret.clone(), tier + 1);
+    *game_data.hints_used.entry(level_id).or_default() += 1;
+    game_data.last_hint = Some(game_data.play_time);
+    info!("Hint {} for secret {secret}", tier + 1);
+    hints.send(HintTaken);
+    save.send(SaveGame);
+}
+
+fn update_hint_text(
+    mut query: Query<(&mut Text, &mut TargetTextColor), With<HintText>>,
+    game_data: Res<GameData>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    players: Query<&GridPosition, With<Player>>,
+) {
+    let shown = levels
+        .get(&current_level.handle)
+        .zip(players.iter().next())
+        .and_then(|(level, player)| {
+            let (secret, text) = shown_hint(&level.file.secrets, &game_data, player.0)?;
+            Some((text, warmth(distance(secret, player.0))))
+        });
+    let (value, warmth) = shown.unwrap_or_default();
+
+    for (mut text, mut target_color) in query.iter_mut() {
+        if let Some(section) = text.sections.first_mut() {
+            if section.value != value {
+                section.value = value.to_string();
+            }
+        }
+        // Warmer hints glow brighter, so walking around shows whether the secret is near.
+        let color = brighten(HINT_COLOR, 0.5 + 2.0 * warmth);
+        if target_color.0 != color {
+            target_color.0 = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ron_asset::parse_ron;
+
+    fn secret(id: &str, position: [i32; 2], hint_after: usize) -> LevelSecret {
+        let [x, y] = position;
+        parse_ron::<LevelSecret>(&format!(
+            "(id: \"{id}\", name: \"{id}\", position: ({x}, {y}), hint: \"{id} 1\", hints: [\"{id} 2\"], \
+             hint_after: {hint_after})"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn hinted_secrets_get_more_detail_before_new_ones_are_hinted() {
+        let secrets = [secret("far", [10, 0], 0), secret("near", [1, 0], 0)];
+        let mut game_data = GameData::default();
+        let (first, tier) = next_hint(&secrets, &game_data, IVec2::ZERO).unwrap();
+        assert_eq!((first.id.as_str(), tier), ("near", 0));
+
+        // Standing next to the other secret doesn't change which one is hinted at next.
+        game_data.hints_taken.insert("near".to_string(), 1);
+        let (second, tier) = next_hint(&secrets, &game_data, IVec2::new(10, 0)).unwrap();
+        assert_eq!((second.id.as_str(), tier), ("near", 1));
+
+        game_data.hints_taken.insert("near".to_string(), 2);
+        let (third, tier) = next_hint(&secrets, &game_data, IVec2::ZERO).unwrap();
+        assert_eq!((third.id.as_str(), tier), ("far", 0));
+
+        game_data.hints_taken.insert("far".to_string(), 2);
+        assert!(next_hint(&secrets, &game_data, IVec2::ZERO).is_none());
+    }
+
+    #[test]
+    fn secrets_are_only_hinted_after_enough_others_are_found() {
+        let secrets = [secret("first", [5, 0], 0), secret("second", [1, 0], 1)];
+        let mut game_data = GameData::default();
+        assert_eq!(next_hint(&secrets, &game_data, IVec2::ZERO).unwrap().0.id, "first");
+
+        game_data.discovered_secrets.insert("first".to_string());
+        assert_eq!(next_hint(&secrets, &game_data, IVec2::ZERO).unwrap().0.id, "second");
+    }
+
+    #[test]
+    fn the_latest_hint_for_the_nearest_hinted_secret_is_shown() {
+        let secrets = [secret("far", [10, 0], 0), secret("near", [1, 0], 0), secret("unhinted", [0, 0], 0)];
+        let mut game_data = GameData::default();
+        assert!(shown_hint(&secrets, &game_data, IVec2::ZERO).is_none());
+
+        game_data.hints_taken.insert("far".to_string(), 1);
+        game_data.hints_taken.insert("near".to_string(), 2);
+        let (secret, text) = shown_hint(&secrets, &game_data, IVec2::ZERO).unwrap();
+        assert_eq!((secret.id.as_str(), text), ("near", "near 2"));
+        let (secret, text) = shown_hint(&secrets, &game_data, IVec2::new(10, 0)).unwrap();
+        assert_eq!((secret.id.as_str(), text), ("far", "far 1"));
+
+        // Found secrets aren't hinted at any more.
+        game_data.discovered_secrets.insert("far".to_string());
+        assert_eq!(shown_hint(&secrets, &game_data, IVec2::new(10, 0)).unwrap().0.id, "near");
+    }
+
+    #[test]
+    fn the_cooldown_ends_exactly_after_its_length() {
+        let mut game_data = GameData {
+            play_time: 100.0,
+            ..GameData::default()
+        };
+        assert_eq!(cooldown_remaining(&game_data), None, "the first hint can be taken straight away");
+
+        game_data.last_hint = Some(100.0);
+        assert_eq!(cooldown_remaining(&game_data), Some(HINT_COOLDOWN));
+        game_data.play_time = 100.0 + HINT_COOLDOWN - 1.0;
+        assert_eq!(cooldown_remaining(&game_data), Some(1.0));
+        game_data.play_time = 100.0 + HINT_COOLDOWN;
+        assert_eq!(cooldown_remaining(&game_data), None);
+    }
+
+    #[test]
+    fn warmth_is_clamped() {
+        assert_eq!(warmth(0), 1.0);
+        assert_eq!(warmth(WARM_RANGE as i32 / 2), 0.5);
+        assert_eq!(warmth(WARM_RANGE as i32), 0.0);
+        assert_eq!(warmth(WARM_RANGE as i32 * 3), 0.0);
+    }
+}
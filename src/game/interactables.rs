@@ -0,0 +1,392 @@
+use crate::game::dialogue::StartDialogue;
+use crate::game::internal::brighten;
+use crate::game::items::{ItemCatalog, ItemSet};
+use crate::game::level::{CurrentLevel, InteractableKind, Level, LevelEntity};
+use crate::game::player::{GridPosition, Player, PlayerInteract, PlayerMovementSet};
+use crate::game::primary_logic::{GameData, GameState, InGameScreen};
+use crate::game::rewind::{Rewind, Snapshot};
+use crate::game::saves::SaveGame;
+use crate::game::textcolor_smoothing::{TargetSpriteColor, TargetTextColor, TextColorSmoothing};
+use crate::game::world::{tile_to_world, FogOfWar, MapLoaded, MapSet, TileVisibility, TILE_SIZE};
+use crate::utils::buttoning::{
+    ButtonClickEvent, ButtonClickType, GenericBoundingBox, HoverOverride, Hovered, SpriteButton,
+};
+use bevy::prelude::*;
+
+///
+/// Signposts, chests, levers and other things in the world the player can use, placed as level entities.
+///
+#[derive(Default)]
+pub struct InteractablesPlugin;
+
+///
+/// Something in the current level the player can interact with while standing on or next to it.
+///
+#[derive(Component)]
+pub struct Interactable {
+    pub kind: InteractableKind,
+    pub entity: LevelEntity,
+    /// The tile it is on, which only differs from where the level placed it for NPCs walking around.
+    pub position: IVec2,
+}
+
+impl Interactable {
+    pub fn position(&self) -> IVec2 {
+        self.position
+    }
+
+    pub fn name(&self) -> &str {
+        self.entity.property("name").unwrap_or(&self.entity.kind)
+    }
+}
+
+///
+/// Sent when the player uses an [Interactable], either with the interact button or by clicking it.
+///
+#[derive(Event, Copy, Clone, Debug)]
+pub struct Interact {
+    pub entity: Entity,
+}
+
+///
+/// Text floating above an interactable, despawned when the timer runs out.
+///
+#[derive(Component)]
+struct Speech(Timer);
+
+pub const INTERACTABLE_Z: f32 = 4.0;
+const SPEECH_Z: f32 = 10.0;
+const SPEECH_DURATION: f32 = 4.0;
+const SPEECH_COLOR: Color = Color::srgb(2.1, 2.1, 2.1);
+const HOVER_BRIGHTEN: f32 = 1.8;
+
+impl Plugin for InteractablesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Interact>();
+        app.add_systems(
+            Update,
+            spawn_interactables
+                .in_set(MapSet::Prepare)
+                .run_if(in_state(GameState::InGame).and_then(on_event::<MapLoaded>())),
+        );
+        app.add_systems(
+            Update,
+            (
+                (mark_nearby, interact_on_click, interact_on_key, pick_up_on_step, handle_interactions)
+                    .run_if(in_state(InGameScreen::Playing)),
+                update_colors,
+                update_speech,
+            )
+                .chain()
+                .after(PlayerMovementSet)
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+///
+/// The [GameData] flag holding whether a chest has been opened, a lever is on or a door is open.
+///
+pub fn state_flag(id: &str) -> String {
+    format!("interactable:{id}")
+}
+
+///
+/// The [GameData] flag holding whether a locked door has been opened with its key.
+///
+pub fn unlocked_flag(id: &str) -> String {
+    format!("unlocked:{id}")
+}
+
+fn is_on(game_data: &GameData, entity: &LevelEntity) -> bool {
+    entity.id.as_ref().is_some_and(|id| game_data.flags.contains(&state_flag(id)))
+}
+
+fn base_color(kind: InteractableKind, on: bool) -> Color {
+    match (kind, on) {
+        (InteractableKind::Signpost, _) => Color::srgb(0.6, 0.45, 0.25),
+        (InteractableKind::Chest, false) => Color::srgb(0.75, 0.55, 0.2),
+        (InteractableKind::Chest, true) => Color::srgb(0.4, 0.3, 0.15),
+        (InteractableKind::Lever, false) => Color::srgb(0.5, 0.5, 0.55),
+        (InteractableKind::Lever, true) => Color::srgb(0.9, 0.8, 0.4),
+        (InteractableKind::Npc, _) => Color::srgb(0.5, 0.8, 0.9),
+        (InteractableKind::Item, false) => Color::srgb(1.6, 1.4, 0.6),
+        // Picked up.
+        (InteractableKind::Item, true) => Color::NONE,
+        (InteractableKind::Door, false) => Color::srgb(0.45, 0.3, 0.18),
+        (InteractableKind::Door, true) => Color::srgba(0.45, 0.3, 0.18, 0.2),
+    }
+}
+
+fn sprite_size(kind: InteractableKind) -> f32 {
+    match kind {
+        // Doors fill the gap they close.
+        InteractableKind::Door => TILE_SIZE,
+        _ => TILE_SIZE * 0.7,
+    }
+}
+
+fn spawn_interactables(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    game_data: Res<GameData>,
+    old_interactables: Query<Entity, With<Interactable>>,
+) {
+    for entity in old_interactables.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(level) = levels.get(&current_level.handle) else {
+        return;
+    };
+
+    for entity in &level.file.entities {
+        let Some(kind) = entity.interactable() else {
+            continue;
+        };
+        // Start hidden, the fog shows it once the player has seen its tile.
+        let position = tile_to_world(IVec2::from(entity.position)).extend(INTERACTABLE_Z);
+        commands.spawn((
+            StateScoped(GameState::InGame),
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(Vec2::splat(sprite_size(kind))),
+                    flip_x: is_on(&game_data, entity),
+                    ..default()
+                },
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            SpriteButton,
+            TargetSpriteColor(Color::NONE),
+            TextColorSmoothing {
+                flat: 1.0,
+                proportional: 4.0,
+            },
+            Interactable {
+                kind,
+                entity: entity.clone(),
+                position: IVec2::from(entity.position),
+            },
+        ));
+    }
+}
+
+///
+/// Highlights the interactables the player can reach.
+///
+fn mark_nearby(
+    mut commands: Commands,
+    players: Query<&GridPosition, With<Player>>,
+    interactables: Query<(Entity, &Interactable, Has<HoverOverride>)>,
+) {
+    let player = players.iter().next().map(|grid_position| grid_position.0);
+    for (entity, interactable, was_near) in interactables.iter() {
+        let near = player.is_some_and(|player| {
+            let distance = (interactable.position() - player).abs();
+            distance.x + distance.y <= 1
+        });
+        if near && !was_near {
+            commands.entity(entity).insert(HoverOverride);
+        } else if !near && was_near {
+            commands.entity(entity).remove::<HoverOverride>();
+        }
+    }
+}
+
+///
+/// Clicking works on anything in reach, so the player can pick which of several neighbours to use. Being
+/// in reach makes an interactable [Hovered](crate::utils::buttoning::Hovered) wherever the cursor is, so
+/// only clicks actually on it count.
+///
+fn interact_on_click(
+    mut clicks: EventReader<ButtonClickEvent>,
+    interactables: Query<&GenericBoundingBox, (With<Interactable>, With<HoverOverride>)>,
+    mut interact: EventWriter<Interact>,
+) {
+    for click in clicks.read() {
+        let on_it = interactables
+            .get(click.entity)
+            .is_ok_and(|bounding_box| bounding_box.contains(click.mouse_position));
+        if click.mouse_button == MouseButton::Left && click.click_type == ButtonClickType::Pressed && on_it {
+            interact.send(Interact { entity: click.entity });
+        }
+    }
+}
+
+///
+/// The interact button uses whatever the player is facing, or else what they are standing on.
+///
+fn interact_on_key(
+    mut interactions: EventReader<PlayerInteract>,
+    interactables: Query<(Entity, &Interactable)>,
+    mut interact: EventWriter<Interact>,
+) {
+    for interaction in interactions.read() {
+        let at = |position: IVec2| {
+            interactables
+                .iter()
+                .find(|(_, interactable)| interactable.position() == position)
+                .map(|(entity, _)| entity)
+        };
+        if let Some(entity) = at(interaction.position + interaction.facing).or_else(|| at(interaction.position)) {
+            interact.send(Interact { entity });
+        }
+    }
+}
+
+///
+/// Items are picked up just by walking over them.
+///
+fn pick_up_on_step(
+    players: Query<&GridPosition, (With<Player>, Changed<GridPosition>)>,
+    interactables: Query<(Entity, &Interactable)>,
+    game_data: Res<GameData>,
+    mut interact: EventWriter<Interact>,
+) {
+    for grid_position in players.iter() {
+        for (entity, interactable) in interactables.iter() {
+            if interactable.kind == InteractableKind::Item
+                && interactable.position() == grid_position.0
+                && !is_on(&game_data, &interactable.entity)
+            {
+                interact.send(Interact { entity });
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_interactions(
+    mut commands: Commands,
+    mut events: EventReader<Interact>,
+    mut interactables: Query<(&Interactable, &mut Sprite)>,
+    speeches: Query<Entity, With<Speech>>,
+    mut game_data: ResMut<GameData>,
+    mut save: EventWriter<SaveGame>,
+    mut start_dialogue: EventWriter<StartDialogue>,
+    item_sets: Res<Assets<ItemSet>>,
+    mut rewind: ResMut<Rewind>,
+) {
+    for event in events.read() {
+        let Ok((interactable, mut sprite)) = interactables.get_mut(event.entity) else {
+            continue;
+        };
+        let entity = &interactable.entity;
+        if let Some(id) = entity.property("dialogue") {
+            start_dialogue.send(StartDialogue { id: id.to_string() });
+            continue;
+        }
+        let on = is_on(&game_data, entity);
+        let before = Snapshot::of(&game_data);
+
+        let text = match interactable.kind {
+            InteractableKind::Signpost | InteractableKind::Npc => entity.property("text").map(str::to_string),
+            InteractableKind::Chest if on => Some(format!("The {} is empty.", interactable.name())),
+            // Already picked up.
+            InteractableKind::Item if on => continue,
+            // Doors are opened by the puzzles, or here with their key.
+            InteractableKind::Door if on => continue,
+            InteractableKind::Door => match (&entity.id, entity.property("key")) {
+                (Some(id), Some(key)) if game_data.has_item(key) => {
+                    game_data.take_item(key);
+                    game_data.flags.insert(unlocked_flag(id));
+                    save.send(SaveGame);
+                    Some(format!("Unlocked with {}", ItemCatalog::name(&item_sets, key)))
+                }
+                (_, Some(_)) => Some(format!("The {} is locked.", interactable.name())),
+                (_, None) => Some(format!("The {} won't budge.", interactable.name())),
+            },
+            InteractableKind::Chest | InteractableKind::Lever | InteractableKind::Item => {
+                // Validation makes sure these have ids.
+                let Some(id) = &entity.id else {
+                    continue;
+                };
+                let flag = state_flag(id);
+                if on {
+                    game_data.flags.remove(&flag);
+                } else {
+                    game_data.flags.insert(flag);
+                }
+                sprite.flip_x = !on;
+                save.send(SaveGame);
+
+                match entity.property("item").filter(|_| interactable.kind != InteractableKind::Lever) {
+                    Some(item) => {
+                        game_data.give_item(item);
+                        Some(format!("Got {}", ItemCatalog::name(&item_sets, item)))
+                    }
+                    None => None,
+                }
+            }
+        };
+        rewind.history.record_since(&before, &game_data);
+
+        if let Some(text) = text {
+            for speech in speeches.iter() {
+                commands.entity(speech).despawn_recursive();
+            }
+            spawn_speech(&mut commands, interactable.position(), text);
+        }
+    }
+}
+
+///
+/// Shows `text` floating above the tile at `position` for a few seconds.
+///
+pub fn spawn_speech(commands: &mut Commands, position: IVec2, text: String) {
+    let position = (tile_to_world(position) + Vec2::Y * TILE_SIZE).extend(SPEECH_Z);
+    commands.spawn((
+        StateScoped(GameState::InGame),
+        Text2dBundle {
+            text: Text::from_section(
+                text,
+                TextStyle {
+                    color: SPEECH_COLOR,
+                    font_size: 20.0,
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            transform: Transform::from_translation(position),
+            ..default()
+        },
+        TargetTextColor(Color::NONE),
+        TextColorSmoothing {
+            flat: 0.3,
+            proportional: 0.3,
+        },
+        Speech(Timer::from_seconds(SPEECH_DURATION, TimerMode::Once)),
+    ));
+}
+
+///
+/// Follows the fog, the interactable's state and whether it is hovered.
+///
+fn update_colors(
+    mut query: Query<(&Interactable, Has<Hovered>, &mut TargetSpriteColor)>,
+    fog: Res<FogOfWar>,
+    game_data: Res<GameData>,
+) {
+    for (interactable, hovered, mut target) in query.iter_mut() {
+        let color = base_color(interactable.kind, is_on(&game_data, &interactable.entity));
+        let color = match fog.get(interactable.position()) {
+            TileVisibility::Unseen => Color::NONE,
+            TileVisibility::Seen => brighten(color, 0.35),
+            TileVisibility::Visible if hovered => brighten(color, HOVER_BRIGHTEN),
+            TileVisibility::Visible => color,
+        };
+        if target.0 != color {
+            target.0 = color;
+        }
+    }
+}
+
+fn update_speech(mut commands: Commands, mut query: Query<(Entity, &mut Speech)>, time: Res<Time>) {
+    for (entity, mut speech) in query.iter_mut() {
+        if speech.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
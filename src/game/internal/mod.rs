@@ -1,5 +1,7 @@
 mod components;
 mod funcs;
+mod ui;
 
 pub use components::*;
 pub use funcs::*;
+pub use ui::*;
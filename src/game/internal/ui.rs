@@ -0,0 +1,173 @@
+use crate::game::internal::DefaultColor;
+use crate::game::textcolor_smoothing::{TargetTextColor, TextColorSmoothing};
+use crate::utils::buttoning::TextButton;
+use crate::utils::position_smoothing::{PSmoothing, PositionSmoothing, TargetPosition, DEFAULT_END_THRESHOLD};
+use crate::utils::responsive::{ResponsivePosition, ResponsiveValue};
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+///
+/// Depth menu text is drawn at, in front of the world.
+///
+pub const UI_Z: f32 = 50.0;
+
+///
+/// Text which glides in from below the screen to a position given in percentages of the view, the
+/// same way the main menu does.
+///
+pub fn menu_text(text: impl Into<String>, color: Color, font_size: f32, x: f32, y: f32, anchor: Anchor) -> impl Bundle {
+    (
+        Text2dBundle {
+            text: Text::from_section(
+                text,
+                TextStyle {
+                    color,
+                    font_size,
+                    font: Handle::default(),
+                },
+            ),
+            transform: Transform::from_xyz(640.0, -250.0, UI_Z),
+            text_anchor: anchor,
+            ..default()
+        },
+        PositionSmoothing::P(PSmoothing {
+            modifier: 2.4,
+            end_threshold: DEFAULT_END_THRESHOLD,
+        }),
+        TargetPosition::default(),
+        ResponsivePosition::new(
+            ResponsiveValue::Percentage(x),
+            ResponsiveValue::Percentage(y),
+            ResponsiveValue::Absolute(UI_Z),
+        ),
+    )
+}
+
+///
+/// [menu_text] which can be hovered and clicked, and brightens while hovered.
+///
+pub fn menu_button(text: impl Into<String>, color: Color, font_size: f32, x: f32, y: f32, anchor: Anchor) -> impl Bundle {
+    (
+        menu_text(text, color, font_size, x, y, anchor),
+        DefaultColor(color),
+        TargetTextColor(color),
+        TextColorSmoothing::default(),
+        TextButton,
+    )
+}
+
+///
+/// Height (in percent of the view) of the first row of a list screen.
+///
+pub const LIST_TOP: f32 = 80.0;
+///
+/// Rows below this height (in percent of the view) don't fit above a list screen's close button.
+///
+pub const LIST_BOTTOM: f32 = 16.0;
+
+const PAGE_COLOR: Color = Color::srgb(1.4, 2.1, 2.4);
+const PAGE_NUMBER_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+
+///
+/// The rows of a list screen split into pages which fit between [LIST_TOP] and [LIST_BOTTOM], so long
+/// lists can be paged through instead of running off the screen.
+///
+pub struct ListPages<T> {
+    /// The rows on each page, with the height to put them at.
+    pages: Vec<Vec<(T, f32)>>,
+}
+
+impl<T> ListPages<T> {
+    ///
+    /// Lays out `rows` from the top, each given with the space it takes up above the next row.
+    ///
+    pub fn new(rows: impl IntoIterator<Item = (T, f32)>) -> Self {
+        let mut pages = vec![Vec::new()];
+        let mut y = LIST_TOP;
+        for (row, height) in rows {
+            if y < LIST_BOTTOM {
+                pages.push(Vec::new());
+                y = LIST_TOP;
+            }
+            if let Some(page) = pages.last_mut() {
+                page.push((row, y));
+            }
+            y -= height;
+        }
+        Self { pages }
+    }
+
+    pub fn last_page(&self) -> usize {
+        self.pages.len() - 1
+    }
+
+    ///
+    /// The rows on `page`, or on the last page if there aren't that many.
+    ///
+    pub fn into_page(mut self, page: usize) -> Vec<(T, f32)> {
+        let page = page.min(self.last_page());
+        self.pages.swap_remove(page)
+    }
+}
+
+///
+/// Spawns the buttons to turn to the pages either side of `page`, and which page it is, if there is more
+/// than one. `button` makes the component the screen's clicks look for to turn to a page.
+///
+pub fn spawn_page_controls<C: Component>(
+    commands: &mut Commands,
+    scope: impl Bundle + Clone,
+    page: usize,
+    last_page: usize,
+    button: impl Fn(usize) -> C,
+) {
+    if last_page == 0 {
+        return;
+    }
+    let page = page.min(last_page);
+    let number = format!("Page {}/{}", page + 1, last_page + 1);
+    commands.spawn((scope.clone(), menu_text(number, PAGE_NUMBER_COLOR, 20.0, 50.0, 13.0, Anchor::Center)));
+    if page > 0 {
+        commands.spawn((
+            scope.clone(),
+            menu_button("Previous", PAGE_COLOR, 26.0, 30.0, 8.0, Anchor::Center),
+            button(page - 1),
+        ));
+    }
+    if page < last_page {
+        commands.spawn((
+            scope,
+            menu_button("Next", PAGE_COLOR, 26.0, 70.0, 8.0, Anchor::Center),
+            button(page + 1),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_go_down_from_the_top() {
+        let pages = ListPages::new([("a", 5.0), ("b", 6.0), ("c", 5.0)]);
+        assert_eq!(pages.last_page(), 0);
+        assert_eq!(pages.into_page(0), [("a", LIST_TOP), ("b", LIST_TOP - 5.0), ("c", LIST_TOP - 11.0)]);
+    }
+
+    #[test]
+    fn rows_which_dont_fit_go_on_the_next_page() {
+        let pages = ListPages::new((0..30).map(|row| (row, 5.0)));
+        // 80 down to 16 in steps of 5 fits 13 rows.
+        assert_eq!(pages.last_page(), 2);
+        let second = pages.into_page(1);
+        assert_eq!(second.len(), 13);
+        assert_eq!(second[0], (13, LIST_TOP));
+        assert!(second.iter().all(|(_, y)| *y >= LIST_BOTTOM));
+    }
+
+    #[test]
+    fn pages_past_the_end_show_the_last_page() {
+        assert_eq!(ListPages::new((0..30).map(|row| (row, 5.0))).into_page(7).len(), 4);
+        assert!(ListPages::<()>::new([]).into_page(3).is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::game::dialogue::DialogueEffect;
+use crate::game::primary_logic::GameData;
+use crate::utils::ron_asset::RonParseError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+
+///
+/// Version of the item format this build reads.
+///
+pub const ITEM_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum ItemError {
+    #[error("could not read items: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] RonParseError),
+    #[error("items have format version {0}, but this build only supports up to {ITEM_FORMAT_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("item id \"{0}\" is used more than once")]
+    DuplicateId(String),
+}
+
+///
+/// A set of items as written in a `.items.ron` file.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemsFile {
+    pub version: u32,
+    pub items: Vec<ItemDef>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemDef {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Image shown in the inventory, relative to the assets folder.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Whether several of the item are listed as one entry with a count, rather than one entry each.
+    #[serde(default = "default_stackable")]
+    pub stackable: bool,
+    /// What happens when the item is used from the inventory, on top of what secrets and puzzles
+    /// react to.
+    #[serde(default)]
+    pub on_use: Option<ItemUse>,
+}
+
+fn default_stackable() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ItemUse {
+    /// Whether using the item uses one up.
+    pub consume: bool,
+    /// The same effects a dialogue can have.
+    pub effects: Vec<DialogueEffect>,
+    /// Shown above the player when the item is used.
+    pub message: String,
+}
+
+impl ItemUse {
+    ///
+    /// Uses up one of `item` if the use consumes it, then applies the effects.
+    ///
+    pub fn apply(&self, item: &str, game_data: &mut GameData) {
+        if self.consume {
+            game_data.take_item(item);
+        }
+        for effect in &self.effects {
+            effect.apply(game_data);
+        }
+    }
+}
+
+impl ItemsFile {
+    pub fn validate(&self) -> Result<(), ItemError> {
+        if self.version > ITEM_FORMAT_VERSION {
+            return Err(ItemError::UnsupportedVersion(self.version));
+        }
+        let mut ids = HashSet::new();
+        for item in &self.items {
+            if !ids.insert(item.id.as_str()) {
+                return Err(ItemError::DuplicateId(item.id.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ron_asset::parse_ron;
+
+    fn items(text: &str) -> ItemsFile {
+        parse_ron::<ItemsFile>(&format!("(version: 1, items: [{text}])")).unwrap()
+    }
+
+    #[test]
+    fn shipped_items_are_valid() {
+        let file = parse_ron::<ItemsFile>(include_str!("../../../assets/items/core.items.ron")).unwrap();
+        file.validate().unwrap();
+    }
+
+    #[test]
+    fn only_id_and_name_are_needed() {
+        let file = items(r#"(id: "pebble", name: "Pebble")"#);
+        let item = &file.items[0];
+        assert!(item.stackable);
+        assert!(item.description.is_empty());
+        assert!(item.icon.is_none());
+        assert!(item.on_use.is_none());
+    }
+
+    #[test]
+    fn using_applies_effects_and_consumes() {
+        let file = items(
+            r#"
+            (id: "berry", name: "Berry", on_use: Some((consume: true, effects: [SetFlag("fed"), GiveItem("seed")]))),
+            (id: "lamp", name: "Lamp", on_use: Some((effects: [SetFlag("lit")]))),
+            "#,
+        );
+        let mut game_data = GameData::default();
+        game_data.give_item("berry");
+        game_data.give_item("berry");
+        game_data.give_item("lamp");
+
+        file.items[0].on_use.as_ref().unwrap().apply("berry", &mut game_data);
+        assert_eq!(game_data.inventory.get("berry"), Some(&1));
+        assert!(game_data.has_item("seed"));
+        assert!(game_data.flags.contains("fed"));
+
+        file.items[1].on_use.as_ref().unwrap().apply("lamp", &mut game_data);
+        assert!(game_data.has_item("lamp"));
+        assert!(game_data.flags.contains("lit"));
+    }
+
+    #[test]
+    fn duplicate_ids_and_future_versions_are_rejected() {
+        let file = items(r#"(id: "pebble", name: "Pebble"), (id: "pebble", name: "Other Pebble")"#);
+        assert!(matches!(file.validate(), Err(ItemError::DuplicateId(id)) if id == "pebble"));
+
+        let file = ItemsFile {
+            version: ITEM_FORMAT_VERSION + 1,
+            items: Vec::new(),
+        };
+        assert!(matches!(file.validate(), Err(ItemError::UnsupportedVersion(_))));
+    }
+}
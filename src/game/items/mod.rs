@@ -0,0 +1,143 @@
+use crate::game::interactables::spawn_speech;
+use crate::game::player::{Facing, GridPosition, Player};
+use crate::game::primary_logic::{GameData, GameState};
+use crate::game::rewind::{Rewind, Snapshot};
+use crate::game::saves::SaveGame;
+use crate::utils::ron_asset::{RonAsset, RonLoader};
+use bevy::asset::LoadedFolder;
+use bevy::prelude::*;
+
+mod format;
+mod screen;
+
+pub use format::*;
+
+///
+/// Items defined in `.items.ron` files in `assets/items`, and the inventory screen, opened with I while playing.
+///
+#[derive(Default)]
+pub struct ItemsPlugin;
+
+///
+/// A validated set of items.
+///
+#[derive(Asset, TypePath, Debug)]
+pub struct ItemSet {
+    pub file: ItemsFile,
+}
+
+impl RonAsset for ItemSet {
+    type File = ItemsFile;
+    type Error = ItemError;
+
+    const EXTENSION: &'static str = "items.ron";
+
+    fn from_file(file: ItemsFile) -> Result<ItemSet, ItemError> {
+        file.validate()?;
+        Ok(ItemSet { file })
+    }
+}
+
+///
+/// Keeps every item set in `assets/items` loaded, so items can be looked up by id.
+///
+#[derive(Resource)]
+pub struct ItemCatalog {
+    _folder: Handle<LoadedFolder>,
+}
+
+impl ItemCatalog {
+    pub fn get<'a>(item_sets: &'a Assets<ItemSet>, id: &str) -> Option<&'a ItemDef> {
+        item_sets
+            .iter()
+            .find_map(|(_, item_set)| item_set.file.items.iter().find(|item| item.id == id))
+    }
+
+    ///
+    /// The item's name, or its id if it isn't defined anywhere.
+    ///
+    pub fn name(item_sets: &Assets<ItemSet>, id: &str) -> String {
+        Self::get(item_sets, id).map_or_else(|| id.to_string(), |item| item.name.clone())
+    }
+}
+
+///
+/// The [GameData] flag set once an item has been used, which puzzles read with
+/// [SignalInput::UsedItem](crate::game::level::SignalInput::UsedItem).
+///
+pub fn used_flag(item: &str) -> String {
+    format!("used:{item}")
+}
+
+///
+/// Sent when the player uses an item from the inventory, after its own effects have been applied and
+/// its [used_flag] set. Secrets which need an item used in a particular place react to this.
+///
+#[derive(Event, Clone, Debug)]
+pub struct ItemUsed {
+    pub item: String,
+    /// The tile the player is standing on.
+    pub position: IVec2,
+    /// The direction the player is facing.
+    pub facing: IVec2,
+}
+
+///
+/// Asks for an item in the inventory to be used.
+///
+#[derive(Event, Clone, Debug)]
+struct UseItem(String);
+
+impl Plugin for ItemsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ItemSet>();
+        app.init_asset_loader::<RonLoader<ItemSet>>();
+        app.add_event::<ItemUsed>();
+        app.add_event::<UseItem>();
+        app.add_systems(Startup, load_catalog);
+        app.add_systems(Update, use_items.run_if(in_state(GameState::InGame).and_then(on_event::<UseItem>())));
+        app.add_plugins(screen::InventoryScreenPlugin);
+    }
+}
+
+fn load_catalog(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ItemCatalog {
+        _folder: asset_server.load_folder("items"),
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn use_items(
+    mut commands: Commands,
+    mut events: EventReader<UseItem>,
+    item_sets: Res<Assets<ItemSet>>,
+    players: Query<(&GridPosition, &Facing), With<Player>>,
+    mut game_data: ResMut<GameData>,
+    mut used: EventWriter<ItemUsed>,
+    mut save: EventWriter<SaveGame>,
+    mut rewind: ResMut<Rewind>,
+) {
+    let Some((grid_position, facing)) = players.iter().next() else {
+        return;
+    };
+    for UseItem(id) in events.read() {
+        if !game_data.has_item(id) {
+            continue;
+        }
+        let before = Snapshot::of(&game_data);
+        game_data.flags.insert(used_flag(id));
+        if let Some(on_use) = ItemCatalog::get(&item_sets, id).and_then(|item| item.on_use.as_ref()) {
+            on_use.apply(id, &mut game_data);
+            if !on_use.message.is_empty() {
+                spawn_speech(&mut commands, grid_position.0, on_use.message.clone());
+            }
+        }
+        rewind.history.record_since(&before, &game_data);
+        save.send(SaveGame);
+        used.send(ItemUsed {
+            item: id.clone(),
+            position: grid_position.0,
+            facing: facing.0,
+        });
+    }
+}
@@ -0,0 +1,264 @@
+use super::{ItemCatalog, ItemSet, UseItem};
+use crate::game::internal::{
+    menu_button, menu_text, spawn_page_controls, ListPages, ScriptControlled, LIST_TOP, UI_Z,
+};
+use crate::game::primary_logic::{GameData, InGameScreen};
+use crate::utils::buttoning::{ButtonClickEvent, ButtonClickType, Clickable, Hovered};
+use crate::utils::responsive::{ResponsivePosition, ResponsiveValue};
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::Text2dBounds;
+
+///
+/// The inventory, listing what the player carries with the selected item's details beside it.
+///
+pub struct InventoryScreenPlugin;
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Component)]
+enum InventoryButton {
+    Item(String),
+    Use(String),
+    /// Turns the list to this page.
+    Page(usize),
+    Close,
+}
+
+///
+/// The item whose details are shown.
+///
+#[derive(Resource, Default)]
+struct InventorySelection(Option<String>);
+
+///
+/// The page of the list shown, when the player carries too much to fit on one.
+///
+#[derive(Resource, Default)]
+struct InventoryPage(usize);
+
+///
+/// Marks everything which is rebuilt when the selection or the inventory changes.
+///
+#[derive(Default, Clone, Component)]
+struct InventoryContent;
+
+const TITLE_COLOR: Color = Color::srgb(1.4, 2.1, 2.4);
+const ITEM_COLOR: Color = Color::srgb(2.4, 2.1, 1.4);
+const SELECTED_COLOR: Color = Color::srgb(2.8, 2.4, 1.2);
+const DETAIL_COLOR: Color = Color::srgb(1.4, 1.4, 1.4);
+const EMPTY_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+const USE_COLOR: Color = Color::srgb(1.4, 2.4, 1.4);
+const CLOSE_COLOR: Color = Color::srgb(2.1, 1.4, 1.4);
+const BACKDROP_COLOR: Color = Color::srgba(0.02, 0.02, 0.03, 0.9);
+const ICON_SIZE: f32 = 96.0;
+
+impl Plugin for InventoryScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InventorySelection>();
+        app.init_resource::<InventoryPage>();
+        app.add_systems(Update, open_inventory.run_if(in_state(InGameScreen::Playing)));
+        app.add_systems(OnEnter(InGameScreen::Inventory), on_enter);
+        app.add_systems(
+            Update,
+            (inventory_button_clicks, close_on_key, rebuild_content)
+                .chain()
+                .run_if(in_state(InGameScreen::Inventory)),
+        );
+    }
+}
+
+fn open_inventory(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<InGameScreen>>) {
+    if keys.just_pressed(KeyCode::KeyI) {
+        next_state.set(InGameScreen::Inventory);
+    }
+}
+
+fn on_enter(
+    mut commands: Commands,
+    mut selection: ResMut<InventorySelection>,
+    page: Res<InventoryPage>,
+    item_sets: Res<Assets<ItemSet>>,
+    game_data: Res<GameData>,
+    asset_server: Res<AssetServer>,
+) {
+    // Keep the last selection if the item is still there.
+    if selection.0.as_ref().is_some_and(|id| !game_data.has_item(id)) {
+        selection.0 = None;
+    }
+
+    commands.spawn((
+        StateScoped(InGameScreen::Inventory),
+        SpriteBundle {
+            sprite: Sprite {
+                color: BACKDROP_COLOR,
+                custom_size: Some(Vec2::splat(10000.0)),
+                ..default()
+            },
+            ..default()
+        },
+        ResponsivePosition::new(
+            ResponsiveValue::Percentage(50.0),
+            ResponsiveValue::Percentage(50.0),
+            ResponsiveValue::Absolute(UI_Z - 1.0),
+        ),
+    ));
+    spawn_content(&mut commands, &selection, page.0, &item_sets, &game_data, &asset_server);
+}
+
+fn spawn_entry<'a>(commands: &'a mut Commands, bundle: impl Bundle) -> EntityCommands<'a> {
+    commands.spawn((StateScoped(InGameScreen::Inventory), InventoryContent, bundle))
+}
+
+fn spawn_content(
+    commands: &mut Commands,
+    selection: &InventorySelection,
+    page: usize,
+    item_sets: &Assets<ItemSet>,
+    game_data: &GameData,
+    asset_server: &AssetServer,
+) {
+    spawn_entry(commands, menu_text("Inventory", TITLE_COLOR, 48.0, 50.0, 90.0, Anchor::Center));
+
+    if game_data.inventory.is_empty() {
+        spawn_entry(commands, menu_text("Nothing yet.", EMPTY_COLOR, 24.0, 10.0, LIST_TOP, Anchor::CenterLeft));
+    }
+
+    let rows = game_data.inventory.iter().flat_map(|(id, count)| {
+        let item = ItemCatalog::get(item_sets, id);
+        let name = item.map_or(id.as_str(), |item| item.name.as_str());
+        let stackable = item.is_none_or(|item| item.stackable);
+        // Stackable items share one row, the rest get a row each.
+        let rows = if stackable { vec![format!("{name}   x{count}")] } else { vec![name.to_string(); *count as usize] };
+        rows.into_iter().map(move |label| ((id, label), 5.0))
+    });
+    let pages = ListPages::new(rows);
+    let last_page = pages.last_page();
+
+    for ((id, label), y) in pages.into_page(page) {
+        let color = if selection.0.as_ref() == Some(id) {
+            SELECTED_COLOR
+        } else {
+            ITEM_COLOR
+        };
+        spawn_entry(
+            commands,
+            (
+                menu_button(label, color, 24.0, 10.0, y, Anchor::CenterLeft),
+                InventoryButton::Item(id.clone()),
+            ),
+        );
+    }
+    spawn_page_controls(
+        commands,
+        (StateScoped(InGameScreen::Inventory), InventoryContent),
+        page,
+        last_page,
+        InventoryButton::Page,
+    );
+
+    if let Some(id) = &selection.0 {
+        let item = ItemCatalog::get(item_sets, id);
+        let name = item.map_or(id.as_str(), |item| item.name.as_str());
+        spawn_entry(commands, menu_text(name, SELECTED_COLOR, 36.0, 55.0, 80.0, Anchor::CenterLeft));
+        if let Some(icon) = item.and_then(|item| item.icon.as_ref()) {
+            spawn_entry(
+                commands,
+                (
+                    SpriteBundle {
+                        texture: asset_server.load(icon.clone()),
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::splat(ICON_SIZE)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ResponsivePosition::new(
+                        ResponsiveValue::Percentage(90.0),
+                        ResponsiveValue::Percentage(80.0),
+                        ResponsiveValue::Absolute(UI_Z),
+                    ),
+                ),
+            );
+        }
+        if let Some(description) = item.map(|item| item.description.clone()).filter(|text| !text.is_empty()) {
+            spawn_entry(commands, menu_text(description, DETAIL_COLOR, 22.0, 55.0, 72.0, Anchor::TopLeft)).insert(
+                Text2dBounds {
+                    size: Vec2::new(480.0, f32::INFINITY),
+                },
+            );
+        }
+        spawn_entry(
+            commands,
+            (
+                menu_button("Use", USE_COLOR, 30.0, 55.0, 40.0, Anchor::CenterLeft),
+                InventoryButton::Use(id.clone()),
+            ),
+        );
+    }
+
+    spawn_entry(
+        commands,
+        (
+            menu_button("Close", CLOSE_COLOR, 30.0, 50.0, 8.0, Anchor::Center),
+            InventoryButton::Close,
+        ),
+    );
+}
+
+#[allow(clippy::type_complexity)]
+fn inventory_button_clicks(
+    query: Query<&InventoryButton, (With<Hovered>, With<Clickable>, Without<ScriptControlled>)>,
+    mut event_reader: EventReader<ButtonClickEvent>,
+    mut selection: ResMut<InventorySelection>,
+    mut page: ResMut<InventoryPage>,
+    mut use_item: EventWriter<UseItem>,
+    mut next_state: ResMut<NextState<InGameScreen>>,
+) {
+    for event in event_reader.read() {
+        if event.mouse_button != MouseButton::Left || event.click_type != ButtonClickType::Pressed {
+            continue;
+        }
+
+        if let Ok(button) = query.get(event.entity) {
+            match button {
+                InventoryButton::Item(id) => {
+                    if selection.0.as_ref() != Some(id) {
+                        selection.0 = Some(id.clone());
+                    }
+                }
+                InventoryButton::Use(id) => {
+                    // Items are used out in the world, where secrets and puzzles can react to them.
+                    use_item.send(UseItem(id.clone()));
+                    next_state.set(InGameScreen::Playing);
+                }
+                InventoryButton::Page(number) => page.0 = *number,
+                InventoryButton::Close => next_state.set(InGameScreen::Playing),
+            }
+        }
+    }
+}
+
+fn close_on_key(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<InGameScreen>>) {
+    if keys.any_just_pressed([KeyCode::Escape, KeyCode::KeyI]) {
+        next_state.set(InGameScreen::Playing);
+    }
+}
+
+fn rebuild_content(
+    mut commands: Commands,
+    selection: Res<InventorySelection>,
+    page: Res<InventoryPage>,
+    item_sets: Res<Assets<ItemSet>>,
+    game_data: Res<GameData>,
+    asset_server: Res<AssetServer>,
+    content: Query<Entity, With<InventoryContent>>,
+) {
+    let changed = (selection.is_changed() && !selection.is_added()) || (page.is_changed() && !page.is_added());
+    if !changed {
+        return;
+    }
+    for entity in content.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_content(&mut commands, &selection, page.0, &item_sets, &game_data, &asset_server);
+}
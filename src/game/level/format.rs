@@ -0,0 +1,847 @@
+use crate::game::world::{Tile, TileMap};
+use crate::utils::ron_asset::RonParseError;
+use bevy::math::{IRect, IVec2};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use thiserror::Error;
+
+///
+/// Version of the level format this build reads. Levels declare the version they were written for.
+///
+pub const LEVEL_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum LevelError {
+    #[error("could not read level: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] RonParseError),
+    #[error("level has format version {0}, but this build only supports 1 to {LEVEL_FORMAT_VERSION}")]
+    UnsupportedVersion(u32),
+    /// `row` and `column` count from 1, like the line and column of a [LevelError::Parse].
+    #[error("unknown tile '{tile}' in tiles row {row}, column {column}")]
+    UnknownTile { tile: char, row: usize, column: usize },
+    #[error("{kind} id \"{id}\" is used more than once")]
+    DuplicateId { kind: &'static str, id: String },
+    #[error("{from} refers to \"{target}\", which does not exist")]
+    MissingReference { from: String, target: String },
+    #[error("{what} at ({x}, {y}) is not on a walkable tile")]
+    NotWalkable { what: String, x: i32, y: i32 },
+    #[error("passage secret \"{id}\" at ({x}, {y}) is not on a hidden tile")]
+    NotHidden { id: String, x: i32, y: i32 },
+    #[error("secret \"{0}\" can never be discovered")]
+    Undiscoverable(String),
+    #[error("{kind} at ({x}, {y}) needs an id to remember its state")]
+    MissingId { kind: String, x: i32, y: i32 },
+    #[error("signal \"{0}\" depends on itself")]
+    CyclicSignal(String),
+    #[error("signal \"{0}\" has a negative hold")]
+    NegativeHold(String),
+    #[error("routine of npc \"{0}\" has no points, a negative wait or an hour outside 0 to 24")]
+    InvalidRoutine(String),
+    #[error("hazard \"{0}\" has no damage, a sight of 0 or a negative rearm time")]
+    InvalidHazard(String),
+    #[error("{kind} at ({x}, {y}) needs a \"{property}\" property")]
+    MissingProperty {
+        kind: String,
+        property: &'static str,
+        x: i32,
+        y: i32,
+    },
+}
+
+///
+/// A level as written in a `.level.ron` file.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelFile {
+    pub version: u32,
+    pub id: String,
+    #[serde(default)]
+    pub metadata: LevelMetadata,
+    ///
+    /// Extra tile characters on top of the defaults from [Tile::from_char].
+    ///
+    #[serde(default)]
+    pub legend: BTreeMap<char, Tile>,
+    /// Rows of tile characters, top row first. Spaces leave a wall.
+    pub tiles: Vec<String>,
+    pub spawn: [i32; 2],
+    #[serde(default)]
+    pub entities: Vec<LevelEntity>,
+    #[serde(default)]
+    pub secrets: Vec<LevelSecret>,
+    #[serde(default)]
+    pub triggers: Vec<LevelTrigger>,
+    #[serde(default)]
+    pub signals: Vec<LevelSignal>,
+    #[serde(default)]
+    pub routines: Vec<NpcRoutine>,
+    #[serde(default)]
+    pub hazards: Vec<LevelHazard>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelMetadata {
+    pub name: String,
+    pub author: String,
+    pub description: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelEntity {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub kind: String,
+    pub position: [i32; 2],
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+///
+/// The kinds of [LevelEntity] the player can interact with, by their `kind` name. Any of them with a
+/// `dialogue` property starts that conversation instead.
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum InteractableKind {
+    /// Shows its `text` property.
+    Signpost,
+    /// Gives its `item` property the first time it is opened.
+    Chest,
+    /// Switches between on and off.
+    Lever,
+    /// Says its `text` property.
+    Npc,
+    /// Gives its `item` property when walked over or interacted with, then disappears.
+    Item,
+    /// Blocks the way until its `signal` turns on, or until it is unlocked with its `key` item.
+    Door,
+}
+
+impl InteractableKind {
+    pub fn from_name(name: &str) -> Option<InteractableKind> {
+        match name {
+            "signpost" => Some(InteractableKind::Signpost),
+            "chest" => Some(InteractableKind::Chest),
+            "lever" => Some(InteractableKind::Lever),
+            "npc" => Some(InteractableKind::Npc),
+            "item" => Some(InteractableKind::Item),
+            "door" => Some(InteractableKind::Door),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Whether the state of this kind is kept in the save, which needs the entity to have an id.
+    ///
+    pub fn has_state(self) -> bool {
+        matches!(
+            self,
+            InteractableKind::Chest | InteractableKind::Lever | InteractableKind::Item | InteractableKind::Door
+        )
+    }
+
+    pub fn required_properties(self) -> &'static [&'static str] {
+        match self {
+            InteractableKind::Item => &["item"],
+            _ => &[],
+        }
+    }
+}
+
+///
+/// How an `npc` entity walks around the level. NPCs without one stand still.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NpcRoutine {
+    /// Id of the `npc` entity.
+    pub npc: String,
+    pub kind: RoutineKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RoutineKind {
+    /// Walks to each point in turn and then back to the first, waiting `wait` seconds at every point.
+    Patrol {
+        points: Vec<[i32; 2]>,
+        #[serde(default)]
+        wait: f32,
+    },
+    /// Walks to the stop which started last, by the hour of the in-game day.
+    Schedule { stops: Vec<ScheduleStop> },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleStop {
+    /// Hour of the day, from 0 up to 24, the NPC sets off for this stop.
+    pub hour: f32,
+    pub position: [i32; 2],
+}
+
+impl RoutineKind {
+    ///
+    /// Every tile the routine sends its NPC to.
+    ///
+    pub fn positions(&self) -> Vec<[i32; 2]> {
+        match self {
+            RoutineKind::Patrol { points, .. } => points.clone(),
+            RoutineKind::Schedule { stops } => stops.iter().map(|stop| stop.position).collect(),
+        }
+    }
+}
+
+///
+/// Something on the path which hurts the player.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LevelHazard {
+    pub id: String,
+    pub position: [i32; 2],
+    /// Health the player loses when it gets them.
+    #[serde(default = "default_damage")]
+    pub damage: u32,
+    pub kind: HazardKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum HazardKind {
+    /// Walks its `patrol` (or stands guard without one) and chases the player once they are in sight,
+    /// up to `sight` tiles ahead of it.
+    Guardian {
+        #[serde(default)]
+        patrol: Vec<[i32; 2]>,
+        #[serde(default = "default_sight")]
+        sight: i32,
+        #[serde(default = "default_guardian_facing")]
+        facing: FacingDirection,
+    },
+    /// Springs when stepped on, and is ready again after `rearm` seconds.
+    Trap {
+        #[serde(default = "default_rearm")]
+        rearm: f32,
+    },
+}
+
+fn default_damage() -> u32 {
+    1
+}
+
+fn default_sight() -> i32 {
+    5
+}
+
+fn default_guardian_facing() -> FacingDirection {
+    FacingDirection::Down
+}
+
+fn default_rearm() -> f32 {
+    3.0
+}
+
+///
+/// The `kind` of a [LevelEntity] which is on while something stands on it.
+///
+pub const PRESSURE_PLATE: &str = "plate";
+
+///
+/// The `kind` of a [LevelEntity] which autosaves and becomes the respawn point when the player reaches it.
+///
+pub const CHECKPOINT: &str = "checkpoint";
+
+impl LevelEntity {
+    pub fn interactable(&self) -> Option<InteractableKind> {
+        InteractableKind::from_name(&self.kind)
+    }
+
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(String::as_str)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelSecret {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub kind: SecretKind,
+    pub position: [i32; 2],
+    #[serde(default)]
+    pub condition: SecretCondition,
+    /// Shown when the secret is found, e.g. the words of a lore fragment.
+    #[serde(default)]
+    pub text: String,
+    /// Item given to the player when the secret is found.
+    #[serde(default)]
+    pub item: Option<String>,
+    /// Shown in the codex while the secret is undiscovered, once `hint_after` other secrets in the level have been found.
+    #[serde(default)]
+    pub hint: String,
+    #[serde(default)]
+    pub hint_after: usize,
+    /// More detailed hints after `hint`, given one at a time when the player asks for one.
+    #[serde(default)]
+    pub hints: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum SecretKind {
+    ///
+    /// Hidden tiles which become path. Every hidden tile connected to the secret's position is revealed.
+    ///
+    #[default]
+    Passage,
+    BuriedItem,
+    Lore,
+}
+
+///
+/// What the player has to do to discover a secret.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SecretCondition {
+    /// Walking within `radius` tiles (counted along the grid) of the secret.
+    Proximity { radius: i32 },
+    /// Interacting with the secret while standing on or next to it, optionally only from one side.
+    Interact {
+        #[serde(default)]
+        facing: Option<FacingDirection>,
+    },
+    /// Using `item` from the inventory while standing on or next to the secret.
+    UseItem { item: String },
+    /// Stepping on each of `steps` in order. Stepping on one out of order starts over.
+    Sequence { steps: Vec<[i32; 2]> },
+    /// Entering a trigger which targets the secret.
+    Triggered,
+    /// The signal with this id turning on.
+    Signal { signal: String },
+}
+
+impl Default for SecretCondition {
+    fn default() -> Self {
+        Self::Proximity { radius: 1 }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum FacingDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl FacingDirection {
+    pub fn to_ivec2(self) -> IVec2 {
+        match self {
+            FacingDirection::Up => IVec2::Y,
+            FacingDirection::Down => IVec2::NEG_Y,
+            FacingDirection::Left => IVec2::NEG_X,
+            FacingDirection::Right => IVec2::X,
+        }
+    }
+}
+
+///
+/// A rectangle of tiles which does something to its `targets` (entity or secret ids) when entered.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelTrigger {
+    pub id: String,
+    pub min: [i32; 2],
+    pub max: [i32; 2],
+    #[serde(default)]
+    pub targets: Vec<String>,
+}
+
+///
+/// A named wire in the level's puzzle graph. Doors and secrets listen to signals, and signals can be built
+/// from other signals.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelSignal {
+    pub id: String,
+    pub input: SignalInput,
+    /// Seconds the signal stays on after its input turns off, for timed gates.
+    #[serde(default)]
+    pub hold: f32,
+    /// Once on, the signal stays on for good, even across saves.
+    #[serde(default)]
+    pub latch: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SignalInput {
+    /// On while the interactable with this id is on: a lever thrown, a chest opened or a door open.
+    Switch(String),
+    /// On while something stands on the pressure plate with this id.
+    Plate(String),
+    /// On while the player carries at least one of this item.
+    HasItem(String),
+    /// On once the player has used this item from the inventory.
+    UsedItem(String),
+    /// On while this flag is set.
+    Flag(String),
+    /// On while the signal with this id is on.
+    Signal(String),
+    Not(Box<SignalInput>),
+    All(Vec<SignalInput>),
+    Any(Vec<SignalInput>),
+}
+
+impl SignalInput {
+    ///
+    /// Every input nested in this one, including itself.
+    ///
+    pub fn walk(&self) -> Vec<&SignalInput> {
+        let mut inputs = vec![self];
+        match self {
+            SignalInput::Not(input) => inputs.extend(input.walk()),
+            SignalInput::All(children) | SignalInput::Any(children) => {
+                inputs.extend(children.iter().flat_map(SignalInput::walk));
+            }
+            _ => {}
+        }
+        inputs
+    }
+}
+
+impl LevelFile {
+    pub fn tile_for(&self, c: char) -> Option<Tile> {
+        self.legend.get(&c).copied().or_else(|| Tile::from_char(c))
+    }
+
+    ///
+    /// Builds the [TileMap] for this level. The bottom row of `tiles` is y = 0.
+    ///
+    pub fn build_map(&self) -> Result<TileMap, LevelError> {
+        let mut map = TileMap::new();
+        let height = self.tiles.len() as i32;
+        for (row, line) in self.tiles.iter().enumerate() {
+            let y = height - 1 - row as i32;
+            for (column, c) in line.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let tile = self.tile_for(c).ok_or(LevelError::UnknownTile {
+                    tile: c,
+                    row: row + 1,
+                    column: column + 1,
+                })?;
+                map.set(IVec2::new(column as i32, y), tile);
+            }
+        }
+        Ok(map)
+    }
+
+    ///
+    /// The tiles covered by `tiles`, from (0, 0) to the longest row and the number of rows.
+    ///
+    pub fn bounds(&self) -> IRect {
+        let width = self.tiles.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        IRect::new(0, 0, width as i32, self.tiles.len() as i32)
+    }
+
+    ///
+    /// Checks everything which can't be expressed in the file's types: versions, unique ids,
+    /// references between objects and that the spawn point can be stood on.
+    ///
+    pub fn validate(&self, map: &TileMap) -> Result<(), LevelError> {
+        if self.version == 0 || self.version > LEVEL_FORMAT_VERSION {
+            return Err(LevelError::UnsupportedVersion(self.version));
+        }
+
+        let mut ids = HashSet::new();
+        let entity_ids = self.entities.iter().filter_map(|e| e.id.as_ref()).map(|id| ("entity", id));
+        let secret_ids = self.secrets.iter().map(|s| ("secret", &s.id));
+        let trigger_ids = self.triggers.iter().map(|t| ("trigger", &t.id));
+        let signal_ids = self.signals.iter().map(|s| ("signal", &s.id));
+        let hazard_ids = self.hazards.iter().map(|h| ("hazard", &h.id));
+        for (kind, id) in entity_ids
+            .chain(secret_ids)
+            .chain(trigger_ids)
+            .chain(signal_ids)
+            .chain(hazard_ids)
+        {
+            if !ids.insert(id.as_str()) {
+                return Err(LevelError::DuplicateId { kind, id: id.clone() });
+            }
+        }
+
+        for trigger in &self.triggers {
+            for target in &trigger.targets {
+                if !ids.contains(target.as_str()) {
+                    return Err(LevelError::MissingReference {
+                        from: format!("trigger \"{}\"", trigger.id),
+                        target: target.clone(),
+                    });
+                }
+            }
+        }
+
+        let [x, y] = self.spawn;
+        if !map.is_walkable(IVec2::new(x, y)) {
+            return Err(LevelError::NotWalkable {
+                what: "spawn".to_string(),
+                x,
+                y,
+            });
+        }
+
+        for entity in &self.entities {
+            let [x, y] = entity.position;
+            if entity.kind == PRESSURE_PLATE && entity.id.is_none() {
+                return Err(LevelError::MissingId {
+                    kind: entity.kind.clone(),
+                    x,
+                    y,
+                });
+            }
+            if entity.kind == CHECKPOINT && !map.is_walkable(IVec2::new(x, y)) {
+                return Err(LevelError::NotWalkable {
+                    what: CHECKPOINT.to_string(),
+                    x,
+                    y,
+                });
+            }
+            let Some(kind) = entity.interactable() else {
+                continue;
+            };
+            if entity.id.is_none() && kind.has_state() {
+                return Err(LevelError::MissingId {
+                    kind: entity.kind.clone(),
+                    x,
+                    y,
+                });
+            }
+            if let Some(property) = kind.required_properties().iter().find(|p| entity.property(p).is_none()) {
+                return Err(LevelError::MissingProperty {
+                    kind: entity.kind.clone(),
+                    property,
+                    x,
+                    y,
+                });
+            }
+            if kind == InteractableKind::Door {
+                self.validate_door(entity, map)?;
+            }
+        }
+
+        self.validate_signals()?;
+        self.validate_routines(map)?;
+        self.validate_hazards(map)?;
+        for secret in &self.secrets {
+            self.validate_secret(secret, map)?;
+        }
+        Ok(())
+    }
+
+    fn validate_door(&self, door: &LevelEntity, map: &TileMap) -> Result<(), LevelError> {
+        let [x, y] = door.position;
+        if door.property("signal").is_none() && door.property("key").is_none() {
+            return Err(LevelError::MissingProperty {
+                kind: door.kind.clone(),
+                property: "signal",
+                x,
+                y,
+            });
+        }
+        if let Some(signal) = door.property("signal") {
+            if !self.signals.iter().any(|s| s.id == signal) {
+                return Err(LevelError::MissingReference {
+                    from: format!("door at ({x}, {y})"),
+                    target: signal.to_string(),
+                });
+            }
+        }
+        // The door's tile is what the player walks on once it opens.
+        if !map.is_walkable(IVec2::new(x, y)) {
+            return Err(LevelError::NotWalkable {
+                what: format!("door at ({x}, {y})"),
+                x,
+                y,
+            });
+        }
+        Ok(())
+    }
+
+    ///
+    /// Checks that signals only refer to things which exist and never depend on themselves.
+    ///
+    fn validate_signals(&self) -> Result<(), LevelError> {
+        let entity = |id: &str| self.entities.iter().find(|e| e.id.as_deref() == Some(id));
+        for signal in &self.signals {
+            if signal.hold < 0.0 {
+                return Err(LevelError::NegativeHold(signal.id.clone()));
+            }
+            for input in signal.input.walk() {
+                let (target, exists) = match input {
+                    SignalInput::Switch(id) => {
+                        let kind = entity(id).and_then(LevelEntity::interactable);
+                        (id, kind.is_some_and(InteractableKind::has_state))
+                    }
+                    SignalInput::Plate(id) => (id, entity(id).is_some_and(|e| e.kind == PRESSURE_PLATE)),
+                    SignalInput::Signal(id) => (id, self.signals.iter().any(|s| s.id == *id)),
+                    _ => continue,
+                };
+                if !exists {
+                    return Err(LevelError::MissingReference {
+                        from: format!("signal \"{}\"", signal.id),
+                        target: target.clone(),
+                    });
+                }
+            }
+        }
+        for signal in &self.signals {
+            if self.signal_depends_on(&signal.id, &signal.id, &mut HashSet::new()) {
+                return Err(LevelError::CyclicSignal(signal.id.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Whether the signal `from` reads `target`, directly or through other signals.
+    ///
+    fn signal_depends_on<'a>(&'a self, from: &str, target: &str, visited: &mut HashSet<&'a str>) -> bool {
+        let Some(signal) = self.signals.iter().find(|s| s.id == from) else {
+            return false;
+        };
+        signal.input.walk().into_iter().any(|input| match input {
+            SignalInput::Signal(id) => id == target || (visited.insert(id) && self.signal_depends_on(id, target, visited)),
+            _ => false,
+        })
+    }
+
+    fn validate_routines(&self, map: &TileMap) -> Result<(), LevelError> {
+        let mut npcs = HashSet::new();
+        for routine in &self.routines {
+            if !npcs.insert(routine.npc.as_str()) {
+                return Err(LevelError::DuplicateId {
+                    kind: "routine",
+                    id: routine.npc.clone(),
+                });
+            }
+            let npc = self.entities.iter().find(|e| e.id.as_ref() == Some(&routine.npc));
+            if npc.and_then(LevelEntity::interactable) != Some(InteractableKind::Npc) {
+                return Err(LevelError::MissingReference {
+                    from: "routine".to_string(),
+                    target: routine.npc.clone(),
+                });
+            }
+
+            let invalid = match &routine.kind {
+                RoutineKind::Patrol { points, wait } => points.is_empty() || *wait < 0.0,
+                RoutineKind::Schedule { stops } => {
+                    stops.is_empty() || stops.iter().any(|stop| !(0.0..24.0).contains(&stop.hour))
+                }
+            };
+            if invalid {
+                return Err(LevelError::InvalidRoutine(routine.npc.clone()));
+            }
+            for [x, y] in routine.kind.positions() {
+                if !map.is_walkable(IVec2::new(x, y)) {
+                    return Err(LevelError::NotWalkable {
+                        what: format!("routine of npc \"{}\"", routine.npc),
+                        x,
+                        y,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_hazards(&self, map: &TileMap) -> Result<(), LevelError> {
+        for hazard in &self.hazards {
+            let (invalid, mut positions) = match &hazard.kind {
+                HazardKind::Guardian { patrol, sight, .. } => (*sight <= 0, patrol.clone()),
+                HazardKind::Trap { rearm } => (*rearm < 0.0, Vec::new()),
+            };
+            if invalid || hazard.damage == 0 {
+                return Err(LevelError::InvalidHazard(hazard.id.clone()));
+            }
+            positions.push(hazard.position);
+            for [x, y] in positions {
+                if !map.is_walkable(IVec2::new(x, y)) {
+                    return Err(LevelError::NotWalkable {
+                        what: format!("hazard \"{}\"", hazard.id),
+                        x,
+                        y,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_secret(&self, secret: &LevelSecret, map: &TileMap) -> Result<(), LevelError> {
+        let [x, y] = secret.position;
+        if secret.kind == SecretKind::Passage && map.get(IVec2::new(x, y)) != Some(Tile::Hidden) {
+            return Err(LevelError::NotHidden {
+                id: secret.id.clone(),
+                x,
+                y,
+            });
+        }
+
+        match &secret.condition {
+            SecretCondition::Proximity { radius } if *radius < 0 => {
+                return Err(LevelError::Undiscoverable(secret.id.clone()));
+            }
+            SecretCondition::Sequence { steps } => {
+                if steps.is_empty() {
+                    return Err(LevelError::Undiscoverable(secret.id.clone()));
+                }
+                for (i, [x, y]) in steps.iter().copied().enumerate() {
+                    if !map.is_walkable(IVec2::new(x, y)) {
+                        return Err(LevelError::NotWalkable {
+                            what: format!("step {} of secret \"{}\"", i + 1, secret.id),
+                            x,
+                            y,
+                        });
+                    }
+                }
+            }
+            SecretCondition::Triggered if !self.triggers.iter().any(|t| t.targets.contains(&secret.id)) => {
+                return Err(LevelError::Undiscoverable(secret.id.clone()));
+            }
+            SecretCondition::Signal { signal } if !self.signals.iter().any(|s| s.id == *signal) => {
+                return Err(LevelError::MissingReference {
+                    from: format!("secret \"{}\"", secret.id),
+                    target: signal.clone(),
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ron_asset::parse_ron;
+
+    ///
+    /// A small valid level, with `extra` fields added. Its only hidden tile is at (2, 1).
+    ///
+    fn level(extra: &str) -> LevelFile {
+        let tiles = "[\"#####\", \"#...#\", \"#.?.#\", \"#####\"]";
+        parse_ron::<LevelFile>(&format!(r#"(version: 1, id: "test", tiles: {tiles}, spawn: (1, 2), {extra})"#)).unwrap()
+    }
+
+    fn check(file: &LevelFile) -> Result<(), LevelError> {
+        file.validate(&file.build_map()?)
+    }
+
+    #[test]
+    fn valid_levels_pass() {
+        check(&level(r#"secrets: [(id: "s", name: "S", position: (2, 1))]"#)).unwrap();
+        let shipped = parse_ron::<LevelFile>(include_str!("../../../assets/levels/first_steps.level.ron")).unwrap();
+        check(&shipped).unwrap();
+    }
+
+    #[test]
+    fn parse_errors_have_a_position() {
+        let text = "(\n    version: \"one\",\n)";
+        let Err(RonParseError { line, column, .. }) = parse_ron::<LevelFile>(text) else {
+            panic!("expected a parse error");
+        };
+        assert_eq!((line, column), (2, 14));
+    }
+
+    #[test]
+    fn versions_outside_the_supported_range_are_rejected() {
+        for version in [0, LEVEL_FORMAT_VERSION + 1] {
+            let file = LevelFile { version, ..level("") };
+            assert!(matches!(check(&file), Err(LevelError::UnsupportedVersion(v)) if v == version));
+        }
+    }
+
+    #[test]
+    fn unknown_tiles_count_rows_and_columns_from_1() {
+        let mut file = level("");
+        file.tiles[1] = "#.x.#".to_string();
+        let result = check(&file);
+        assert!(matches!(result, Err(LevelError::UnknownTile { tile: 'x', row: 2, column: 3 })), "{result:?}");
+    }
+
+    #[test]
+    fn ids_are_unique_across_kinds() {
+        let file = level(
+            r#"
+            entities: [(id: Some("a"), kind: "lever", position: (1, 2))],
+            secrets: [(id: "a", name: "A", position: (2, 1))],
+            "#,
+        );
+        assert!(matches!(check(&file), Err(LevelError::DuplicateId { kind: "secret", id }) if id == "a"));
+    }
+
+    #[test]
+    fn references_must_exist() {
+        let file = level(r#"triggers: [(id: "t", min: (1, 1), max: (1, 1), targets: ["nowhere"])]"#);
+        assert!(matches!(check(&file), Err(LevelError::MissingReference { target, .. }) if target == "nowhere"));
+    }
+
+    #[test]
+    fn spawn_must_be_walkable() {
+        let file = LevelFile { spawn: [0, 0], ..level("") };
+        assert!(matches!(check(&file), Err(LevelError::NotWalkable { x: 0, y: 0, .. })));
+    }
+
+    #[test]
+    fn passages_must_be_hidden() {
+        let file = level(r#"secrets: [(id: "s", name: "S", position: (1, 2))]"#);
+        assert!(matches!(check(&file), Err(LevelError::NotHidden { x: 1, y: 2, .. })));
+    }
+
+    #[test]
+    fn secrets_must_be_discoverable() {
+        let file = level(r#"secrets: [(id: "s", name: "S", kind: Lore, position: (1, 2), condition: Triggered)]"#);
+        assert!(matches!(check(&file), Err(LevelError::Undiscoverable(id)) if id == "s"));
+    }
+
+    #[test]
+    fn stateful_entities_need_an_id() {
+        for kind in ["lever", "plate"] {
+            let file = level(&format!(r#"entities: [(kind: "{kind}", position: (1, 2))]"#));
+            assert!(matches!(check(&file), Err(LevelError::MissingId { x: 1, y: 2, .. })), "{kind}");
+        }
+    }
+
+    #[test]
+    fn entities_need_their_properties() {
+        let file = level(r#"entities: [(id: Some("i"), kind: "item", position: (1, 2))]"#);
+        assert!(matches!(check(&file), Err(LevelError::MissingProperty { property: "item", .. })));
+    }
+
+    #[test]
+    fn signals_cant_depend_on_themselves() {
+        let file = level(r#"signals: [(id: "a", input: Signal("b")), (id: "b", input: Not(Signal("a")))]"#);
+        assert!(matches!(check(&file), Err(LevelError::CyclicSignal(_))));
+    }
+
+    #[test]
+    fn signals_cant_hold_for_negative_time() {
+        let file = level(r#"signals: [(id: "a", input: Flag("f"), hold: -1.0)]"#);
+        assert!(matches!(check(&file), Err(LevelError::NegativeHold(id)) if id == "a"));
+    }
+
+    #[test]
+    fn routines_need_somewhere_to_go() {
+        let file = level(
+            r#"
+            entities: [(id: Some("n"), kind: "npc", position: (1, 2))],
+            routines: [(npc: "n", kind: Patrol(points: []))],
+            "#,
+        );
+        assert!(matches!(check(&file), Err(LevelError::InvalidRoutine(npc)) if npc == "n"));
+    }
+
+    #[test]
+    fn hazards_must_do_damage() {
+        let file = level(r#"hazards: [(id: "h", position: (1, 2), damage: 0, kind: Trap(rearm: 1.0))]"#);
+        assert!(matches!(check(&file), Err(LevelError::InvalidHazard(id)) if id == "h"));
+    }
+}
@@ -0,0 +1,161 @@
+use crate::game::primary_logic::{GameData, GameState};
+use crate::game::world::{CurrentMap, MapLoaded, MapSet, TileMap};
+use crate::utils::ron_asset::{RonAsset, RonLoader};
+use bevy::asset::LoadedFolder;
+use bevy::prelude::*;
+
+mod format;
+mod procgen;
+
+pub use format::*;
+pub use procgen::{generate, ProcgenParams, WorldRng};
+
+///
+/// Loads `.level.ron` files from `assets/levels` and builds the [CurrentMap] from them, rebuilding it
+/// whenever the file changes on disk.
+///
+#[derive(Default)]
+pub struct LevelPlugin;
+
+///
+/// The level a new game starts on.
+///
+pub const STARTING_LEVEL: &str = "first_steps";
+
+///
+/// A validated level, ready to be played.
+///
+#[derive(Asset, TypePath, Debug)]
+pub struct Level {
+    pub file: LevelFile,
+    pub map: TileMap,
+}
+
+#[derive(Resource)]
+pub struct CurrentLevel {
+    pub handle: Handle<Level>,
+    applied: bool,
+}
+
+///
+/// Keeps every level in `assets/levels` loaded, so screens like the codex can list what they contain.
+///
+#[derive(Resource)]
+pub struct LevelCatalog {
+    folder: Handle<LoadedFolder>,
+}
+
+impl LevelCatalog {
+    ///
+    /// The loaded levels from `assets/levels`, ordered by id. Generated worlds aren't part of the catalog.
+    ///
+    pub fn levels<'a>(&self, folders: &Assets<LoadedFolder>, levels: &'a Assets<Level>) -> Vec<&'a Level> {
+        let Some(folder) = folders.get(&self.folder) else {
+            return Vec::new();
+        };
+        let mut levels: Vec<&Level> = folder
+            .handles
+            .iter()
+            .filter_map(|handle| levels.get(handle.id().try_typed::<Level>().ok()?))
+            .collect();
+        levels.sort_by(|a, b| a.file.id.cmp(&b.file.id));
+        levels
+    }
+}
+
+impl RonAsset for Level {
+    type File = LevelFile;
+    type Error = LevelError;
+
+    const EXTENSION: &'static str = "level.ron";
+
+    fn from_file(file: LevelFile) -> Result<Level, LevelError> {
+        let map = file.build_map()?;
+        file.validate(&map)?;
+        Ok(Level { file, map })
+    }
+}
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Level>();
+        app.init_asset_loader::<RonLoader<Level>>();
+        app.add_systems(Startup, load_catalog);
+        app.add_systems(OnEnter(GameState::InGame), request_level);
+        app.add_systems(Update, apply_level.in_set(MapSet::Load).run_if(in_state(GameState::InGame)));
+    }
+}
+
+pub fn level_path(id: &str) -> String {
+    format!("levels/{id}.level.ron")
+}
+
+fn load_catalog(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LevelCatalog {
+        folder: asset_server.load_folder("levels"),
+    });
+}
+
+fn request_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut levels: ResMut<Assets<Level>>,
+    mut game_data: ResMut<GameData>,
+) {
+    let generated = game_data.procedural_seed.and_then(|seed| {
+        let mut rng = WorldRng::from_seed(seed);
+        let file = generate(seed, &ProcgenParams::default(), &mut rng.0);
+        commands.insert_resource(rng);
+        Level::from_file(file)
+            .inspect_err(|e| error!("World generated from seed {seed} is invalid: {e}"))
+            .ok()
+    });
+
+    let handle = match generated {
+        Some(level) => {
+            game_data.level = level.file.id.clone();
+            levels.add(level)
+        }
+        None => {
+            if game_data.level.is_empty() || game_data.procedural_seed.is_some() {
+                game_data.level = STARTING_LEVEL.to_string();
+            }
+            asset_server.load(level_path(&game_data.level))
+        }
+    };
+    commands.insert_resource(CurrentLevel { handle, applied: false });
+}
+
+fn apply_level(
+    mut asset_events: EventReader<AssetEvent<Level>>,
+    mut current_level: ResMut<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    mut current_map: ResMut<CurrentMap>,
+    mut game_data: ResMut<GameData>,
+    mut map_loaded: EventWriter<MapLoaded>,
+) {
+    let id = current_level.handle.id();
+    let modified = asset_events
+        .read()
+        .any(|event| matches!(event, AssetEvent::Modified { id: modified } if *modified == id));
+    if current_level.applied && !modified {
+        return;
+    }
+    let Some(level) = levels.get(id) else {
+        return;
+    };
+
+    if current_level.applied {
+        info!("Reloaded level {}", level.file.id);
+    }
+    current_level.applied = true;
+    *current_map = CurrentMap {
+        map: level.map.clone(),
+        spawn: IVec2::from(level.file.spawn),
+        bounds: level.file.bounds(),
+    };
+    game_data.location_name = level.file.metadata.name.clone();
+    let play_time = game_data.play_time;
+    game_data.level_entered.entry(level.file.id.clone()).or_insert(play_time);
+    map_loaded.send(MapLoaded);
+}
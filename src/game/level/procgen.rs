@@ -0,0 +1,521 @@
+use crate::game::level::format::*;
+use crate::game::world::Tile;
+use bevy::math::IVec2;
+use bevy::prelude::Resource;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+
+///
+/// The random number generator for a world. Everything random about a world should be drawn from
+/// this so that the same seed always gives the same world.
+///
+#[derive(Resource, Clone, Debug)]
+pub struct WorldRng(pub ChaCha8Rng);
+
+impl WorldRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProcgenParams {
+    pub width: i32,
+    pub height: i32,
+    /// Side paths leading off the main path, each ending in a small clearing.
+    pub branches: usize,
+    /// Up to this many secrets are placed. There are only fewer when nowhere is left to put one, which the
+    /// default size of map always has room for.
+    pub secrets: usize,
+}
+
+impl Default for ProcgenParams {
+    fn default() -> Self {
+        Self {
+            width: 48,
+            height: 28,
+            branches: 6,
+            secrets: 5,
+        }
+    }
+}
+
+const DIRECTIONS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+const PASSAGE_NAMES: [&str; 4] = ["Overgrown Gap", "Hollow Stone", "Root Tunnel", "Forgotten Way"];
+const ITEM_NAMES: [(&str, &str); 4] = [
+    ("Smooth Pebble", "smooth_pebble"),
+    ("Old Coin", "old_coin"),
+    ("Glass Bead", "glass_bead"),
+    ("Carved Token", "carved_token"),
+];
+const LORE: [(&str, &str); 4] = [
+    ("Faded Carving", "A path drawn in the stone, looping back on itself."),
+    ("Scratched Verse", "Walk slowly. The path remembers those who hurry."),
+    ("Mossy Marker", "A traveller's mark, pointing somewhere that isn't here anymore."),
+    ("Weathered Face", "A face carved into the rock, smiling at nothing."),
+];
+
+///
+/// A rectangle of tiles being carved, with y = 0 at the bottom. Everything outside it, and its outer
+/// ring, is always wall.
+///
+struct Grid {
+    width: i32,
+    height: i32,
+    tiles: Vec<Tile>,
+}
+
+impl Grid {
+    fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            tiles: vec![Tile::Wall; (width * height) as usize],
+        }
+    }
+
+    ///
+    /// Whether `position` can be carved, which keeps a ring of wall around the edge.
+    ///
+    fn carvable(&self, position: IVec2) -> bool {
+        position.x >= 1 && position.y >= 1 && position.x < self.width - 1 && position.y < self.height - 1
+    }
+
+    fn get(&self, position: IVec2) -> Tile {
+        if position.x < 0 || position.y < 0 || position.x >= self.width || position.y >= self.height {
+            return Tile::Wall;
+        }
+        self.tiles[(position.y * self.width + position.x) as usize]
+    }
+
+    fn set(&mut self, position: IVec2, tile: Tile) {
+        if self.carvable(position) {
+            self.tiles[(position.y * self.width + position.x) as usize] = tile;
+        }
+    }
+
+    fn positions(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| IVec2::new(x, y)))
+    }
+
+    ///
+    /// Whether everything within `margin` tiles of `position` (including it) is wall, ignoring `except`.
+    ///
+    fn solid_around(&self, position: IVec2, margin: i32, except: &[IVec2]) -> bool {
+        (-margin..=margin).all(|dy| {
+            (-margin..=margin).all(|dx| {
+                let around = position + IVec2::new(dx, dy);
+                except.contains(&around) || (self.carvable(around) && self.get(around) == Tile::Wall)
+            })
+        })
+    }
+
+    fn rows(&self) -> Vec<String> {
+        (0..self.height)
+            .rev()
+            .map(|y| (0..self.width).map(|x| self.get(IVec2::new(x, y)).to_char()).collect())
+            .collect()
+    }
+}
+
+///
+/// Generates a level with a winding path from left to right, side paths ending in clearings and up to
+/// `params.secrets` secrets which can all be reached from the spawn.
+///
+pub fn generate(seed: u64, params: &ProcgenParams, rng: &mut impl Rng) -> LevelFile {
+    let mut grid = Grid::new(params.width.max(16), params.height.max(12));
+    let spawn = IVec2::new(2, rng.gen_range(3..grid.height - 3));
+    let main_path = carve_main_path(&mut grid, spawn, rng);
+
+    let mut clearings = Vec::new();
+    for _ in 0..params.branches {
+        let from = main_path[rng.gen_range(main_path.len() / 8..main_path.len())];
+        if let Some(clearing) = carve_branch(&mut grid, from, rng) {
+            clearings.push(clearing);
+        }
+    }
+    scatter_grass(&mut grid, rng);
+    scatter_water(&mut grid, rng);
+
+    let secrets = place_secrets(&mut grid, seed, params.secrets, spawn, &clearings, rng);
+    LevelFile {
+        version: LEVEL_FORMAT_VERSION,
+        id: format!("wander_{seed}"),
+        metadata: LevelMetadata {
+            name: "Wandering Path".to_string(),
+            author: String::new(),
+            description: format!("Grown from seed {seed}."),
+        },
+        legend: Default::default(),
+        tiles: grid.rows(),
+        spawn: spawn.into(),
+        entities: Vec::new(),
+        secrets,
+        triggers: Vec::new(),
+        signals: Vec::new(),
+        routines: Vec::new(),
+        hazards: Vec::new(),
+    }
+}
+
+fn carve_main_path(grid: &mut Grid, start: IVec2, rng: &mut impl Rng) -> Vec<IVec2> {
+    let mut path = Vec::new();
+    let mut position = start;
+    let mut direction = IVec2::X;
+    while position.x < grid.width - 3 {
+        grid.set(position, Tile::Path);
+        path.push(position);
+
+        // Wander up and down for a few tiles at a time, always heading right overall.
+        if direction == IVec2::X && rng.gen_bool(0.3) {
+            direction = if rng.gen_bool(0.5) { IVec2::Y } else { IVec2::NEG_Y };
+        } else if direction != IVec2::X && rng.gen_bool(0.4) {
+            direction = IVec2::X;
+        }
+        let next = position + direction;
+        if next.y < 2 || next.y > grid.height - 3 {
+            direction = IVec2::X;
+        }
+        position += direction;
+    }
+    path
+}
+
+///
+/// Carves a side path from `from`, ending in a clearing. Returns the centre of the clearing.
+///
+fn carve_branch(grid: &mut Grid, from: IVec2, rng: &mut impl Rng) -> Option<IVec2> {
+    let heading = if rng.gen_bool(0.5) { IVec2::Y } else { IVec2::NEG_Y };
+    let mut direction = heading;
+    let mut position = from;
+    let length = rng.gen_range(4..10);
+    for _ in 0..length {
+        let next = position + direction;
+        if next.x < 3 || next.y < 3 || next.x > grid.width - 4 || next.y > grid.height - 4 {
+            break;
+        }
+        position = next;
+        grid.set(position, Tile::Path);
+        if rng.gen_bool(0.3) {
+            direction = if direction == heading {
+                if rng.gen_bool(0.5) { IVec2::X } else { IVec2::NEG_X }
+            } else {
+                heading
+            };
+        }
+    }
+    if position == from {
+        return None;
+    }
+
+    let radius = rng.gen_range(1..=2);
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let tile = position + IVec2::new(dx, dy);
+            if grid.get(tile) == Tile::Wall {
+                grid.set(tile, Tile::Grass);
+            }
+        }
+    }
+    Some(position)
+}
+
+///
+/// Grows grass onto some of the walls beside the path. Grass is walkable, and only ever grows next to
+/// something walkable, so everything stays connected.
+///
+fn scatter_grass(grid: &mut Grid, rng: &mut impl Rng) {
+    let beside_path: Vec<IVec2> = grid
+        .positions()
+        .filter(|position| grid.carvable(*position) && grid.get(*position) == Tile::Wall)
+        .filter(|position| DIRECTIONS.iter().any(|d| grid.get(*position + *d).is_walkable()))
+        .collect();
+    for position in beside_path {
+        if rng.gen_bool(0.2) {
+            grid.set(position, Tile::Grass);
+        }
+    }
+}
+
+fn scatter_water(grid: &mut Grid, rng: &mut impl Rng) {
+    for _ in 0..(grid.width * grid.height / 150) {
+        let centre = IVec2::new(rng.gen_range(2..grid.width - 2), rng.gen_range(2..grid.height - 2));
+        if grid.solid_around(centre, 2, &[]) {
+            for direction in DIRECTIONS.iter().chain([&IVec2::ZERO]) {
+                grid.set(centre + *direction, Tile::Water);
+            }
+        }
+    }
+}
+
+fn place_secrets(
+    grid: &mut Grid,
+    seed: u64,
+    count: usize,
+    spawn: IVec2,
+    clearings: &[IVec2],
+    rng: &mut impl Rng,
+) -> Vec<LevelSecret> {
+    let mut secrets: Vec<LevelSecret> = Vec::new();
+    let mut used = HashSet::from([spawn]);
+    let mut clearings = clearings.to_vec();
+    clearings.shuffle(rng);
+
+    for i in 0..count {
+        let id = format!("wander_{seed}_{i}");
+        let kind = [SecretKind::Passage, SecretKind::BuriedItem, SecretKind::Lore][i % 3];
+        let secret = match kind {
+            SecretKind::Passage => place_passage(grid, &id, &used, rng),
+            SecretKind::BuriedItem => place_item(grid, &id, &mut clearings, &used, rng),
+            SecretKind::Lore => None,
+        }
+        // Lore fits beside any path, so it fills in whenever there's no room for anything else.
+        .or_else(|| place_lore(grid, &id, &used, rng));
+
+        if let Some(mut secret) = secret {
+            used.insert(IVec2::from(secret.position));
+            secret.hint_after = i;
+            secrets.push(secret);
+        }
+    }
+    secrets
+}
+
+///
+/// Carves a short hidden tunnel into the wall beside the path, ending in a small hidden room.
+///
+fn place_passage(grid: &mut Grid, id: &str, used: &HashSet<IVec2>, rng: &mut impl Rng) -> Option<LevelSecret> {
+    let candidates: Vec<(IVec2, IVec2)> = grid
+        .positions()
+        .filter(|position| grid.get(*position).is_walkable())
+        .flat_map(|position| DIRECTIONS.map(|direction| (position, direction)))
+        .collect();
+
+    for _ in 0..64 {
+        let (from, direction) = *candidates.choose(rng)?;
+        let length = rng.gen_range(2..=4);
+        let tunnel: Vec<IVec2> = (1..=length).map(|i| from + direction * i).collect();
+        let room_centre = from + direction * (length + 2);
+        let room: Vec<IVec2> = (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| room_centre + IVec2::new(dx, dy)))
+            .collect();
+
+        let mut carved = tunnel.clone();
+        carved.extend(&room);
+        carved.push(from + direction * (length + 1));
+        let mut allowed = carved.clone();
+        allowed.push(from);
+        // Only the first tile of the tunnel may touch the path, so nothing else gives the passage away.
+        // Lore is carved into walls too, so the entrance mustn't be one of those.
+        let entrance_is_wall =
+            grid.carvable(tunnel[0]) && grid.get(tunnel[0]) == Tile::Wall && !used.contains(&tunnel[0]);
+        if !entrance_is_wall || !carved.iter().skip(1).all(|tile| grid.solid_around(*tile, 1, &allowed)) {
+            continue;
+        }
+        for tile in &carved {
+            grid.set(*tile, Tile::Hidden);
+        }
+
+        let condition = if rng.gen_bool(0.5) {
+            SecretCondition::Proximity { radius: 1 }
+        } else {
+            SecretCondition::Interact {
+                facing: facing_direction(direction),
+            }
+        };
+        return Some(LevelSecret {
+            id: id.to_string(),
+            name: PASSAGE_NAMES.choose(rng)?.to_string(),
+            kind: SecretKind::Passage,
+            position: tunnel[0].into(),
+            condition,
+            text: String::new(),
+            item: None,
+            hint: "Some of the rock beside the path sounds hollow.".to_string(),
+            hint_after: 0,
+            hints: vec!["The hollow rock is right beside the path, where it turns.".to_string()],
+        });
+    }
+    None
+}
+
+///
+/// Buries an item in one of the clearings at the end of the side paths, or anywhere off the path if
+/// every clearing is taken.
+///
+fn place_item(
+    grid: &Grid,
+    id: &str,
+    clearings: &mut Vec<IVec2>,
+    used: &HashSet<IVec2>,
+    rng: &mut impl Rng,
+) -> Option<LevelSecret> {
+    let position = match clearings.pop() {
+        Some(clearing) => clearing,
+        None => {
+            let walkable: Vec<IVec2> = grid
+                .positions()
+                .filter(|position| grid.get(*position).is_walkable() && !used.contains(position))
+                .collect();
+            *walkable.choose(rng)?
+        }
+    };
+    let (name, item) = ITEM_NAMES.choose(rng)?;
+    Some(LevelSecret {
+        id: id.to_string(),
+        name: name.to_string(),
+        kind: SecretKind::BuriedItem,
+        position: position.into(),
+        condition: SecretCondition::Interact { facing: None },
+        text: String::new(),
+        item: Some(item.to_string()),
+        hint: "Something glints at the end of a side path.".to_string(),
+        hint_after: 0,
+        hints: vec!["Dig at the very end of a path which leads nowhere.".to_string()],
+    })
+}
+
+fn place_lore(grid: &Grid, id: &str, used: &HashSet<IVec2>, rng: &mut impl Rng) -> Option<LevelSecret> {
+    let candidates: Vec<(IVec2, IVec2)> = grid
+        .positions()
+        .filter(|position| grid.carvable(*position) && grid.get(*position) == Tile::Wall && !used.contains(position))
+        .flat_map(|position| DIRECTIONS.map(|direction| (position, direction)))
+        .filter(|(position, direction)| grid.get(*position - *direction).is_walkable())
+        .collect();
+    let (position, direction) = *candidates.choose(rng)?;
+    let (name, text) = LORE.choose(rng)?;
+    Some(LevelSecret {
+        id: id.to_string(),
+        name: name.to_string(),
+        kind: SecretKind::Lore,
+        position: position.into(),
+        condition: SecretCondition::Interact {
+            facing: facing_direction(direction),
+        },
+        text: text.to_string(),
+        item: None,
+        hint: "Someone left words in the rock along the path.".to_string(),
+        hint_after: 0,
+        hints: vec!["Face the rock walls beside the path and look closely.".to_string()],
+    })
+}
+
+fn facing_direction(direction: IVec2) -> Option<FacingDirection> {
+    match direction {
+        IVec2::Y => Some(FacingDirection::Up),
+        IVec2::NEG_Y => Some(FacingDirection::Down),
+        IVec2::NEG_X => Some(FacingDirection::Left),
+        IVec2::X => Some(FacingDirection::Right),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::items::ItemsFile;
+    use crate::game::level::Level;
+    use crate::game::world::TileMap;
+    use crate::utils::ron_asset::{parse_ron, RonAsset};
+    use std::collections::VecDeque;
+
+    const SEEDS: u64 = 200;
+
+    fn generate_seed(seed: u64, params: &ProcgenParams) -> LevelFile {
+        generate(seed, params, &mut WorldRng::from_seed(seed).0)
+    }
+
+    fn map(file: &LevelFile) -> TileMap {
+        file.build_map().expect("generated a map which doesn't build")
+    }
+
+    ///
+    /// Whether every secret can be found from `spawn`: each one is on, or next to, a tile the player can
+    /// reach once every passage has been revealed.
+    ///
+    fn all_reachable(map: &TileMap, spawn: IVec2, secrets: &[LevelSecret]) -> bool {
+        let passable = |tile: Option<Tile>| tile.is_some_and(|tile| tile.is_walkable() || tile == Tile::Hidden);
+        let mut reached = HashSet::from([spawn]);
+        let mut open = VecDeque::from([spawn]);
+        while let Some(position) = open.pop_front() {
+            for direction in DIRECTIONS {
+                let next = position + direction;
+                if passable(map.get(next)) && reached.insert(next) {
+                    open.push_back(next);
+                }
+            }
+        }
+
+        secrets.iter().all(|secret| {
+            let position = IVec2::from(secret.position);
+            reached.contains(&position) || DIRECTIONS.iter().any(|direction| reached.contains(&(position + *direction)))
+        })
+    }
+
+    #[test]
+    fn every_secret_is_reachable() {
+        let params = ProcgenParams::default();
+        for seed in 0..SEEDS {
+            let file = generate_seed(seed, &params);
+            let spawn = IVec2::from(file.spawn);
+            assert!(all_reachable(&map(&file), spawn, &file.secrets), "seed {seed} has an unreachable secret");
+        }
+    }
+
+    #[test]
+    fn generated_levels_load() {
+        let params = ProcgenParams::default();
+        for seed in 0..SEEDS {
+            let result = Level::from_file(generate_seed(seed, &params));
+            assert!(result.is_ok(), "seed {seed}: {:?}", result.err());
+        }
+    }
+
+    #[test]
+    fn buried_items_are_in_the_shipped_catalog() {
+        let file = parse_ron::<ItemsFile>(include_str!("../../../assets/items/core.items.ron")).unwrap();
+        for (_, item) in ITEM_NAMES {
+            assert!(file.items.iter().any(|def| def.id == item), "{item} is not in core.items.ron");
+        }
+    }
+
+    #[test]
+    fn default_maps_have_every_secret() {
+        let params = ProcgenParams::default();
+        for seed in 0..SEEDS {
+            assert_eq!(generate_seed(seed, &params).secrets.len(), params.secrets, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn small_maps_have_at_most_the_secrets_asked_for() {
+        let params = ProcgenParams {
+            width: 16,
+            height: 12,
+            branches: 1,
+            secrets: 40,
+        };
+        for seed in 0..SEEDS {
+            let file = generate_seed(seed, &params);
+            assert!(file.secrets.len() <= params.secrets, "seed {seed}");
+            let positions: HashSet<[i32; 2]> = file.secrets.iter().map(|secret| secret.position).collect();
+            assert_eq!(positions.len(), file.secrets.len(), "seed {seed} put two secrets on one tile");
+            assert!(all_reachable(&map(&file), IVec2::from(file.spawn), &file.secrets), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn the_same_seed_grows_the_same_world() {
+        let params = ProcgenParams::default();
+        for seed in 0..10 {
+            let first = generate_seed(seed, &params);
+            let second = generate_seed(seed, &params);
+            assert_eq!(first.tiles, second.tiles);
+            let positions = |file: &LevelFile| file.secrets.iter().map(|secret| secret.position).collect::<Vec<_>>();
+            assert_eq!(positions(&first), positions(&second));
+        }
+    }
+}
@@ -1,14 +1,58 @@
+use crate::game::achievements::AchievementsPlugin;
+use crate::game::camera::CameraPlugin;
+use crate::game::checkpoints::CheckpointsPlugin;
+use crate::game::codex::CodexPlugin;
+use crate::game::dialogue::DialoguePlugin;
+use crate::game::fog::FogPlugin;
+use crate::game::hazards::HazardsPlugin;
+use crate::game::hints::HintsPlugin;
+use crate::game::interactables::InteractablesPlugin;
+use crate::game::items::ItemsPlugin;
+use crate::game::level::LevelPlugin;
+use crate::game::npcs::NpcsPlugin;
+use crate::game::outro::OutroPlugin;
+use crate::game::player::PlayerPlugin;
 use crate::game::primary_logic::GameLogicPlugin;
+use crate::game::puzzles::PuzzlesPlugin;
+use crate::game::quests::QuestsPlugin;
+use crate::game::rewind::RewindPlugin;
+use crate::game::saves::SavesPlugin;
+use crate::game::secrets::SecretsPlugin;
+use crate::game::settings::SettingsPlugin;
+use crate::game::slot_select::SlotSelectPlugin;
 use crate::game::textcolor_smoothing::TextColorSmoothingPlugin;
+use crate::game::world::WorldPlugin;
 use crate::utils::buttoning::ButtoningPlugin;
 use crate::utils::position_smoothing::PositionSmoothingPlugin;
 use crate::utils::responsive::ResponsivePlugin;
 use bevy::app::PluginGroupBuilder;
-use bevy::prelude::{Plugin, PluginGroup};
+use bevy::prelude::PluginGroup;
 
-pub(self) mod internal;
-pub(self) mod primary_logic;
-pub(self) mod textcolor_smoothing;
+mod achievements;
+mod camera;
+mod checkpoints;
+mod codex;
+mod dialogue;
+mod fog;
+mod hazards;
+mod hints;
+mod interactables;
+mod items;
+mod internal;
+mod level;
+mod npcs;
+mod outro;
+mod player;
+mod primary_logic;
+mod puzzles;
+mod quests;
+mod rewind;
+mod saves;
+mod secrets;
+mod settings;
+mod slot_select;
+mod textcolor_smoothing;
+pub mod world;
 
 #[derive(Default)]
 pub struct GamePlugins;
@@ -31,5 +75,27 @@ impl PluginGroup for GamePlugins {
             .add_group(GameDependencyPlugins)
             .add(GameLogicPlugin)
             .add(TextColorSmoothingPlugin)
+            .add(OutroPlugin)
+            .add(SettingsPlugin::default())
+            .add(SavesPlugin)
+            .add(SlotSelectPlugin)
+            .add(WorldPlugin)
+            .add(LevelPlugin)
+            .add(PlayerPlugin)
+            .add(SecretsPlugin)
+            .add(CodexPlugin)
+            .add(CameraPlugin)
+            .add(FogPlugin)
+            .add(InteractablesPlugin)
+            .add(NpcsPlugin)
+            .add(HazardsPlugin)
+            .add(CheckpointsPlugin)
+            .add(DialoguePlugin)
+            .add(ItemsPlugin)
+            .add(PuzzlesPlugin)
+            .add(RewindPlugin)
+            .add(QuestsPlugin)
+            .add(HintsPlugin)
+            .add(AchievementsPlugin)
     }
 }
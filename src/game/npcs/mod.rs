@@ -0,0 +1,170 @@
+use crate::game::interactables::{Interact, Interactable, INTERACTABLE_Z};
+use crate::game::level::{CurrentLevel, InteractableKind, Level, RoutineKind};
+use crate::game::player::{GridPosition, Player, PlayerMovementSet};
+use crate::game::primary_logic::{GameData, GameState, InGameScreen};
+use crate::game::world::{find_path, tile_to_world, CurrentMap, MapLoaded, MapSet, TILE_SIZE};
+use crate::utils::position_smoothing::{PFPSmoothing, PositionSmoothing, TargetPosition};
+use bevy::prelude::*;
+
+mod routine;
+
+use routine::{hour_of_day, routine_goal};
+
+///
+/// NPCs walking the level by their [crate::game::level::NpcRoutine], on patrol or following the hours
+/// of the in-game day. They find their way with [find_path], step around the player and stop for a
+/// while when talked to.
+///
+#[derive(Default)]
+pub struct NpcsPlugin;
+
+///
+/// An NPC with a routine, on the same entity as its [Interactable].
+///
+#[derive(Component)]
+struct Walker {
+    routine: RoutineKind,
+    /// Tiles left to walk to the goal, the next step first.
+    path: Vec<IVec2>,
+    /// The patrol point being walked to.
+    point: usize,
+    /// Seconds until the NPC next moves.
+    wait: f32,
+}
+
+/// Walking speed in tiles per second.
+const NPC_SPEED: f32 = 2.5;
+/// Seconds an NPC stands still after being talked to.
+const TALK_PAUSE: f32 = 4.0;
+/// Seconds an NPC waits before trying again when its way is blocked, or it has nowhere to go.
+const IDLE_WAIT: f32 = 1.0;
+
+impl Plugin for NpcsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            setup_walkers
+                .after(MapSet::Prepare)
+                .run_if(in_state(GameState::InGame).and_then(on_event::<MapLoaded>())),
+        );
+        app.add_systems(
+            Update,
+            (pause_on_interact, walk)
+                .chain()
+                .after(PlayerMovementSet)
+                .run_if(in_state(InGameScreen::Playing)),
+        );
+    }
+}
+
+fn setup_walkers(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    game_data: Res<GameData>,
+    mut npcs: Query<(Entity, &mut Interactable, &mut Transform)>,
+) {
+    let Some(level) = levels.get(&current_level.handle) else {
+        return;
+    };
+    for (entity, mut interactable, mut transform) in npcs.iter_mut() {
+        if interactable.kind != InteractableKind::Npc {
+            continue;
+        }
+        let Some(routine) = level
+            .file
+            .routines
+            .iter()
+            .find(|routine| interactable.entity.id.as_ref() == Some(&routine.npc))
+        else {
+            continue;
+        };
+
+        // NPCs on a schedule are already where the time of day puts them.
+        if let RoutineKind::Schedule { .. } = routine.kind {
+            if let Some(goal) = routine_goal(&routine.kind, 0, hour_of_day(game_data.play_time)) {
+                interactable.position = goal;
+                transform.translation = tile_to_world(goal).extend(INTERACTABLE_Z);
+            }
+        }
+        commands.entity(entity).insert((
+            Walker {
+                routine: routine.kind.clone(),
+                path: Vec::new(),
+                point: 0,
+                wait: 0.0,
+            },
+            TargetPosition(transform.translation),
+            PositionSmoothing::PFP(PFPSmoothing {
+                smoothing_speed: NPC_SPEED * TILE_SIZE,
+                near_slowdown: false,
+                end_threshold: 0.5,
+                ..default()
+            }),
+        ));
+    }
+}
+
+fn pause_on_interact(mut events: EventReader<Interact>, mut walkers: Query<&mut Walker>) {
+    for event in events.read() {
+        if let Ok(mut walker) = walkers.get_mut(event.entity) {
+            walker.wait = walker.wait.max(TALK_PAUSE);
+        }
+    }
+}
+
+fn walk(
+    time: Res<Time>,
+    current_map: Res<CurrentMap>,
+    game_data: Res<GameData>,
+    players: Query<&GridPosition, With<Player>>,
+    mut walkers: Query<(Entity, &mut Walker, &mut Interactable, &mut TargetPosition)>,
+) {
+    let player = players.iter().next().map(|grid_position| grid_position.0);
+    let hour = hour_of_day(game_data.play_time);
+    let mut occupied: Vec<(Entity, IVec2)> = walkers
+        .iter()
+        .map(|(entity, _, interactable, _)| (entity, interactable.position))
+        .collect();
+
+    for (entity, mut walker, mut interactable, mut target) in walkers.iter_mut() {
+        walker.wait -= time.delta_seconds();
+        if walker.wait > 0.0 {
+            continue;
+        }
+        let Some(goal) = routine_goal(&walker.routine, walker.point, hour) else {
+            continue;
+        };
+        let position = interactable.position;
+        if position == goal {
+            walker.wait = match walker.routine {
+                RoutineKind::Patrol { wait, .. } => {
+                    walker.point += 1;
+                    wait
+                }
+                RoutineKind::Schedule { .. } => IDLE_WAIT,
+            };
+            continue;
+        }
+
+        let blocked = |tile: IVec2| {
+            Some(tile) == player || occupied.iter().any(|(other, at)| *other != entity && *at == tile)
+        };
+        // Walk the old path while it's clear, otherwise find a new way around whatever is in it.
+        if walker.path.last() != Some(&goal) || walker.path.first().is_none_or(|next| blocked(*next)) {
+            walker.path = find_path(&current_map.map, position, goal, blocked).unwrap_or_default();
+        }
+        if walker.path.is_empty() {
+            walker.wait = IDLE_WAIT;
+            continue;
+        }
+
+        let next = walker.path.remove(0);
+        interactable.position = next;
+        target.0 = tile_to_world(next).extend(INTERACTABLE_Z);
+        walker.wait = 1.0 / NPC_SPEED;
+        if let Some((_, at)) = occupied.iter_mut().find(|(other, _)| *other == entity) {
+            *at = next;
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::game::level::{RoutineKind, ScheduleStop};
+use bevy::math::IVec2;
+
+///
+/// Seconds of play time in one in-game day.
+///
+pub const DAY_LENGTH: f64 = 600.0;
+
+///
+/// The hour of the in-game day, from 0 up to 24, after `play_time` seconds of play.
+///
+pub fn hour_of_day(play_time: f64) -> f32 {
+    ((play_time / DAY_LENGTH).fract() * 24.0) as f32
+}
+
+///
+/// The stop which started last at `hour`. Before the first stop of the day, that is the last stop of the
+/// day before.
+///
+pub fn current_stop(stops: &[ScheduleStop], hour: f32) -> Option<&ScheduleStop> {
+    let by_hour = |a: &&ScheduleStop, b: &&ScheduleStop| a.hour.total_cmp(&b.hour);
+    stops
+        .iter()
+        .filter(|stop| stop.hour <= hour)
+        .max_by(by_hour)
+        .or_else(|| stops.iter().max_by(by_hour))
+}
+
+///
+/// Where an NPC following `routine` is headed. `point` is the patrol point it is walking to.
+///
+pub fn routine_goal(routine: &RoutineKind, point: usize, hour: f32) -> Option<IVec2> {
+    match routine {
+        RoutineKind::Patrol { points, .. } => points.get(point % points.len().max(1)).map(|p| IVec2::from(*p)),
+        RoutineKind::Schedule { stops } => current_stop(stops, hour).map(|stop| IVec2::from(stop.position)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stop(hour: f32, x: i32) -> ScheduleStop {
+        ScheduleStop { hour, position: [x, 0] }
+    }
+
+    #[test]
+    fn the_day_wraps_around() {
+        assert_eq!(hour_of_day(0.0), 0.0);
+        assert_eq!(hour_of_day(DAY_LENGTH / 2.0), 12.0);
+        assert_eq!(hour_of_day(DAY_LENGTH * 3.0 + DAY_LENGTH / 4.0), 6.0);
+    }
+
+    #[test]
+    fn the_stop_which_started_last_is_current() {
+        // Out of order on purpose, the stops are sorted by hour rather than read in order.
+        let stops = [stop(18.0, 3), stop(8.0, 1), stop(12.0, 2)];
+        assert_eq!(current_stop(&stops, 8.0), Some(&stops[1]));
+        assert_eq!(current_stop(&stops, 15.5), Some(&stops[2]));
+        assert_eq!(current_stop(&stops, 23.9), Some(&stops[0]));
+        // Before the first stop of the day, the NPC is still at the last stop of the day before.
+        assert_eq!(current_stop(&stops, 3.0), Some(&stops[0]));
+        assert_eq!(current_stop(&[], 3.0), None);
+    }
+
+    #[test]
+    fn patrols_go_back_to_the_first_point() {
+        let patrol = RoutineKind::Patrol {
+            points: vec![[0, 0], [4, 0], [4, 4]],
+            wait: 0.0,
+        };
+        assert_eq!(routine_goal(&patrol, 1, 0.0), Some(IVec2::new(4, 0)));
+        assert_eq!(routine_goal(&patrol, 3, 0.0), Some(IVec2::ZERO));
+        assert_eq!(routine_goal(&patrol, 5, 0.0), Some(IVec2::new(4, 4)));
+
+        let empty = RoutineKind::Patrol {
+            points: Vec::new(),
+            wait: 0.0,
+        };
+        assert_eq!(routine_goal(&empty, 0, 0.0), None);
+    }
+
+    #[test]
+    fn schedules_head_for_the_current_stop() {
+        let schedule = RoutineKind::Schedule {
+            stops: vec![stop(6.0, 1), stop(20.0, 2)],
+        };
+        assert_eq!(routine_goal(&schedule, 0, 12.0), Some(IVec2::new(1, 0)));
+        assert_eq!(routine_goal(&schedule, 0, 2.0), Some(IVec2::new(2, 0)));
+        // The patrol point doesn't matter on a schedule.
+        assert_eq!(routine_goal(&schedule, 7, 21.0), Some(IVec2::new(2, 0)));
+    }
+}
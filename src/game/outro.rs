@@ -0,0 +1,136 @@
+use crate::game::internal::ScriptControlled;
+use crate::game::primary_logic::GameState;
+use crate::game::textcolor_smoothing::{TargetClearColor, TargetTextColor, TextColorSmoothing};
+use bevy::prelude::*;
+
+///
+/// Plays the outro when entering [GameState::Exiting] and exits the app once it has finished.
+///
+/// Requires [crate::game::textcolor_smoothing::TextColorSmoothingPlugin]
+///
+#[derive(Default)]
+pub struct OutroPlugin;
+
+#[derive(Resource)]
+pub struct OutroSettings {
+    /// Seconds to hold before fading, so the quit button flash is visible.
+    pub hold: f32,
+    /// Seconds the fade runs before the app exits.
+    pub fade: f32,
+    pub fade_color: Color,
+    /// Proportional smoothing used by text and the camera while fading.
+    pub fade_speed: f32,
+    /// Ask "Are you sure?" before quitting from the main menu.
+    pub confirm_quit: bool,
+}
+
+impl Default for OutroSettings {
+    fn default() -> Self {
+        Self {
+            hold: 0.35,
+            fade: 1.5,
+            fade_color: Color::BLACK,
+            fade_speed: 3.0,
+            confirm_quit: false,
+        }
+    }
+}
+
+///
+/// Anything which has to be written out before the app exits (settings, saves) should run in this set.
+/// It runs exactly once, on the frame the outro finishes, right before [AppExit] is sent.
+///
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExitFlush;
+
+#[derive(Resource)]
+struct OutroTimer {
+    timer: Timer,
+    fading: bool,
+}
+
+impl Plugin for OutroPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OutroSettings>();
+        app.add_systems(OnEnter(GameState::Exiting), start_outro);
+        app.add_systems(
+            Update,
+            (
+                tick_outro.run_if(in_state(GameState::Exiting)),
+                send_app_exit.after(ExitFlush).run_if(outro_finished),
+            ),
+        );
+        app.configure_sets(Update, ExitFlush.after(tick_outro).run_if(outro_finished));
+    }
+}
+
+///
+/// Enters [GameState::Exiting], keeping everything currently on screen alive so it can fade out.
+///
+pub fn begin_exit(commands: &mut Commands, next_state: &mut NextState<GameState>) {
+    commands.add(|world: &mut World| {
+        let scoped = world
+            .query_filtered::<Entity, With<StateScoped<GameState>>>()
+            .iter(world)
+            .collect::<Vec<_>>();
+        for entity in scoped {
+            world.entity_mut(entity).insert(StateScoped(GameState::Exiting));
+        }
+    });
+    next_state.set(GameState::Exiting);
+}
+
+fn start_outro(mut commands: Commands, settings: Res<OutroSettings>) {
+    info!("Exiting");
+    commands.insert_resource(OutroTimer {
+        timer: Timer::from_seconds(settings.hold + settings.fade, TimerMode::Once),
+        fading: false,
+    });
+}
+
+fn tick_outro(
+    mut commands: Commands,
+    mut outro_timer: ResMut<OutroTimer>,
+    settings: Res<OutroSettings>,
+    mut texts: Query<(Entity, &mut TargetTextColor, Option<&mut TextColorSmoothing>)>,
+    cameras: Query<Entity, With<Camera>>,
+    time: Res<Time>,
+) {
+    outro_timer.timer.tick(time.delta());
+    if outro_timer.fading || outro_timer.timer.elapsed_secs() < settings.hold {
+        return;
+    }
+    outro_timer.fading = true;
+
+    for (entity, mut target_color, smoothing) in texts.iter_mut() {
+        target_color.0 = settings.fade_color;
+        match smoothing {
+            Some(mut smoothing) => smoothing.proportional = settings.fade_speed,
+            None => {
+                commands.entity(entity).insert(TextColorSmoothing {
+                    proportional: settings.fade_speed,
+                    ..default()
+                });
+            }
+        }
+        commands.entity(entity).insert(ScriptControlled);
+    }
+
+    for camera in cameras.iter() {
+        commands.entity(camera).insert((
+            TargetClearColor(settings.fade_color),
+            TextColorSmoothing {
+                proportional: settings.fade_speed,
+                ..default()
+            },
+        ));
+    }
+}
+
+fn outro_finished(outro_timer: Option<Res<OutroTimer>>) -> bool {
+    outro_timer.is_some_and(|t| t.timer.just_finished())
+}
+
+fn send_app_exit(mut app_exit: EventWriter<AppExit>) {
+    app_exit.send(AppExit::Success);
+}
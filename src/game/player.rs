@@ -0,0 +1,423 @@
+use crate::game::camera::{CameraImpulse, CameraTarget};
+use crate::game::interactables::Interactable;
+use crate::game::level::InteractableKind;
+use crate::game::primary_logic::{GameData, GameState, InGameScreen};
+use crate::game::settings::{MovementMode, Settings};
+use crate::game::world::{tile_to_world, world_to_tile, CurrentMap, MapLoaded, MapSet, TileMap, TILE_SIZE};
+use crate::utils::position_smoothing::{PFPSmoothing, PositionSmoothing, TargetPosition};
+use bevy::prelude::*;
+
+///
+/// The player character walking the path, in either [MovementMode].
+///
+#[derive(Default)]
+pub struct PlayerPlugin;
+
+///
+/// Walking speed in tiles per second, for both movement modes.
+///
+pub const PLAYER_SPEED: f32 = 4.0;
+
+///
+/// Half the width of the player's collision box in free movement, in tiles.
+///
+const PLAYER_RADIUS: f32 = 0.35;
+
+pub const PLAYER_Z: f32 = 5.0;
+const PLAYER_COLOR: Color = Color::srgb(2.4, 2.1, 1.4);
+
+#[derive(Default, Component)]
+pub struct Player;
+
+///
+/// The tile the player is standing on. In grid movement this is the tile being walked to.
+///
+#[derive(Component, Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GridPosition(pub IVec2);
+
+///
+/// The direction the player last tried to move in, one tile long.
+///
+#[derive(Component, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Facing(pub IVec2);
+
+impl Default for Facing {
+    fn default() -> Self {
+        Self(IVec2::NEG_Y)
+    }
+}
+
+///
+/// Sent when the player presses the interact button.
+///
+#[derive(Event, Copy, Clone, Debug)]
+pub struct PlayerInteract {
+    /// The tile the player is standing on.
+    pub position: IVec2,
+    /// The direction the player is facing. The tile in front of them is `position + facing`.
+    pub facing: IVec2,
+}
+
+///
+/// Systems moving the player. Anything reacting to where the player is should run after this.
+///
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlayerMovementSet;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerInteract>();
+        app.add_systems(
+            Update,
+            (
+                spawn_player.after(MapSet::Prepare).run_if(on_event::<MapLoaded>()),
+                apply_movement_mode,
+            )
+                .chain()
+                .before(PlayerMovementSet)
+                .run_if(in_state(GameState::InGame)),
+        );
+        app.add_systems(
+            Update,
+            (
+                (grid_movement, free_movement).in_set(PlayerMovementSet),
+                record_position,
+                send_interact,
+            )
+                .chain()
+                .run_if(in_state(InGameScreen::Playing)),
+        );
+    }
+}
+
+fn smoothing_for(mode: MovementMode) -> PositionSmoothing {
+    match mode {
+        // Glide between tiles at a constant speed, landing exactly on the tile centre.
+        MovementMode::Grid => PositionSmoothing::PFP(PFPSmoothing {
+            smoothing_speed: PLAYER_SPEED * TILE_SIZE,
+            near_slowdown: false,
+            end_threshold: 0.5,
+            ..default()
+        }),
+        // Free movement already moves a little every frame, so follow the target directly.
+        MovementMode::Free => PositionSmoothing::None,
+    }
+}
+
+fn spawn_player(
+    mut commands: Commands,
+    current_map: Res<CurrentMap>,
+    game_data: Res<GameData>,
+    settings: Res<Settings>,
+    old_players: Query<Entity, With<Player>>,
+) {
+    for entity in old_players.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // A saved position can become unwalkable when the level is edited, so fall back to the spawn.
+    let tile = game_data
+        .position
+        .map(IVec2::from)
+        .filter(|tile| current_map.map.is_walkable(*tile))
+        .unwrap_or(current_map.spawn);
+    let position = tile_to_world(tile).extend(PLAYER_Z);
+
+    commands.spawn((
+        StateScoped(GameState::InGame),
+        SpriteBundle {
+            sprite: Sprite {
+                color: PLAYER_COLOR,
+                custom_size: Some(Vec2::splat(TILE_SIZE * PLAYER_RADIUS * 2.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(position),
+            ..default()
+        },
+        Player,
+        CameraTarget,
+        GridPosition(tile),
+        Facing::default(),
+        TargetPosition(position),
+        smoothing_for(settings.gameplay.movement),
+    ));
+}
+
+///
+/// Switches the player over when the movement mode setting changes.
+///
+fn apply_movement_mode(
+    settings: Res<Settings>,
+    mut query: Query<(&GridPosition, &mut TargetPosition, &mut PositionSmoothing), With<Player>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (grid_position, mut target, mut smoothing) in query.iter_mut() {
+        *smoothing = smoothing_for(settings.gameplay.movement);
+        if settings.gameplay.movement == MovementMode::Grid {
+            target.0 = tile_to_world(grid_position.0).extend(PLAYER_Z);
+        }
+    }
+}
+
+///
+/// The direction the player is holding, from the keyboard, the dpad or the left stick.
+///
+fn movement_input(
+    keys: &ButtonInput<KeyCode>,
+    gamepads: &Gamepads,
+    gamepad_buttons: &ButtonInput<GamepadButton>,
+    gamepad_axes: &Axis<GamepadAxis>,
+) -> Vec2 {
+    let mut direction = Vec2::ZERO;
+    let mut stick = Vec2::ZERO;
+    let mut held = |pressed: bool, towards: Vec2| {
+        if pressed {
+            direction += towards;
+        }
+    };
+
+    held(keys.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]), Vec2::Y);
+    held(keys.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]), Vec2::NEG_Y);
+    held(keys.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]), Vec2::NEG_X);
+    held(keys.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]), Vec2::X);
+
+    for gamepad in gamepads.iter() {
+        let button = |button_type| gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type));
+        held(button(GamepadButtonType::DPadUp), Vec2::Y);
+        held(button(GamepadButtonType::DPadDown), Vec2::NEG_Y);
+        held(button(GamepadButtonType::DPadLeft), Vec2::NEG_X);
+        held(button(GamepadButtonType::DPadRight), Vec2::X);
+
+        let axis = |axis_type| gamepad_axes.get(GamepadAxis::new(gamepad, axis_type)).unwrap_or_default();
+        stick += Vec2::new(axis(GamepadAxisType::LeftStickX), axis(GamepadAxisType::LeftStickY));
+    }
+
+    (direction + stick).clamp_length_max(1.0)
+}
+
+///
+/// Turns a held direction into one of the four tile directions, or `None` when nothing is held.
+///
+fn step_direction(direction: Vec2) -> Option<IVec2> {
+    if direction.length() < 0.5 {
+        None
+    } else if direction.x.abs() > direction.y.abs() {
+        Some(IVec2::new(direction.x.signum() as i32, 0))
+    } else {
+        Some(IVec2::new(0, direction.y.signum() as i32))
+    }
+}
+
+///
+/// The tile a grid step from `from` lands on, or `None` when it is not walkable or `blocked`, e.g. by an NPC.
+///
+fn grid_step(map: &TileMap, from: IVec2, step: IVec2, blocked: impl Fn(IVec2) -> bool) -> Option<IVec2> {
+    let next = from + step;
+    (map.is_walkable(next) && !blocked(next)).then_some(next)
+}
+
+///
+/// Whether the player's collision box centred on the world position `position` only covers walkable tiles.
+///
+fn fits(map: &TileMap, position: Vec2) -> bool {
+    let half = TILE_SIZE * PLAYER_RADIUS;
+    let min = world_to_tile(position - half);
+    let max = world_to_tile(position + half);
+    (min.x..=max.x).all(|x| (min.y..=max.y).all(|y| map.is_walkable(IVec2::new(x, y))))
+}
+
+///
+/// Moves `position` by `delta` in free movement, stopping at anything the player does not [fits] in.
+///
+/// Each axis is moved separately so the player slides along walls instead of sticking to them.
+///
+fn slide(map: &TileMap, mut position: Vec2, delta: Vec2) -> Vec2 {
+    for axis_delta in [Vec2::new(delta.x, 0.0), Vec2::new(0.0, delta.y)] {
+        if fits(map, position + axis_delta) {
+            position += axis_delta;
+        }
+    }
+    position
+}
+
+#[allow(clippy::too_many_arguments)]
+fn grid_movement(
+    settings: Res<Settings>,
+    current_map: Res<CurrentMap>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut query: Query<(&Transform, &mut GridPosition, &mut Facing, &mut TargetPosition), With<Player>>,
+    interactables: Query<&Interactable>,
+    mut impulses: EventWriter<CameraImpulse>,
+    mut bumped: Local<bool>,
+) {
+    if settings.gameplay.movement != MovementMode::Grid {
+        return;
+    }
+    let Some(step) = step_direction(movement_input(&keys, &gamepads, &gamepad_buttons, &gamepad_axes)) else {
+        *bumped = false;
+        return;
+    };
+
+    for (transform, mut grid_position, mut facing, mut target) in query.iter_mut() {
+        // Only take the next step once the last one has finished gliding.
+        if transform.translation != target.0 {
+            continue;
+        }
+        if facing.0 != step {
+            facing.0 = step;
+            *bumped = false;
+        }
+        let npc_at = |tile: IVec2| {
+            interactables
+                .iter()
+                .any(|interactable| interactable.kind == InteractableKind::Npc && interactable.position == tile)
+        };
+        if let Some(next) = grid_step(&current_map.map, grid_position.0, step, npc_at) {
+            grid_position.0 = next;
+            target.0 = tile_to_world(next).extend(PLAYER_Z);
+        } else if !*bumped {
+            // Nudge the camera once per bump, not every frame the direction is held against the wall.
+            *bumped = true;
+            impulses.send(CameraImpulse::Punch(step.as_vec2() * TILE_SIZE * 0.1));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn free_movement(
+    settings: Res<Settings>,
+    current_map: Res<CurrentMap>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    time: Res<Time>,
+    mut query: Query<(&mut GridPosition, &mut Facing, &mut TargetPosition), With<Player>>,
+) {
+    if settings.gameplay.movement != MovementMode::Free {
+        return;
+    }
+    let direction = movement_input(&keys, &gamepads, &gamepad_buttons, &gamepad_axes);
+    if direction == Vec2::ZERO {
+        return;
+    }
+
+    let delta = direction * PLAYER_SPEED * TILE_SIZE * time.delta_seconds();
+    for (mut grid_position, mut facing, mut target) in query.iter_mut() {
+        if let Some(step) = step_direction(direction) {
+            if facing.0 != step {
+                facing.0 = step;
+            }
+        }
+
+        let position = slide(&current_map.map, target.0.truncate(), delta);
+        target.0 = position.extend(PLAYER_Z);
+
+        let tile = world_to_tile(position);
+        if grid_position.0 != tile {
+            grid_position.0 = tile;
+        }
+    }
+}
+
+fn record_position(mut game_data: ResMut<GameData>, query: Query<&GridPosition, (With<Player>, Changed<GridPosition>)>) {
+    for grid_position in query.iter() {
+        game_data.position = Some(grid_position.0.into());
+    }
+}
+
+fn send_interact(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    query: Query<(&GridPosition, &Facing), With<Player>>,
+    mut events: EventWriter<PlayerInteract>,
+) {
+    let pressed = keys.any_just_pressed([KeyCode::KeyE, KeyCode::Space, KeyCode::Enter])
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South)));
+    if !pressed {
+        return;
+    }
+    for (grid_position, facing) in query.iter() {
+        events.send(PlayerInteract {
+            position: grid_position.0,
+            facing: facing.0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::Tile;
+
+    ///
+    /// A map from rows of [Tile::from_char] characters, with the first row at the top.
+    ///
+    fn map(rows: &[&str]) -> TileMap {
+        let mut map = TileMap::new();
+        for (row, line) in rows.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let y = (rows.len() - 1 - row) as i32;
+                map.set(IVec2::new(x as i32, y), Tile::from_char(c).unwrap());
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn grid_steps_are_blocked_by_walls_and_water() {
+        let map = map(&["#####", "#.~,#", "#####"]);
+        let start = IVec2::new(1, 1);
+        assert_eq!(grid_step(&map, start, IVec2::X, |_| false), None);
+        assert_eq!(grid_step(&map, start, IVec2::Y, |_| false), None);
+        assert_eq!(grid_step(&map, IVec2::new(2, 1), IVec2::X, |_| false), Some(IVec2::new(3, 1)));
+    }
+
+    #[test]
+    fn grid_steps_are_blocked_by_npcs() {
+        let map = map(&["..."]);
+        let npc = IVec2::new(1, 0);
+        assert_eq!(grid_step(&map, IVec2::ZERO, IVec2::X, |tile| tile == npc), None);
+        assert_eq!(grid_step(&map, IVec2::ZERO, IVec2::X, |tile| tile == IVec2::new(2, 0)), Some(npc));
+    }
+
+    #[test]
+    fn grid_steps_do_not_leave_the_map() {
+        let map = map(&["..", ".."]);
+        assert_eq!(grid_step(&map, IVec2::ZERO, IVec2::NEG_X, |_| false), None);
+        assert_eq!(grid_step(&map, IVec2::ZERO, IVec2::NEG_Y, |_| false), None);
+        assert_eq!(grid_step(&map, IVec2::ZERO, IVec2::X, |_| false), Some(IVec2::X));
+    }
+
+    #[test]
+    fn the_collision_box_does_not_overlap_walls() {
+        let map = map(&["###", "#.#", "###"]);
+        let centre = tile_to_world(IVec2::ONE);
+        assert!(fits(&map, centre));
+        assert!(!fits(&map, centre + Vec2::new(TILE_SIZE * 0.2, 0.0)));
+    }
+
+    #[test]
+    fn free_movement_slides_along_walls() {
+        let map = map(&["#####", "#...#", "#...#", "#####"]);
+        let start = tile_to_world(IVec2::new(2, 2));
+        let moved = slide(&map, start, Vec2::new(4.0, TILE_SIZE));
+        assert_eq!(moved, start + Vec2::new(4.0, 0.0));
+    }
+
+    #[test]
+    fn free_movement_stops_at_the_map_edge() {
+        let map = map(&["...", "..."]);
+        let start = tile_to_world(IVec2::ZERO);
+        assert_eq!(slide(&map, start, Vec2::new(-TILE_SIZE, 0.0)), start);
+        assert_eq!(slide(&map, start, Vec2::new(4.0, 0.0)), start + Vec2::new(4.0, 0.0));
+    }
+}
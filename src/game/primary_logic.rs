@@ -1,15 +1,18 @@
+use crate::game::camera::{camera_smoothing, CameraController, CameraShake};
 use crate::game::internal::brighten;
 use crate::game::internal::DefaultColor;
+use crate::game::quests::QuestState;
 use crate::game::textcolor_smoothing::{TargetTextColor, TextColorSmoothing};
 use crate::utils::buttoning::{ButtonClickEvent, ButtonClickType, Clickable, Hovered, TextButton};
 use crate::utils::position_smoothing::{
     PSmoothing, PositionSmoothing, TargetPosition, DEFAULT_END_THRESHOLD,
 };
-use crate::utils::responsive::{ResponsivePosition, ResponsiveValue};
 use bevy::core_pipeline::bloom::BloomSettings;
 use bevy::core_pipeline::tonemapping::Tonemapping;
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 
 ///
 /// Requires [utils::position_smoothing::PositionSmoothingPlugin]
@@ -17,31 +20,143 @@ use bevy::sprite::Anchor;
 #[derive(Default)]
 pub struct GameLogicPlugin;
 
-#[derive(Default, Resource)]
-pub struct GameData {}
+///
+/// Everything about a playthrough which is kept in a save slot.
+///
+#[derive(Default, Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameData {
+    /// Id of the level the player is on.
+    pub level: String,
+    /// Human readable name of where the player is, shown on the save slot.
+    pub location_name: String,
+    /// Tile the player is standing on, or `None` to start at the level's spawn point.
+    pub position: Option<[i32; 2]>,
+    pub discovered_secrets: BTreeSet<String>,
+    /// Item id to count.
+    pub inventory: BTreeMap<String, u32>,
+    /// Seconds played on this save.
+    pub play_time: f64,
+    /// Level id to [GameData::play_time] when the player first entered the level.
+    pub level_entered: BTreeMap<String, f64>,
+    pub flags: BTreeSet<String>,
+    /// Discovered secrets whose codex page hasn't been opened yet.
+    pub unread_secrets: BTreeSet<String>,
+    /// Seed of the generated world this save plays in, or `None` for the hand-made levels.
+    pub procedural_seed: Option<u64>,
+    /// Level id to the tiles the player has seen there.
+    pub explored: BTreeMap<String, BTreeSet<[i32; 2]>>,
+    /// Quest id to the progress on every quest which has been started.
+    pub quests: BTreeMap<String, QuestState>,
+    /// Level id to how many hints the player has taken there.
+    pub hints_used: BTreeMap<String, u32>,
+    /// Secret id to how many of its hints have been given.
+    pub hints_taken: BTreeMap<String, usize>,
+    /// [GameData::play_time] when the last hint was given.
+    pub last_hint: Option<f64>,
+    /// Tile of the last checkpoint reached on this level, where the player goes back to when they lose
+    /// all their health. `None` respawns them at the level's spawn point.
+    pub respawn: Option<[i32; 2]>,
+}
+
+impl GameData {
+    pub fn has_item(&self, item: &str) -> bool {
+        self.inventory.get(item).is_some_and(|count| *count > 0)
+    }
+
+    pub fn give_item(&mut self, item: &str) {
+        *self.inventory.entry(item.to_string()).or_default() += 1;
+    }
+
+    ///
+    /// Takes one of `item`, returning `false` if there was none to take.
+    ///
+    pub fn take_item(&mut self, item: &str) -> bool {
+        let Some(count) = self.inventory.get_mut(item) else {
+            return false;
+        };
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            self.inventory.remove(item);
+        }
+        true
+    }
+}
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default, States)]
 pub enum GameState {
     #[default]
     MainMenu,
+    SlotSelect,
+    Settings,
+    InGame,
     Exiting,
 }
 
+///
+/// What is shown on top of the world while [GameState::InGame]. Gameplay only runs while [InGameScreen::Playing].
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default, SubStates)]
+#[source(GameState = GameState::InGame)]
+pub enum InGameScreen {
+    #[default]
+    Playing,
+    Codex,
+    Dialogue,
+    Inventory,
+    Quests,
+}
+
+///
+/// The camera everything is rendered with. It lives for the whole app so that camera settings and
+/// effects carry across states.
+///
+#[derive(Default, Component)]
+pub struct MainCamera;
+
 impl Plugin for GameLogicPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<GameData>();
         app.init_state::<GameState>();
         app.enable_state_scoped_entities::<GameState>();
+        app.add_sub_state::<InGameScreen>();
+        app.enable_state_scoped_entities::<InGameScreen>();
+        app.add_systems(Startup, spawn_camera);
         main_menu::configure_app(app);
     }
 }
 
+pub fn spawn_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                hdr: true,
+                ..default()
+            },
+            tonemapping: Tonemapping::TonyMcMapface,
+            projection: OrthographicProjection {
+                far: 1000.,
+                near: -1000.,
+                viewport_origin: Vec2::new(0.0, 0.0),
+                ..default()
+            },
+            ..default()
+        },
+        BloomSettings::default(),
+        MainCamera,
+        CameraController::default(),
+        CameraShake::default(),
+        camera_smoothing(),
+        TargetPosition::default(),
+    ));
+}
+
 mod main_menu {
     use super::*;
-    use std::time::Instant;
-    use crate::game::internal;
-    use crate::game::internal::ScriptControlled;
-    use crate::utils::buttoning::GenericBoundingBox;
+    use crate::game::internal::{menu_button, menu_text, ScriptControlled, UI_Z};
+    use crate::game::outro::{begin_exit, OutroSettings};
+    use crate::game::saves::SaveSlots;
+    use crate::game::slot_select::{start_game, GameStart};
 
     pub fn configure_app(app: &mut App) {
         app.add_systems(OnEnter(GameState::MainMenu), main_menu::on_enter);
@@ -51,11 +166,22 @@ mod main_menu {
 
     #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Component)]
     pub enum MenuButton {
+        Continue,
         Play,
         Quit,
         Settings,
+        ConfirmQuit,
+        CancelQuit,
     }
 
+    ///
+    /// Marks the entities making up the "Are you sure?" prompt shown before quitting.
+    ///
+    #[derive(Default, Component)]
+    pub struct QuitPrompt;
+
+    const TITLE_COLOR: Color = Color::srgb(1.4, 2.1, 2.4);
+
     #[derive(Component)]
     pub struct MenuController {
         pub title: Entity,
@@ -63,148 +189,125 @@ mod main_menu {
         pub quit_button: Entity,
     }
 
-    pub fn spawn_camera(commands: &mut Commands) {
-        commands.spawn((
-            Camera2dBundle {
-                camera: Camera {
-                    hdr: true,
-                    ..default()
-                },
-                tonemapping: Tonemapping::TonyMcMapface,
-                projection: OrthographicProjection {
-                    far: 1000.,
-                    near: -1000.,
-                    viewport_origin: Vec2::new(0.0, 0.0),
-                    ..default()
-                },
-                ..default()
-            },
-            BloomSettings::default(),
-            StateScoped(GameState::MainMenu),
-        ));
+    ///
+    /// Slows the glide of a menu entry, so the entries arrive one after another.
+    ///
+    fn glide(modifier: f32) -> PositionSmoothing {
+        PositionSmoothing::P(PSmoothing {
+            modifier,
+            end_threshold: DEFAULT_END_THRESHOLD,
+        })
     }
 
     pub fn spawn_title(commands: &mut Commands) -> Entity {
+        let title = menu_text("Secrets of the Path", TITLE_COLOR, 60.0, 50.0, 90.0, Anchor::Center);
         commands
-            .spawn((
-                StateScoped(GameState::MainMenu),
-                Text2dBundle {
-                    text: Text::from_section(
-                        "Secrets of the Path",
-                        TextStyle {
-                            color: Color::srgb(1.4, 2.1, 2.4),
-                            font_size: 60.0,
-                            font: Handle::default(),
-                            ..default()
-                        },
-                    ),
-                    transform: Transform::from_xyz(640.0, 1000.0, 1.0),
-                    text_anchor: Anchor::Center,
-                    ..default()
-                },
-                PositionSmoothing::P(PSmoothing {
-                    modifier: 2.5,
-                    end_threshold: DEFAULT_END_THRESHOLD,
-                }),
-                TargetPosition::default(),
-                ResponsivePosition::new(
-                    ResponsiveValue::Percentage(50.0),
-                    ResponsiveValue::Percentage(90.0),
-                    ResponsiveValue::Absolute(0.0),
-                ),
-            ))
+            .spawn((StateScoped(GameState::MainMenu), title))
+            // The title drops in from above while everything else rises from below.
+            .insert((Transform::from_xyz(640.0, 1000.0, UI_Z), glide(2.5)))
             .id()
     }
 
-    pub fn spawn_buttons(commands: &mut Commands) -> (Entity, Entity) {
-        (
+    pub fn spawn_buttons(commands: &mut Commands) -> (Entity, Entity, Entity) {
+        let mut spawn = |text, color, y, modifier, button| {
             commands
                 .spawn((
                     StateScoped(GameState::MainMenu),
-                    Text2dBundle {
-                        text: Text::from_section(
-                            "Play",
-                            TextStyle {
-                                color: Color::srgb(1.4, 2.1, 1.4),
-                                font_size: 30.0,
-                                font: Handle::default(),
-                                ..default()
-                            },
-                        ),
-                        transform: Transform::from_xyz(640.0, -250.0, 1.0),
-                        text_anchor: Anchor::Center,
-                        ..default()
-                    },
-                    PositionSmoothing::P(PSmoothing {
-                        modifier: 2.3,
-                        end_threshold: DEFAULT_END_THRESHOLD,
-                    }),
-                    TargetPosition(Vec3::new(640.0, 0.0, 0.0)),
-                    ResponsivePosition::new(
-                        ResponsiveValue::Percentage(50.0),
-                        ResponsiveValue::Percentage(50.0),
-                        ResponsiveValue::Absolute(0.0),
-                    ),
-                    DefaultColor(Color::srgb(1.4, 2.1, 1.4)),
-                    TargetTextColor(Color::srgb(1.4, 2.1, 1.4)),
-                    TextColorSmoothing::default(),
-                    TextButton,
-                    MenuButton::Play,
+                    menu_button(text, color, 30.0, 50.0, y, Anchor::Center),
+                    button,
                 ))
-                .id(),
+                .insert(glide(modifier))
+                .id()
+        };
+        (
+            spawn("Play", Color::srgb(1.4, 2.1, 1.4), 50.0, 2.3, MenuButton::Play),
+            spawn("Settings", Color::srgb(1.4, 1.4, 2.1), 43.0, 2.2, MenuButton::Settings),
+            spawn("Quit", Color::srgb(2.1, 1.4, 1.4), 36.0, 2.1, MenuButton::Quit),
+        )
+    }
+
+    ///
+    /// Continues the most recent save. Shown dimmed and unclickable when there is nothing to continue.
+    ///
+    pub fn spawn_continue_button(commands: &mut Commands, enabled: bool) -> Entity {
+        let mut entity = if enabled {
+            let button = menu_button("Continue", Color::srgb(2.4, 2.1, 1.4), 30.0, 50.0, 57.0, Anchor::Center);
+            commands.spawn((StateScoped(GameState::MainMenu), button, MenuButton::Continue))
+        } else {
+            let text = menu_text("Continue", Color::srgb(0.5, 0.5, 0.5), 30.0, 50.0, 57.0, Anchor::Center);
+            commands.spawn((StateScoped(GameState::MainMenu), text, MenuButton::Continue))
+        };
+        entity.insert(glide(2.4)).id()
+    }
+
+    pub fn on_enter(mut commands: Commands, slots: Res<SaveSlots>) {
+        let title_e = spawn_title(&mut commands);
+        spawn_continue_button(&mut commands, slots.most_recent().is_some());
+        let (play_btn_e, _, quit_btn_e) = spawn_buttons(&mut commands);
+
+        commands.spawn((
+            StateScoped(GameState::MainMenu),
+            MenuController {
+                title: title_e,
+                play_button: play_btn_e,
+                quit_button: quit_btn_e,
+            },
+        ));
+    }
+
+    pub fn spawn_quit_prompt(commands: &mut Commands) {
+        commands
+            .spawn((
+                StateScoped(GameState::MainMenu),
+                menu_text("Are you sure?", Color::srgb(2.1, 2.1, 2.1), 30.0, 50.0, 24.0, Anchor::Center),
+                QuitPrompt,
+            ))
+            .insert(glide(2.5));
+
+        for (label, color, x, button) in [
+            ("Yes", Color::srgb(2.1, 1.4, 1.4), 45.0, MenuButton::ConfirmQuit),
+            ("No", Color::srgb(1.4, 2.1, 1.4), 55.0, MenuButton::CancelQuit),
+        ] {
             commands
                 .spawn((
                     StateScoped(GameState::MainMenu),
-                    Text2dBundle {
-                        text: Text::from_section(
-                            "Quit",
-                            TextStyle {
-                                color: Color::srgb(2.1, 1.4, 1.4),
-                                font_size: 30.0,
-                                font: Handle::default(),
-                                ..default()
-                            },
-                        ),
-                        transform: Transform::from_xyz(640.0, -250.0, 1.0),
-                        text_anchor: Anchor::Center,
-                        ..default()
-                    },
-                    PositionSmoothing::P(PSmoothing {
-                        modifier: 2.1,
-                        end_threshold: DEFAULT_END_THRESHOLD,
-                    }),
-                    TargetPosition(Vec3::new(640.0, 0.0, 0.0)),
-                    ResponsivePosition::new(
-                        ResponsiveValue::Percentage(50.0),
-                        ResponsiveValue::Percentage(40.0),
-                        ResponsiveValue::Absolute(0.0),
-                    ),
-                    DefaultColor(Color::srgb(2.1, 1.4, 1.4)),
-                    TargetTextColor(Color::srgb(2.1, 1.4, 1.4)),
-                    TextColorSmoothing::default(),
-                    TextButton,
-                    MenuButton::Quit,
+                    menu_button(label, color, 30.0, x, 16.0, Anchor::Center),
+                    button,
+                    QuitPrompt,
                 ))
-                .id(),
-        )
+                .insert(glide(2.3));
+        }
     }
 
-    pub fn on_enter(mut commands: Commands) {
-        spawn_camera(&mut commands);
-        let title_e = spawn_title(&mut commands);
-        let (play_btn_e, quit_btn_e) = spawn_buttons(&mut commands);
-
-        commands.spawn(MenuController {
-            title: title_e,
-            play_button: play_btn_e,
-            quit_button: quit_btn_e,
+    fn flash_button(commands: &mut Commands, entity: Entity) {
+        commands.entity(entity).add(|mut e: EntityWorldMut| {
+            e.insert(ScriptControlled);
+            let newcolor = e.get::<DefaultColor>().map(|dc| dc.0).unwrap_or(Color::srgb(2.1, 1.4, 1.4));
+            if let Some(mut ttc) = e.get_mut::<TargetTextColor>() {
+                ttc.0 = brighten(newcolor, 64.0);
+            }
+            if let Some(mut ts) = e.get_mut::<TextColorSmoothing>() {
+                ts.proportional = 0.35;
+            }
         });
     }
 
+    fn quit(commands: &mut Commands, menu_controller: &MenuController, next_state: &mut NextState<GameState>) {
+        flash_button(commands, menu_controller.quit_button);
+        // The title isn't a button, so it needs a colour to fade from for the outro to fade it out too.
+        commands.entity(menu_controller.title).insert(TargetTextColor(TITLE_COLOR));
+        begin_exit(commands, next_state);
+    }
+
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
     fn menu_button_clicks(
-        query: Query<(&MenuButton), (With<Hovered>, With<Clickable>, Without<ScriptControlled>)>,
+        query: Query<&MenuButton, (With<Hovered>, With<Clickable>, Without<ScriptControlled>)>,
+        prompt_q: Query<Entity, With<QuitPrompt>>,
         mut event_reader: EventReader<ButtonClickEvent>,
         menu_controller_q: Query<&MenuController>,
+        outro_settings: Res<OutroSettings>,
+        slots: Res<SaveSlots>,
+        mut next_state: ResMut<NextState<GameState>>,
         mut commands: Commands,
     ) {
         if menu_controller_q.is_empty() { return; }
@@ -216,41 +319,89 @@ mod main_menu {
             {
                 if let Ok(menu_button) = query.get(event.entity) {
                     match menu_button {
-                        MenuButton::Play => info!("Play Button Clicked"),
+                        MenuButton::Continue => {
+                            if let Some(slot) = slots.most_recent() {
+                                start_game(slot, GameStart::Continue, &mut commands);
+                            }
+                        }
+                        MenuButton::Play => {
+                            info!("Play Button Clicked");
+                            flash_button(&mut commands, menu_controller.play_button);
+                            next_state.set(GameState::SlotSelect);
+                        }
                         MenuButton::Quit => {
                             info!("Quit Button Clicked");
-                            commands.entity(menu_controller.quit_button).add(|mut e: EntityWorldMut| {
-                                e.insert(ScriptControlled::default());
-                                let newcolor = e.get::<DefaultColor>().map(|dc| dc.0).unwrap_or(Color::srgb(2.1, 1.4, 1.4));
-                                if let Some(mut ttc) = e.get_mut::<TargetTextColor>() {
-                                    ttc.0 = brighten(newcolor, 64.0);
+                            if outro_settings.confirm_quit {
+                                if prompt_q.is_empty() {
+                                    spawn_quit_prompt(&mut commands);
                                 }
-                                if let Some(mut ts) = e.get_mut::<TextColorSmoothing>() {
-                                    ts.proportional = 0.35;
-                                }
-                            });
+                            } else {
+                                quit(&mut commands, menu_controller, &mut next_state);
+                            }
+                        }
+                        MenuButton::ConfirmQuit => {
+                            flash_button(&mut commands, event.entity);
+                            quit(&mut commands, menu_controller, &mut next_state);
+                        }
+                        MenuButton::CancelQuit => {
+                            for entity in prompt_q.iter() {
+                                commands.entity(entity).despawn_recursive();
+                            }
+                        }
+                        MenuButton::Settings => {
+                            info!("Settings Button Clicked");
+                            next_state.set(GameState::Settings);
                         }
-                        MenuButton::Settings => info!("Settings Button Clicked"),
                     }
                 }
             }
         }
     }
 
+    #[allow(clippy::type_complexity)]
     fn hovered_texts(
-        mut query: Query<(&mut TargetTextColor, &DefaultColor), (Added<Hovered>, (With<MenuButton>, Without<ScriptControlled>))>,
+        mut query: Query<(&mut TargetTextColor, &DefaultColor), (Added<Hovered>, (With<TextButton>, Without<ScriptControlled>))>,
     ) {
         for (mut target_color, default_color) in query.iter_mut() {
             target_color.0 = brighten(default_color.0, 2.0);
         }
     }
 
+    #[allow(clippy::type_complexity)]
     fn unhovered_texts(
         trigger: Trigger<OnRemove, Hovered>,
-        mut query: Query<(&mut TargetTextColor, &DefaultColor), (With<MenuButton>, Without<ScriptControlled>)>,
+        mut query: Query<(&mut TargetTextColor, &DefaultColor), (With<TextButton>, Without<ScriptControlled>)>,
     ) {
         if let Ok((mut target_color, default_color)) = query.get_mut(trigger.entity()) {
             target_color.0 = default_color.0;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_stack_in_the_inventory() {
+        let mut game_data = GameData::default();
+        game_data.give_item("pebble");
+        game_data.give_item("pebble");
+        game_data.give_item("key");
+        assert_eq!(game_data.inventory.get("pebble"), Some(&2));
+        assert_eq!(game_data.inventory.get("key"), Some(&1));
+    }
+
+    #[test]
+    fn taking_the_last_item_removes_it() {
+        let mut game_data = GameData::default();
+        game_data.give_item("pebble");
+        game_data.give_item("pebble");
+        assert!(game_data.take_item("pebble"));
+        assert!(game_data.has_item("pebble"));
+        assert!(game_data.take_item("pebble"));
+        assert!(!game_data.has_item("pebble"));
+        assert!(!game_data.inventory.contains_key("pebble"));
+        assert!(!game_data.take_item("pebble"));
+    }
+}
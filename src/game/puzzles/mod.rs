@@ -0,0 +1,226 @@
+use crate::game::interactables::{state_flag, unlocked_flag};
+use crate::game::internal::brighten;
+use crate::game::level::{CurrentLevel, InteractableKind, Level, PRESSURE_PLATE};
+use crate::game::player::{GridPosition, PlayerMovementSet};
+use crate::game::primary_logic::{GameData, GameState, InGameScreen};
+use crate::game::rewind::{RecordMovesSet, Rewind, Snapshot};
+use crate::game::textcolor_smoothing::{TargetSpriteColor, TextColorSmoothing};
+use crate::game::world::{tile_to_world, CurrentMap, FogOfWar, MapLoaded, MapSet, Tile, TileVisibility, TILE_SIZE};
+use crate::utils::position_smoothing::{PSmoothing, PositionSmoothing, TargetPosition};
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+mod signals;
+
+pub use signals::{door_open, SignalGraph};
+
+///
+/// Switches, pressure plates, doors and timed gates, wired together by the signals declared in the level.
+///
+#[derive(Default)]
+pub struct PuzzlesPlugin;
+
+///
+/// The signals of the current level.
+///
+#[derive(Resource, Default)]
+pub struct Signals(pub SignalGraph);
+
+///
+/// A pressure plate in the current level, pressed while anything with a [GridPosition] stands on it.
+///
+#[derive(Component)]
+pub struct PressurePlate {
+    pub id: String,
+    pub position: IVec2,
+}
+
+const PLATE_Z: f32 = 1.0;
+const PLATE_COLOR: Color = Color::srgb(0.5, 0.48, 0.42);
+const PRESSED_COLOR: Color = Color::srgb(0.9, 0.75, 0.35);
+/// How far a plate sinks when pressed.
+const PLATE_SINK: f32 = 3.0;
+
+impl Plugin for PuzzlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Signals>();
+        app.add_systems(
+            Update,
+            setup_puzzles
+                .in_set(MapSet::Prepare)
+                .run_if(in_state(GameState::InGame).and_then(on_event::<MapLoaded>())),
+        );
+        app.add_systems(
+            Update,
+            (
+                update_signals.after(RecordMovesSet).run_if(in_state(InGameScreen::Playing)),
+                update_plates,
+            )
+                .chain()
+                .after(PlayerMovementSet)
+                .before(MapSet::Render)
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+fn setup_puzzles(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    mut signals: ResMut<Signals>,
+    mut game_data: ResMut<GameData>,
+    mut current_map: ResMut<CurrentMap>,
+    old_plates: Query<Entity, With<PressurePlate>>,
+) {
+    for entity in old_plates.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(level) = levels.get(&current_level.handle) else {
+        return;
+    };
+
+    for entity in level.file.entities.iter().filter(|entity| entity.kind == PRESSURE_PLATE) {
+        // Validation makes sure plates have ids.
+        let Some(id) = &entity.id else {
+            continue;
+        };
+        let position = IVec2::from(entity.position);
+        let translation = tile_to_world(position).extend(PLATE_Z);
+        commands.spawn((
+            StateScoped(GameState::InGame),
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(Vec2::splat(TILE_SIZE * 0.8)),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            TargetSpriteColor(Color::NONE),
+            TextColorSmoothing {
+                flat: 1.0,
+                proportional: 4.0,
+            },
+            TargetPosition(translation),
+            PositionSmoothing::P(PSmoothing {
+                modifier: 12.0,
+                end_threshold: 0.1,
+            }),
+            PressurePlate { id: id.clone(), position },
+        ));
+    }
+
+    // Settle the doors before anything is built from the map, so closed ones never flash open. The player
+    // isn't spawned yet, but a door they were saved standing in has to stay open for them.
+    let occupied: HashSet<IVec2> = game_data.position.map(IVec2::from).into_iter().collect();
+    signals.0 = SignalGraph::new(&level.file.signals);
+    signals.0.update(&mut game_data, &HashSet::new(), 0.0);
+    apply_doors(level, &signals.0, &mut game_data, &mut current_map, &occupied);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_signals(
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    mut signals: ResMut<Signals>,
+    mut game_data: ResMut<GameData>,
+    mut current_map: ResMut<CurrentMap>,
+    mut rewind: ResMut<Rewind>,
+    standing: Query<&GridPosition>,
+    plates: Query<&PressurePlate>,
+    time: Res<Time>,
+) {
+    let Some(level) = levels.get(&current_level.handle) else {
+        return;
+    };
+    let occupied: HashSet<IVec2> = standing.iter().map(|grid_position| grid_position.0).collect();
+    let pressed: HashSet<String> = plates
+        .iter()
+        .filter(|plate| occupied.contains(&plate.position))
+        .map(|plate| plate.id.clone())
+        .collect();
+
+    // Latching is undone along with whatever pressed the plate or flipped the switch. Doors aren't recorded, as
+    // they follow the signals again after an undo.
+    let before = Snapshot::of(&game_data);
+    signals.0.update(&mut game_data, &pressed, time.delta_seconds());
+    rewind.history.fold_since(&before, &game_data);
+    apply_doors(level, &signals.0, &mut game_data, &mut current_map, &occupied);
+}
+
+///
+/// Opens and closes the level's doors, walling off the tiles of the closed ones. A door's [state_flag] holds
+/// whether it is open.
+///
+fn apply_doors(
+    level: &Level,
+    signals: &SignalGraph,
+    game_data: &mut GameData,
+    current_map: &mut ResMut<CurrentMap>,
+    occupied: &HashSet<IVec2>,
+) {
+    let doors = level
+        .file
+        .entities
+        .iter()
+        .filter(|entity| entity.interactable() == Some(InteractableKind::Door));
+    for door in doors {
+        let Some(id) = &door.id else {
+            continue;
+        };
+        let position = IVec2::from(door.position);
+        let flag = state_flag(id);
+        let was_open = game_data.flags.contains(&flag);
+        let open = door_open(
+            door.property("signal").is_some_and(|signal| signals.is_on(signal)),
+            game_data.flags.contains(&unlocked_flag(id)),
+            was_open,
+            occupied.contains(&position),
+        );
+
+        if open && !was_open {
+            game_data.flags.insert(flag);
+        } else if !open && was_open {
+            game_data.flags.remove(&flag);
+        }
+        let tile = if open {
+            level.map.get(position).unwrap_or_default()
+        } else {
+            Tile::Wall
+        };
+        // Only touch the map when it changes, as the fog and the tiles rebuild whenever it does.
+        if current_map.map.get(position) != Some(tile) {
+            current_map.map.set(position, tile);
+        }
+    }
+}
+
+///
+/// Sinks and lights up pressed plates, following the fog like everything else in the world.
+///
+fn update_plates(
+    mut plates: Query<(&PressurePlate, &mut TargetSpriteColor, &mut TargetPosition)>,
+    standing: Query<&GridPosition>,
+    fog: Res<FogOfWar>,
+) {
+    for (plate, mut target_color, mut target_position) in plates.iter_mut() {
+        let pressed = standing.iter().any(|grid_position| grid_position.0 == plate.position);
+        let color = if pressed { PRESSED_COLOR } else { PLATE_COLOR };
+        let color = match fog.get(plate.position) {
+            TileVisibility::Unseen => Color::NONE,
+            TileVisibility::Seen => brighten(color, 0.35),
+            TileVisibility::Visible => color,
+        };
+        if target_color.0 != color {
+            target_color.0 = color;
+        }
+
+        let sink = if pressed { PLATE_SINK } else { 0.0 };
+        let translation = (tile_to_world(plate.position) - Vec2::Y * sink).extend(PLATE_Z);
+        if target_position.0 != translation {
+            target_position.0 = translation;
+        }
+    }
+}
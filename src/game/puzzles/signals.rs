@@ -0,0 +1,265 @@
+use crate::game::interactables::state_flag;
+use crate::game::items::used_flag;
+use crate::game::level::{LevelSignal, SignalInput};
+use crate::game::primary_logic::GameData;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+///
+/// The [GameData] flag remembering that a latched signal has turned on.
+///
+pub fn latch_flag(id: &str) -> String {
+    format!("signal:{id}")
+}
+
+///
+/// A level's signals and their state, stepped once per frame. This knows nothing about the ECS, everything
+/// it reads is passed in.
+///
+#[derive(Clone, Debug, Default)]
+pub struct SignalGraph {
+    /// In an order where every signal comes after the signals it reads.
+    signals: Vec<LevelSignal>,
+    values: BTreeMap<String, bool>,
+    /// Seconds left before a held signal turns off.
+    held: HashMap<String, f32>,
+}
+
+impl SignalGraph {
+    ///
+    /// Builds the graph for a validated level, whose signals can't depend on themselves.
+    ///
+    pub fn new(signals: &[LevelSignal]) -> SignalGraph {
+        let mut ordered: Vec<LevelSignal> = Vec::with_capacity(signals.len());
+        let mut pending: Vec<&LevelSignal> = signals.iter().collect();
+        while !pending.is_empty() {
+            let before = pending.len();
+            pending.retain(|signal| {
+                let ready = signal.input.walk().into_iter().all(|input| match input {
+                    SignalInput::Signal(id) => ordered.iter().any(|s| s.id == *id),
+                    _ => true,
+                });
+                if ready {
+                    ordered.push((*signal).clone());
+                }
+                !ready
+            });
+            if pending.len() == before {
+                // Only possible with a cycle or a missing signal, which validation rules out. Drop them.
+                break;
+            }
+        }
+
+        SignalGraph {
+            signals: ordered,
+            values: BTreeMap::new(),
+            held: HashMap::new(),
+        }
+    }
+
+    pub fn is_on(&self, id: &str) -> bool {
+        self.values.get(id).copied().unwrap_or(false)
+    }
+
+    ///
+    /// Works out every signal from the game's state and the plates which are `pressed`, `delta` seconds after
+    /// the last update. Latched signals which turn on are remembered in `game_data`. Returns whether any signal
+    /// changed.
+    ///
+    pub fn update(&mut self, game_data: &mut GameData, pressed: &HashSet<String>, delta: f32) -> bool {
+        let mut changed = false;
+        for signal in &self.signals {
+            let input = self.read(&signal.input, game_data, pressed);
+            let latched = signal.latch && game_data.flags.contains(&latch_flag(&signal.id));
+
+            let held = self.held.entry(signal.id.clone()).or_default();
+            let on = if input {
+                *held = signal.hold;
+                true
+            } else {
+                *held = (*held - delta).max(0.0);
+                *held > 0.0
+            };
+
+            let on = on || latched;
+            if on && signal.latch && !latched {
+                game_data.flags.insert(latch_flag(&signal.id));
+            }
+            if self.values.insert(signal.id.clone(), on) != Some(on) {
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn read(&self, input: &SignalInput, game_data: &GameData, pressed: &HashSet<String>) -> bool {
+        match input {
+            SignalInput::Switch(id) => game_data.flags.contains(&state_flag(id)),
+            SignalInput::Plate(id) => pressed.contains(id),
+            SignalInput::HasItem(item) => game_data.has_item(item),
+            SignalInput::UsedItem(item) => game_data.flags.contains(&used_flag(item)),
+            SignalInput::Flag(flag) => game_data.flags.contains(flag),
+            SignalInput::Signal(id) => self.is_on(id),
+            SignalInput::Not(input) => !self.read(input, game_data, pressed),
+            SignalInput::All(inputs) => inputs.iter().all(|input| self.read(input, game_data, pressed)),
+            SignalInput::Any(inputs) => inputs.iter().any(|input| self.read(input, game_data, pressed)),
+        }
+    }
+}
+
+///
+/// Whether a door should be open. A door which is open stays open while something stands in it, so nothing
+/// gets shut inside a wall.
+///
+pub fn door_open(signal: bool, unlocked: bool, was_open: bool, occupied: bool) -> bool {
+    signal || unlocked || (was_open && occupied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rewind::{History, Snapshot};
+    use bevy::math::IVec2;
+
+    fn signal(id: &str, input: SignalInput) -> LevelSignal {
+        LevelSignal {
+            id: id.to_string(),
+            input,
+            hold: 0.0,
+            latch: false,
+        }
+    }
+
+    fn flag(flag: &str) -> SignalInput {
+        SignalInput::Flag(flag.to_string())
+    }
+
+    fn data_with(flags: &[&str]) -> GameData {
+        GameData {
+            flags: flags.iter().map(|flag| flag.to_string()).collect(),
+            ..GameData::default()
+        }
+    }
+
+    #[test]
+    fn signals_read_signals_declared_after_them() {
+        let mut graph = SignalGraph::new(&[
+            signal("door", SignalInput::Signal("lever".to_string())),
+            signal("lever", SignalInput::Switch("lever".to_string())),
+        ]);
+        let mut game_data = data_with(&[&state_flag("lever")]);
+        // One update is enough, because "lever" is worked out first.
+        assert!(graph.update(&mut game_data, &HashSet::new(), 0.0));
+        assert!(graph.is_on("lever"));
+        assert!(graph.is_on("door"));
+        assert!(!graph.update(&mut game_data, &HashSet::new(), 0.0));
+    }
+
+    #[test]
+    fn not_all_and_any() {
+        let mut graph = SignalGraph::new(&[
+            signal("not", SignalInput::Not(Box::new(flag("a")))),
+            signal("all", SignalInput::All(vec![flag("a"), SignalInput::Plate("plate".to_string())])),
+            signal("any", SignalInput::Any(vec![flag("a"), SignalInput::Plate("plate".to_string())])),
+        ]);
+        let pressed = HashSet::from(["plate".to_string()]);
+
+        graph.update(&mut GameData::default(), &HashSet::new(), 0.0);
+        assert_eq!([graph.is_on("not"), graph.is_on("all"), graph.is_on("any")], [true, false, false]);
+
+        graph.update(&mut GameData::default(), &pressed, 0.0);
+        assert_eq!([graph.is_on("not"), graph.is_on("all"), graph.is_on("any")], [true, false, true]);
+
+        graph.update(&mut data_with(&["a"]), &pressed, 0.0);
+        assert_eq!([graph.is_on("not"), graph.is_on("all"), graph.is_on("any")], [false, true, true]);
+    }
+
+    #[test]
+    fn held_signals_count_down_after_their_input_turns_off() {
+        let mut graph = SignalGraph::new(&[LevelSignal {
+            hold: 1.0,
+            ..signal("gate", flag("plate"))
+        }]);
+        graph.update(&mut data_with(&["plate"]), &HashSet::new(), 0.0);
+        assert!(graph.is_on("gate"));
+
+        let mut game_data = GameData::default();
+        assert!(!graph.update(&mut game_data, &HashSet::new(), 0.6));
+        assert!(graph.is_on("gate"));
+        assert!(graph.update(&mut game_data, &HashSet::new(), 0.6));
+        assert!(!graph.is_on("gate"));
+
+        // Turning the input back on starts the full hold again.
+        graph.update(&mut data_with(&["plate"]), &HashSet::new(), 0.0);
+        graph.update(&mut game_data, &HashSet::new(), 0.9);
+        assert!(graph.is_on("gate"));
+    }
+
+    #[test]
+    fn latched_signals_stay_on_through_the_save() {
+        let signals = [LevelSignal {
+            latch: true,
+            ..signal("bridge", flag("lever"))
+        }];
+        let mut graph = SignalGraph::new(&signals);
+        let mut game_data = data_with(&["lever"]);
+        graph.update(&mut game_data, &HashSet::new(), 0.0);
+        assert!(game_data.flags.contains(&latch_flag("bridge")));
+
+        game_data.flags.remove("lever");
+        graph.update(&mut game_data, &HashSet::new(), 0.0);
+        assert!(graph.is_on("bridge"));
+
+        // A fresh graph, as after loading the save, reads the latch back from the flags.
+        let mut loaded = SignalGraph::new(&signals);
+        loaded.update(&mut game_data, &HashSet::new(), 0.0);
+        assert!(loaded.is_on("bridge"));
+    }
+
+    #[test]
+    fn cycles_are_dropped() {
+        let mut graph = SignalGraph::new(&[
+            signal("a", SignalInput::Signal("b".to_string())),
+            signal("b", SignalInput::Not(Box::new(SignalInput::Signal("a".to_string())))),
+            signal("c", flag("c")),
+        ]);
+        graph.update(&mut data_with(&["c"]), &HashSet::new(), 0.0);
+        assert!(!graph.is_on("a"));
+        assert!(!graph.is_on("b"));
+        assert!(graph.is_on("c"));
+    }
+
+    #[test]
+    fn doors_stay_open_while_occupied() {
+        assert!(door_open(true, false, false, false));
+        assert!(door_open(false, true, false, false));
+        assert!(door_open(false, false, true, true));
+        assert!(!door_open(false, false, true, false));
+        // Something standing in a closed door doesn't open it.
+        assert!(!door_open(false, false, false, true));
+    }
+
+    #[test]
+    fn undoing_a_move_onto_a_latched_plate_unlatches_it() {
+        let signals = [LevelSignal {
+            id: "bridge".to_string(),
+            input: SignalInput::Plate("plate".to_string()),
+            hold: 0.0,
+            latch: true,
+        }];
+        let mut graph = SignalGraph::new(&signals);
+        let mut game_data = GameData::default();
+        let mut history = History::default();
+
+        history.record_move(IVec2::new(1, 1), IVec2::new(2, 1));
+        let before = Snapshot::of(&game_data);
+        graph.update(&mut game_data, &HashSet::from(["plate".to_string()]), 0.0);
+        history.fold_since(&before, &game_data);
+        assert!(game_data.flags.contains(&latch_flag("bridge")));
+        assert_eq!(history.steps.len(), 1, "the latch is part of the move onto the plate");
+
+        assert_eq!(history.undo(&mut game_data), Some(Some(IVec2::new(1, 1))));
+        assert!(game_data.flags.is_empty());
+        graph.update(&mut game_data, &HashSet::new(), 0.0);
+        assert!(!graph.is_on("bridge"));
+    }
+}
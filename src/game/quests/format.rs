@@ -0,0 +1,166 @@
+use crate::game::dialogue::{DialogueCondition, DialogueEffect};
+use crate::utils::ron_asset::RonParseError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+
+///
+/// Version of the quest format this build reads.
+///
+pub const QUEST_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum QuestError {
+    #[error("could not read quests: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] RonParseError),
+    #[error("quests have format version {0}, but this build only supports up to {QUEST_FORMAT_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("quest id \"{0}\" is used more than once")]
+    DuplicateId(String),
+    #[error("quest \"{0}\" has no objectives")]
+    NoObjectives(String),
+    #[error("objective {index} of quest \"{quest}\" has an empty area or a count of 0")]
+    InvalidObjective { quest: String, index: usize },
+}
+
+///
+/// A set of quests as written in a `.quests.ron` file.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuestsFile {
+    pub version: u32,
+    pub quests: Vec<QuestDef>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuestDef {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// The quest starts as soon as this holds.
+    #[serde(default)]
+    pub start: DialogueCondition,
+    /// All of them have to be done, in any order.
+    pub objectives: Vec<Objective>,
+    /// Applied when the last objective is done.
+    #[serde(default)]
+    pub rewards: Vec<DialogueEffect>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Objective {
+    /// What the tracker and the quest log show, e.g. "Find the hermit".
+    pub description: String,
+    pub kind: ObjectiveKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ObjectiveKind {
+    /// Standing anywhere in the rectangle from `min` to `max` of a level.
+    Reach { level: String, min: [i32; 2], max: [i32; 2] },
+    /// Finding `count` secrets after the quest starts, only counting those in `level` if it is given.
+    FindSecrets {
+        count: u32,
+        #[serde(default)]
+        level: Option<String>,
+    },
+    /// Talking to the NPC with this entity id.
+    TalkTo { npc: String },
+    /// Carrying `count` of an item at once.
+    ObtainItem {
+        item: String,
+        #[serde(default = "default_count")]
+        count: u32,
+    },
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+impl ObjectiveKind {
+    ///
+    /// The progress at which the objective is done.
+    ///
+    pub fn target(&self) -> u32 {
+        match self {
+            ObjectiveKind::Reach { .. } | ObjectiveKind::TalkTo { .. } => 1,
+            ObjectiveKind::FindSecrets { count, .. } | ObjectiveKind::ObtainItem { count, .. } => *count,
+        }
+    }
+}
+
+impl QuestsFile {
+    pub fn validate(&self) -> Result<(), QuestError> {
+        if self.version > QUEST_FORMAT_VERSION {
+            return Err(QuestError::UnsupportedVersion(self.version));
+        }
+        let mut ids = HashSet::new();
+        for quest in &self.quests {
+            if !ids.insert(quest.id.as_str()) {
+                return Err(QuestError::DuplicateId(quest.id.clone()));
+            }
+            if quest.objectives.is_empty() {
+                return Err(QuestError::NoObjectives(quest.id.clone()));
+            }
+            for (i, objective) in quest.objectives.iter().enumerate() {
+                let invalid = match &objective.kind {
+                    ObjectiveKind::Reach { min, max, .. } => min[0] > max[0] || min[1] > max[1],
+                    kind => kind.target() == 0,
+                };
+                if invalid {
+                    return Err(QuestError::InvalidObjective {
+                        quest: quest.id.clone(),
+                        index: i + 1,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ron_asset::parse_ron;
+
+    fn quests(text: &str) -> QuestsFile {
+        parse_ron::<QuestsFile>(&format!("(version: 1, quests: [{text}])")).unwrap()
+    }
+
+    #[test]
+    fn shipped_quests_are_valid() {
+        let file = parse_ron::<QuestsFile>(include_str!("../../../assets/quests/first_steps.quests.ron")).unwrap();
+        file.validate().unwrap();
+    }
+
+    #[test]
+    fn areas_must_not_be_empty() {
+        let file = quests(
+            r#"(id: "walk", name: "Walk", objectives: [
+                (description: "Somewhere", kind: Reach(level: "grove", min: (0, 0), max: (3, 3))),
+                (description: "Nowhere", kind: Reach(level: "grove", min: (4, 0), max: (3, 3))),
+            ])"#,
+        );
+        assert!(matches!(
+            file.validate(),
+            Err(QuestError::InvalidObjective { quest, index: 2 }) if quest == "walk"
+        ));
+    }
+
+    #[test]
+    fn counts_of_zero_and_quests_without_objectives_are_rejected() {
+        let file = quests(r#"(id: "none", name: "None", objectives: [])"#);
+        assert!(matches!(file.validate(), Err(QuestError::NoObjectives(id)) if id == "none"));
+
+        let file = quests(
+            r#"(id: "zero", name: "Zero",
+                objectives: [(description: "Nothing", kind: ObtainItem(item: "pebble", count: 0))])"#,
+        );
+        assert!(matches!(file.validate(), Err(QuestError::InvalidObjective { index: 1, .. })));
+    }
+}
@@ -0,0 +1,216 @@
+use crate::game::interactables::{spawn_speech, Interact, Interactable};
+use crate::game::internal::menu_text;
+use crate::game::level::InteractableKind;
+use crate::game::player::{GridPosition, Player, PlayerMovementSet};
+use crate::game::primary_logic::{GameData, InGameScreen};
+use crate::game::saves::SaveGame;
+use crate::game::secrets::SecretDiscovered;
+use crate::utils::position_smoothing::PositionSmoothing;
+use crate::utils::ron_asset::{RonAsset, RonLoader};
+use bevy::asset::LoadedFolder;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+mod format;
+mod progress;
+mod screen;
+
+pub use format::*;
+pub use progress::{advance, QuestEvent, QuestState, QuestUpdate};
+
+///
+/// Quests defined in `.quests.ron` files in `assets/quests`, tracked in the corner of the screen while
+/// playing and listed in the quest log, opened with J.
+///
+#[derive(Default)]
+pub struct QuestsPlugin;
+
+///
+/// A validated set of quests.
+///
+#[derive(Asset, TypePath, Debug)]
+pub struct QuestSet {
+    pub file: QuestsFile,
+}
+
+impl RonAsset for QuestSet {
+    type File = QuestsFile;
+    type Error = QuestError;
+
+    const EXTENSION: &'static str = "quests.ron";
+
+    fn from_file(file: QuestsFile) -> Result<QuestSet, QuestError> {
+        file.validate()?;
+        Ok(QuestSet { file })
+    }
+}
+
+///
+/// Keeps every quest set in `assets/quests` loaded.
+///
+#[derive(Resource)]
+pub struct QuestCatalog {
+    _folder: Handle<LoadedFolder>,
+}
+
+impl QuestCatalog {
+    ///
+    /// All loaded quests, ordered by id.
+    ///
+    pub fn quests(quest_sets: &Assets<QuestSet>) -> Vec<&QuestDef> {
+        let mut quests: Vec<&QuestDef> = quest_sets.iter().flat_map(|(_, set)| &set.file.quests).collect();
+        quests.sort_by(|a, b| a.id.cmp(&b.id));
+        quests
+    }
+}
+
+///
+/// The list of active quests in the corner of the screen.
+///
+#[derive(Component)]
+struct QuestTracker;
+
+/// How many quests the tracker lists at once. The rest are in the quest log.
+const MAX_TRACKED: usize = 3;
+
+const TRACKER_FONT_SIZE: f32 = 14.0;
+const TRACKER_TITLE_COLOR: Color = Color::srgb(2.4, 2.1, 1.4);
+const TRACKER_COLOR: Color = Color::srgb(1.4, 1.4, 1.4);
+const TRACKER_DONE_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+
+impl Plugin for QuestsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<QuestSet>();
+        app.init_asset_loader::<RonLoader<QuestSet>>();
+        app.add_systems(Startup, load_catalog);
+        app.add_systems(OnEnter(InGameScreen::Playing), spawn_tracker);
+        app.add_systems(
+            Update,
+            (track_quests.after(PlayerMovementSet), update_tracker)
+                .chain()
+                .run_if(in_state(InGameScreen::Playing)),
+        );
+        app.add_plugins(screen::QuestLogPlugin);
+    }
+}
+
+fn load_catalog(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(QuestCatalog {
+        _folder: asset_server.load_folder("quests"),
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn track_quests(
+    mut commands: Commands,
+    quest_sets: Res<Assets<QuestSet>>,
+    mut game_data: ResMut<GameData>,
+    players: Query<&GridPosition, With<Player>>,
+    interactables: Query<&Interactable>,
+    mut interactions: EventReader<Interact>,
+    mut discoveries: EventReader<SecretDiscovered>,
+    mut save: EventWriter<SaveGame>,
+) {
+    let level = game_data.level.clone();
+    let player = players.iter().next().map(|grid_position| grid_position.0);
+
+    // Where the player stands counts every frame, so a quest started in the right place completes there.
+    let mut events: Vec<QuestEvent> = player
+        .map(|position| QuestEvent::Reached {
+            level: level.clone(),
+            position,
+        })
+        .into_iter()
+        .collect();
+    events.extend(discoveries.read().map(|_| QuestEvent::SecretFound { level: level.clone() }));
+    events.extend(interactions.read().filter_map(|interaction| {
+        let interactable = interactables.get(interaction.entity).ok()?;
+        if interactable.kind != InteractableKind::Npc {
+            return None;
+        }
+        Some(QuestEvent::TalkedTo {
+            npc: interactable.entity.id.clone()?,
+        })
+    }));
+
+    let quests = QuestCatalog::quests(&quest_sets);
+    let updates = advance(&quests, &mut game_data, &events);
+    for update in &updates {
+        let (id, text) = match update {
+            QuestUpdate::Started(id) => (id, "New quest"),
+            QuestUpdate::Completed(id) => (id, "Quest complete"),
+        };
+        let name = quests.iter().find(|quest| quest.id == *id).map_or(id.as_str(), |quest| &quest.name);
+        info!("{text}: {name} ({id})");
+        if let Some(position) = player {
+            spawn_speech(&mut commands, position, format!("{text}: {name}"));
+        }
+    }
+    if !updates.is_empty() {
+        save.send(SaveGame);
+    }
+}
+
+fn spawn_tracker(mut commands: Commands) {
+    commands
+        .spawn((
+            StateScoped(InGameScreen::Playing),
+            QuestTracker,
+            menu_text("", TRACKER_COLOR, TRACKER_FONT_SIZE, 2.0, 96.0, Anchor::TopLeft),
+        ))
+        .insert(PositionSmoothing::None);
+}
+
+///
+/// The lines of the tracker, with the color of each.
+///
+fn tracker_lines(quests: &[&QuestDef], game_data: &GameData) -> Vec<(String, Color)> {
+    let mut lines = Vec::new();
+    let active = quests.iter().filter_map(|quest| {
+        let state = game_data.quests.get(&quest.id)?;
+        (!state.completed).then_some((quest, state))
+    });
+    for (quest, state) in active.take(MAX_TRACKED) {
+        lines.push((format!("{}\n", quest.name), TRACKER_TITLE_COLOR));
+        for (i, objective) in quest.objectives.iter().enumerate() {
+            let target = objective.kind.target();
+            let progress = state.progress.get(i).copied().unwrap_or(0);
+            let count = if target > 1 { format!(" ({progress}/{target})") } else { String::new() };
+            let color = if state.is_done(quest, i) { TRACKER_DONE_COLOR } else { TRACKER_COLOR };
+            lines.push((format!("  - {}{count}\n", objective.description), color));
+        }
+    }
+    lines
+}
+
+fn update_tracker(
+    mut query: Query<&mut Text, With<QuestTracker>>,
+    quest_sets: Res<Assets<QuestSet>>,
+    game_data: Res<GameData>,
+) {
+    let lines = tracker_lines(&QuestCatalog::quests(&quest_sets), &game_data);
+    for mut text in query.iter_mut() {
+        let unchanged = text.sections.len() == lines.len()
+            && text
+                .sections
+                .iter()
+                .zip(&lines)
+                .all(|(section, (value, color))| section.value == *value && section.style.color == *color);
+        if unchanged {
+            continue;
+        }
+        text.sections = lines
+            .iter()
+            .map(|(value, color)| {
+                TextSection::new(
+                    value.clone(),
+                    TextStyle {
+                        color: *color,
+                        font_size: TRACKER_FONT_SIZE,
+                        ..default()
+                    },
+                )
+            })
+            .collect();
+    }
+}
@@ -0,0 +1,216 @@
+use super::{ObjectiveKind, QuestDef};
+use crate::game::primary_logic::GameData;
+use bevy::math::IVec2;
+use serde::{Deserialize, Serialize};
+
+///
+/// How far the player is with a quest they have started, kept in [GameData::quests].
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuestState {
+    /// Progress towards each objective, in the order the quest lists them.
+    pub progress: Vec<u32>,
+    pub completed: bool,
+}
+
+///
+/// Something which happened in the game that objectives can count.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum QuestEvent {
+    Reached { level: String, position: IVec2 },
+    SecretFound { level: String },
+    TalkedTo { npc: String },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum QuestUpdate {
+    Started(String),
+    Completed(String),
+}
+
+impl QuestState {
+    pub fn is_done(&self, quest: &QuestDef, index: usize) -> bool {
+        let target = quest.objectives.get(index).map_or(0, |objective| objective.kind.target());
+        self.progress.get(index).copied().unwrap_or(0) >= target
+    }
+}
+
+///
+/// Progress towards one objective after `events`. Progress never goes back, so an item which has been
+/// obtained stays obtained.
+///
+fn objective_progress(kind: &ObjectiveKind, progress: u32, game_data: &GameData, events: &[QuestEvent]) -> u32 {
+    let progress = match kind {
+        ObjectiveKind::Reach { level, min, max } => {
+            let inside = events.iter().any(|event| match event {
+                QuestEvent::Reached { level: at, position } => {
+                    at == level && position.cmpge(IVec2::from(*min)).all() && position.cmple(IVec2::from(*max)).all()
+                }
+                _ => false,
+            });
+            progress.max(inside as u32)
+        }
+        ObjectiveKind::FindSecrets { level, .. } => {
+            let found = events.iter().filter(|event| match event {
+                QuestEvent::SecretFound { level: at } => level.as_ref().is_none_or(|level| level == at),
+                _ => false,
+            });
+            progress + found.count() as u32
+        }
+        ObjectiveKind::TalkTo { npc } => {
+            let talked = events
+                .iter()
+                .any(|event| matches!(event, QuestEvent::TalkedTo { npc: to } if to == npc));
+            progress.max(talked as u32)
+        }
+        ObjectiveKind::ObtainItem { item, .. } => progress.max(game_data.inventory.get(item).copied().unwrap_or(0)),
+    };
+    progress.min(kind.target())
+}
+
+///
+/// Starts quests whose start condition holds, counts `events` towards the objectives of the started ones
+/// and completes those with every objective done, applying their rewards.
+///
+pub fn advance(quests: &[&QuestDef], game_data: &mut GameData, events: &[QuestEvent]) -> Vec<QuestUpdate> {
+    let mut updates = Vec::new();
+    for quest in quests {
+        let mut state = match game_data.quests.get(&quest.id) {
+            Some(state) if state.completed => continue,
+            Some(state) => state.clone(),
+            None if quest.start.check(game_data) => {
+                updates.push(QuestUpdate::Started(quest.id.clone()));
+                QuestState::default()
+            }
+            None => continue,
+        };
+
+        // Quests can gain or lose objectives between versions of the game.
+        state.progress.resize(quest.objectives.len(), 0);
+        for (progress, objective) in state.progress.iter_mut().zip(&quest.objectives) {
+            *progress = objective_progress(&objective.kind, *progress, game_data, events);
+        }
+
+        if (0..quest.objectives.len()).all(|i| state.is_done(quest, i)) {
+            state.completed = true;
+            for reward in &quest.rewards {
+                reward.apply(game_data);
+            }
+            updates.push(QuestUpdate::Completed(quest.id.clone()));
+        }
+        if game_data.quests.get(&quest.id) != Some(&state) {
+            game_data.quests.insert(quest.id.clone(), state);
+        }
+    }
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ron_asset::parse_ron;
+
+    fn quest(text: &str) -> QuestDef {
+        parse_ron::<QuestDef>(text).unwrap()
+    }
+
+    fn secret_in(level: &str) -> QuestEvent {
+        QuestEvent::SecretFound {
+            level: level.to_string(),
+        }
+    }
+
+    fn talked_to(npc: &str) -> QuestEvent {
+        QuestEvent::TalkedTo { npc: npc.to_string() }
+    }
+
+    #[test]
+    fn quests_start_when_their_condition_holds() {
+        let quest = quest(
+            r#"(id: "hermit", name: "Hermit", start: Flag("heard_of_hermit"),
+                objectives: [(description: "Talk to the hermit", kind: TalkTo(npc: "hermit"))])"#,
+        );
+        let mut game_data = GameData::default();
+        assert!(advance(&[&quest], &mut game_data, &[talked_to("hermit")]).is_empty());
+        assert!(game_data.quests.is_empty(), "talking before the quest starts doesn't count");
+
+        game_data.flags.insert("heard_of_hermit".to_string());
+        assert_eq!(advance(&[&quest], &mut game_data, &[]), vec![QuestUpdate::Started("hermit".to_string())]);
+        assert_eq!(game_data.quests["hermit"].progress, vec![0]);
+        assert_eq!(
+            advance(&[&quest], &mut game_data, &[talked_to("hermit")]),
+            vec![QuestUpdate::Completed("hermit".to_string())]
+        );
+    }
+
+    #[test]
+    fn finding_secrets_only_counts_the_given_level() {
+        let quest = quest(
+            r#"(id: "grove", name: "Grove",
+                objectives: [(description: "Find secrets", kind: FindSecrets(count: 2, level: Some("grove")))])"#,
+        );
+        let mut game_data = GameData::default();
+        advance(&[&quest], &mut game_data, &[secret_in("grove"), secret_in("river"), secret_in("river")]);
+        assert_eq!(game_data.quests["grove"].progress, vec![1]);
+        assert!(!game_data.quests["grove"].completed);
+
+        advance(&[&quest], &mut game_data, &[secret_in("grove")]);
+        assert!(game_data.quests["grove"].completed);
+    }
+
+    #[test]
+    fn obtained_items_stay_obtained() {
+        let quest = quest(
+            r#"(id: "pebbles", name: "Pebbles", objectives: [
+                (description: "Collect pebbles", kind: ObtainItem(item: "pebble", count: 3)),
+                (description: "Talk to the hermit", kind: TalkTo(npc: "hermit")),
+            ])"#,
+        );
+        let mut game_data = GameData::default();
+        game_data.give_item("pebble");
+        game_data.give_item("pebble");
+        advance(&[&quest], &mut game_data, &[]);
+        assert_eq!(game_data.quests["pebbles"].progress, vec![2, 0]);
+
+        game_data.take_item("pebble");
+        advance(&[&quest], &mut game_data, &[]);
+        assert_eq!(game_data.quests["pebbles"].progress, vec![2, 0]);
+    }
+
+    #[test]
+    fn rewards_are_given_once() {
+        let quest = quest(
+            r#"(id: "hermit", name: "Hermit", rewards: [GiveItem("glowberry")],
+                objectives: [(description: "Talk to the hermit", kind: TalkTo(npc: "hermit"))])"#,
+        );
+        let mut game_data = GameData::default();
+        advance(&[&quest], &mut game_data, &[talked_to("hermit")]);
+        assert!(advance(&[&quest], &mut game_data, &[talked_to("hermit")]).is_empty());
+        assert_eq!(game_data.inventory.get("glowberry"), Some(&1));
+    }
+
+    #[test]
+    fn progress_follows_objectives_being_added_and_removed() {
+        let quest = quest(
+            r#"(id: "walk", name: "Walk", objectives: [
+                (description: "Talk to the hermit", kind: TalkTo(npc: "hermit")),
+                (description: "Talk to the ferryman", kind: TalkTo(npc: "ferryman")),
+            ])"#,
+        );
+        let mut game_data = GameData::default();
+        let state = |progress: Vec<u32>| QuestState {
+            progress,
+            completed: false,
+        };
+
+        game_data.quests.insert("walk".to_string(), state(vec![1]));
+        advance(&[&quest], &mut game_data, &[]);
+        assert_eq!(game_data.quests["walk"].progress, vec![1, 0]);
+
+        game_data.quests.insert("walk".to_string(), state(vec![0, 1, 1]));
+        advance(&[&quest], &mut game_data, &[]);
+        assert_eq!(game_data.quests["walk"].progress, vec![0, 1]);
+    }
+}
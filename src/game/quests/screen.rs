@@ -0,0 +1,256 @@
+use super::{QuestCatalog, QuestDef, QuestSet};
+use crate::game::internal::{
+    menu_button, menu_text, spawn_page_controls, ListPages, ScriptControlled, LIST_TOP, UI_Z,
+};
+use crate::game::primary_logic::{GameData, InGameScreen};
+use crate::utils::buttoning::{ButtonClickEvent, ButtonClickType, Clickable, Hovered};
+use crate::utils::responsive::{ResponsivePosition, ResponsiveValue};
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::Text2dBounds;
+
+///
+/// The quest log, listing started quests with the selected quest's objectives beside it.
+///
+pub struct QuestLogPlugin;
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Component)]
+enum QuestLogButton {
+    Quest(String),
+    /// Turns the list to this page.
+    Page(usize),
+    Close,
+}
+
+///
+/// The quest whose details are shown.
+///
+#[derive(Resource, Default)]
+struct QuestLogSelection(Option<String>);
+
+///
+/// The page of the list shown, when there are too many quests to fit on one.
+///
+#[derive(Resource, Default)]
+struct QuestLogPage(usize);
+
+///
+/// Marks everything which is rebuilt when the selection changes.
+///
+#[derive(Default, Clone, Component)]
+struct QuestLogContent;
+
+const TITLE_COLOR: Color = Color::srgb(1.4, 2.1, 2.4);
+const QUEST_COLOR: Color = Color::srgb(2.4, 2.1, 1.4);
+const COMPLETED_COLOR: Color = Color::srgb(1.2, 1.5, 1.2);
+const SELECTED_COLOR: Color = Color::srgb(2.8, 2.4, 1.2);
+const DETAIL_COLOR: Color = Color::srgb(1.4, 1.4, 1.4);
+const DONE_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+const EMPTY_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+const CLOSE_COLOR: Color = Color::srgb(2.1, 1.4, 1.4);
+const BACKDROP_COLOR: Color = Color::srgba(0.02, 0.02, 0.03, 0.9);
+
+impl Plugin for QuestLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QuestLogSelection>();
+        app.init_resource::<QuestLogPage>();
+        app.add_systems(Update, open_quest_log.run_if(in_state(InGameScreen::Playing)));
+        app.add_systems(OnEnter(InGameScreen::Quests), on_enter);
+        app.add_systems(
+            Update,
+            (quest_log_button_clicks, close_on_key, rebuild_content)
+                .chain()
+                .run_if(in_state(InGameScreen::Quests)),
+        );
+    }
+}
+
+fn open_quest_log(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<InGameScreen>>) {
+    if keys.just_pressed(KeyCode::KeyJ) {
+        next_state.set(InGameScreen::Quests);
+    }
+}
+
+///
+/// Started quests, the ones still in progress first.
+///
+fn started_quests<'a>(quest_sets: &'a Assets<QuestSet>, game_data: &GameData) -> Vec<&'a QuestDef> {
+    let mut quests: Vec<&QuestDef> = QuestCatalog::quests(quest_sets)
+        .into_iter()
+        .filter(|quest| game_data.quests.contains_key(&quest.id))
+        .collect();
+    quests.sort_by_key(|quest| game_data.quests.get(&quest.id).is_some_and(|state| state.completed));
+    quests
+}
+
+fn on_enter(
+    mut commands: Commands,
+    mut selection: ResMut<QuestLogSelection>,
+    page: Res<QuestLogPage>,
+    quest_sets: Res<Assets<QuestSet>>,
+    game_data: Res<GameData>,
+) {
+    // Start on the first quest in progress, unless one was picked before. The content is built below, so
+    // this mustn't rebuild it.
+    if selection.0.as_ref().is_none_or(|id| !game_data.quests.contains_key(id)) {
+        selection.bypass_change_detection().0 = started_quests(&quest_sets, &game_data).first().map(|quest| quest.id.clone());
+    }
+
+    commands.spawn((
+        StateScoped(InGameScreen::Quests),
+        SpriteBundle {
+            sprite: Sprite {
+                color: BACKDROP_COLOR,
+                custom_size: Some(Vec2::splat(10000.0)),
+                ..default()
+            },
+            ..default()
+        },
+        ResponsivePosition::new(
+            ResponsiveValue::Percentage(50.0),
+            ResponsiveValue::Percentage(50.0),
+            ResponsiveValue::Absolute(UI_Z - 1.0),
+        ),
+    ));
+    spawn_content(&mut commands, &selection, page.0, &quest_sets, &game_data);
+}
+
+fn spawn_entry<'a>(commands: &'a mut Commands, bundle: impl Bundle) -> EntityCommands<'a> {
+    commands.spawn((StateScoped(InGameScreen::Quests), QuestLogContent, bundle))
+}
+
+fn spawn_content(
+    commands: &mut Commands,
+    selection: &QuestLogSelection,
+    page: usize,
+    quest_sets: &Assets<QuestSet>,
+    game_data: &GameData,
+) {
+    spawn_entry(commands, menu_text("Quests", TITLE_COLOR, 48.0, 50.0, 90.0, Anchor::Center));
+
+    let quests = started_quests(quest_sets, game_data);
+    if quests.is_empty() {
+        spawn_entry(commands, menu_text("No quests yet.", EMPTY_COLOR, 24.0, 10.0, LIST_TOP, Anchor::CenterLeft));
+    }
+
+    let pages = ListPages::new(quests.iter().map(|quest| (quest, 5.0)));
+    let last_page = pages.last_page();
+    for (quest, y) in pages.into_page(page) {
+        let completed = game_data.quests.get(&quest.id).is_some_and(|state| state.completed);
+        let color = if selection.0.as_ref() == Some(&quest.id) {
+            SELECTED_COLOR
+        } else if completed {
+            COMPLETED_COLOR
+        } else {
+            QUEST_COLOR
+        };
+        spawn_entry(
+            commands,
+            (
+                menu_button(quest.name.clone(), color, 24.0, 10.0, y, Anchor::CenterLeft),
+                QuestLogButton::Quest(quest.id.clone()),
+            ),
+        );
+    }
+
+    spawn_page_controls(
+        commands,
+        (StateScoped(InGameScreen::Quests), QuestLogContent),
+        page,
+        last_page,
+        QuestLogButton::Page,
+    );
+
+    let selected = selection.0.as_ref().and_then(|id| quests.iter().find(|quest| quest.id == *id));
+    if let (Some(quest), Some(state)) = (selected, selection.0.as_ref().and_then(|id| game_data.quests.get(id))) {
+        let name = if state.completed {
+            format!("{} (complete)", quest.name)
+        } else {
+            quest.name.clone()
+        };
+        spawn_entry(commands, menu_text(name, SELECTED_COLOR, 36.0, 50.0, 80.0, Anchor::CenterLeft));
+        if !quest.description.is_empty() {
+            spawn_entry(
+                commands,
+                menu_text(quest.description.clone(), DETAIL_COLOR, 22.0, 50.0, 74.0, Anchor::TopLeft),
+            )
+            .insert(Text2dBounds {
+                size: Vec2::new(520.0, f32::INFINITY),
+            });
+        }
+
+        for (i, objective) in quest.objectives.iter().enumerate() {
+            let done = state.is_done(quest, i);
+            let target = objective.kind.target();
+            let progress = state.progress.get(i).copied().unwrap_or(0);
+            let mark = if done { "[x]" } else { "[ ]" };
+            let count = if target > 1 { format!(" ({progress}/{target})") } else { String::new() };
+            let color = if done { DONE_COLOR } else { DETAIL_COLOR };
+            let y = 56.0 - i as f32 * 5.0;
+            spawn_entry(
+                commands,
+                menu_text(format!("{mark} {}{count}", objective.description), color, 22.0, 50.0, y, Anchor::CenterLeft),
+            );
+        }
+    }
+
+    spawn_entry(
+        commands,
+        (
+            menu_button("Close", CLOSE_COLOR, 30.0, 50.0, 8.0, Anchor::Center),
+            QuestLogButton::Close,
+        ),
+    );
+}
+
+#[allow(clippy::type_complexity)]
+fn quest_log_button_clicks(
+    query: Query<&QuestLogButton, (With<Hovered>, With<Clickable>, Without<ScriptControlled>)>,
+    mut event_reader: EventReader<ButtonClickEvent>,
+    mut selection: ResMut<QuestLogSelection>,
+    mut page: ResMut<QuestLogPage>,
+    mut next_state: ResMut<NextState<InGameScreen>>,
+) {
+    for event in event_reader.read() {
+        if event.mouse_button != MouseButton::Left || event.click_type != ButtonClickType::Pressed {
+            continue;
+        }
+
+        if let Ok(button) = query.get(event.entity) {
+            match button {
+                QuestLogButton::Quest(id) => {
+                    if selection.0.as_ref() != Some(id) {
+                        selection.0 = Some(id.clone());
+                    }
+                }
+                QuestLogButton::Page(number) => page.0 = *number,
+                QuestLogButton::Close => next_state.set(InGameScreen::Playing),
+            }
+        }
+    }
+}
+
+fn close_on_key(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<InGameScreen>>) {
+    if keys.any_just_pressed([KeyCode::Escape, KeyCode::KeyJ]) {
+        next_state.set(InGameScreen::Playing);
+    }
+}
+
+fn rebuild_content(
+    mut commands: Commands,
+    selection: Res<QuestLogSelection>,
+    page: Res<QuestLogPage>,
+    quest_sets: Res<Assets<QuestSet>>,
+    game_data: Res<GameData>,
+    content: Query<Entity, With<QuestLogContent>>,
+) {
+    let changed = (selection.is_changed() && !selection.is_added()) || (page.is_changed() && !page.is_added());
+    if !changed {
+        return;
+    }
+    for entity in content.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_content(&mut commands, &selection, page.0, &quest_sets, &game_data);
+}
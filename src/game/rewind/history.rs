@@ -0,0 +1,269 @@
+use crate::game::primary_logic::GameData;
+use bevy::math::IVec2;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+///
+/// One reversible change to the game.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    /// The player stepping from one tile to another.
+    Move { from: [i32; 2], to: [i32; 2] },
+    /// A flag being set, or cleared when `on` is false.
+    Flag { flag: String, on: bool },
+    /// The number of an item the player carries changing.
+    Item { item: String, from: u32, to: u32 },
+}
+
+///
+/// Everything one action of the player changed, undone together.
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    pub commands: Vec<Command>,
+}
+
+///
+/// The flags and inventory before an action, to record what the action changed.
+///
+pub struct Snapshot {
+    flags: BTreeSet<String>,
+    inventory: BTreeMap<String, u32>,
+}
+
+///
+/// The steps taken in the current room, oldest first. This knows nothing about the ECS, undoing a step only
+/// changes [GameData] and hands back where the player should stand.
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    pub steps: Vec<Step>,
+}
+
+impl Command {
+    ///
+    /// Reverts the command. Moves are reverted by the caller, using [Command::undo_position].
+    ///
+    pub fn undo(&self, game_data: &mut GameData) {
+        match self {
+            Command::Move { .. } => {}
+            Command::Flag { flag, on: true } => {
+                game_data.flags.remove(flag);
+            }
+            Command::Flag { flag, on: false } => {
+                game_data.flags.insert(flag.clone());
+            }
+            Command::Item { item, from, .. } => {
+                if *from == 0 {
+                    game_data.inventory.remove(item);
+                } else {
+                    game_data.inventory.insert(item.clone(), *from);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Where the player stood before the command, if it moved them.
+    ///
+    pub fn undo_position(&self) -> Option<IVec2> {
+        match self {
+            Command::Move { from, .. } => Some(IVec2::from(*from)),
+            _ => None,
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn of(game_data: &GameData) -> Snapshot {
+        Snapshot {
+            flags: game_data.flags.clone(),
+            inventory: game_data.inventory.clone(),
+        }
+    }
+
+    ///
+    /// The commands which turn this snapshot into `game_data`.
+    ///
+    pub fn changes(&self, game_data: &GameData) -> Vec<Command> {
+        let set = game_data.flags.difference(&self.flags).map(|flag| Command::Flag {
+            flag: flag.clone(),
+            on: true,
+        });
+        let cleared = self.flags.difference(&game_data.flags).map(|flag| Command::Flag {
+            flag: flag.clone(),
+            on: false,
+        });
+
+        let items: BTreeSet<&String> = self.inventory.keys().chain(game_data.inventory.keys()).collect();
+        let count = |inventory: &BTreeMap<String, u32>, item: &str| inventory.get(item).copied().unwrap_or(0);
+        let items = items.into_iter().filter_map(|item| {
+            let from = count(&self.inventory, item);
+            let to = count(&game_data.inventory, item);
+            (from != to).then(|| Command::Item {
+                item: item.clone(),
+                from,
+                to,
+            })
+        });
+
+        set.chain(cleared).chain(items).collect()
+    }
+}
+
+impl History {
+    pub fn record_move(&mut self, from: IVec2, to: IVec2) {
+        self.steps.push(Step {
+            commands: vec![Command::Move {
+                from: from.into(),
+                to: to.into(),
+            }],
+        });
+    }
+
+    ///
+    /// Records what changed since `before` as one step, if anything did.
+    ///
+    pub fn record_since(&mut self, before: &Snapshot, game_data: &GameData) {
+        let commands = before.changes(game_data);
+        if !commands.is_empty() {
+            self.steps.push(Step { commands });
+        }
+    }
+
+    ///
+    /// Adds what changed since `before` to the last step, so it is undone along with the action which caused it.
+    /// Starts a step if there isn't one.
+    ///
+    pub fn fold_since(&mut self, before: &Snapshot, game_data: &GameData) {
+        let commands = before.changes(game_data);
+        if commands.is_empty() {
+            return;
+        }
+        match self.steps.last_mut() {
+            Some(step) => step.commands.extend(commands),
+            None => self.steps.push(Step { commands }),
+        }
+    }
+
+    ///
+    /// Where [History::undo] would put the player, if it would move them.
+    ///
+    pub fn undo_position(&self) -> Option<IVec2> {
+        self.steps.last()?.commands.iter().find_map(Command::undo_position)
+    }
+
+    ///
+    /// Where [History::reset] would put the player, if it would move them.
+    ///
+    pub fn reset_position(&self) -> Option<IVec2> {
+        self.steps.iter().flat_map(|step| &step.commands).find_map(Command::undo_position)
+    }
+
+    ///
+    /// Undoes the last step, returning where the player should stand if it moved them.
+    ///
+    pub fn undo(&mut self, game_data: &mut GameData) -> Option<Option<IVec2>> {
+        let step = self.steps.pop()?;
+        let mut position = None;
+        for command in step.commands.iter().rev() {
+            command.undo(game_data);
+            position = command.undo_position().or(position);
+        }
+        Some(position)
+    }
+
+    ///
+    /// Undoes every step, returning where the player stood when the history started if they have moved since.
+    ///
+    pub fn reset(&mut self, game_data: &mut GameData) -> Option<IVec2> {
+        let mut position = None;
+        while let Some(step_position) = self.undo(game_data) {
+            position = step_position.or(position);
+        }
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(flags: &[&str], inventory: &[(&str, u32)]) -> GameData {
+        GameData {
+            flags: flags.iter().map(|flag| flag.to_string()).collect(),
+            inventory: inventory.iter().map(|(item, count)| (item.to_string(), *count)).collect(),
+            ..GameData::default()
+        }
+    }
+
+    #[test]
+    fn changes_cover_flags_and_items_both_ways() {
+        let before = data(&["lever", "door"], &[("key", 1), ("coin", 2)]);
+        let after = data(&["lever", "bridge"], &[("coin", 5), ("lamp", 1)]);
+
+        let changes = Snapshot::of(&before).changes(&after);
+
+        assert_eq!(
+            changes,
+            vec![
+                Command::Flag { flag: "bridge".into(), on: true },
+                Command::Flag { flag: "door".into(), on: false },
+                Command::Item { item: "coin".into(), from: 2, to: 5 },
+                Command::Item { item: "key".into(), from: 1, to: 0 },
+                Command::Item { item: "lamp".into(), from: 0, to: 1 },
+            ]
+        );
+        assert!(Snapshot::of(&after).changes(&after).is_empty());
+    }
+
+    #[test]
+    fn undoing_restores_flags_and_inventory() {
+        let start = data(&["door"], &[("key", 1)]);
+        let mut game_data = start.clone();
+        let mut history = History::default();
+
+        let before = Snapshot::of(&game_data);
+        game_data.flags.remove("door");
+        game_data.flags.insert("lever".into());
+        game_data.inventory.remove("key");
+        game_data.inventory.insert("coin".into(), 3);
+        history.record_since(&before, &game_data);
+        let changed = game_data.clone();
+
+        let before = Snapshot::of(&game_data);
+        history.record_since(&before, &game_data);
+        assert_eq!(history.steps.len(), 1, "nothing changed, so nothing is recorded");
+
+        assert_eq!(history.undo(&mut game_data), Some(None));
+        assert_eq!(game_data, start);
+        assert_eq!(history.undo(&mut game_data), None);
+
+        game_data = changed;
+        history.record_since(&Snapshot::of(&start), &game_data);
+        assert_eq!(history.reset(&mut game_data), None);
+        assert_eq!(game_data, start);
+    }
+
+    #[test]
+    fn undoing_puts_the_player_back() {
+        let mut game_data = GameData::default();
+        let mut history = History::default();
+        history.record_move(IVec2::new(1, 1), IVec2::new(2, 1));
+        let before = Snapshot::of(&game_data);
+        game_data.flags.insert("plate".into());
+        history.record_since(&before, &game_data);
+        history.record_move(IVec2::new(2, 1), IVec2::new(3, 1));
+
+        assert_eq!(history.undo_position(), Some(IVec2::new(2, 1)));
+        assert_eq!(history.undo(&mut game_data), Some(Some(IVec2::new(2, 1))));
+        assert_eq!(history.undo_position(), None);
+        assert_eq!(history.undo(&mut game_data), Some(None));
+        assert!(game_data.flags.is_empty());
+        history.record_move(IVec2::new(2, 1), IVec2::new(2, 2));
+        assert_eq!(history.reset_position(), Some(IVec2::new(1, 1)));
+        assert_eq!(history.reset(&mut game_data), Some(IVec2::new(1, 1)));
+        assert!(history.steps.is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::game::interactables::spawn_speech;
+use crate::game::player::{GridPosition, Player, PlayerMovementSet, PLAYER_Z};
+use crate::game::primary_logic::{GameData, GameState, InGameScreen};
+use crate::game::world::{tile_to_world, CurrentMap, MapLoaded, MapSet, TileMap};
+use crate::utils::files::write_atomic;
+use crate::utils::position_smoothing::TargetPosition;
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod history;
+
+pub use history::{History, Snapshot};
+
+///
+/// Undo (Z) and room reset (R) for puzzles. Every move and change the player makes in a room is recorded,
+/// and forgotten when they leave it. F8 writes the record to a file for bug reports.
+///
+#[derive(Default)]
+pub struct RewindPlugin;
+
+///
+/// What has happened in the current room.
+///
+#[derive(Resource, Default)]
+pub struct Rewind {
+    pub history: History,
+    /// Where the player was last seen, to tell their moves apart from being put back by an undo.
+    last_position: Option<IVec2>,
+}
+
+impl Rewind {
+    pub fn clear(&mut self) {
+        *self = Rewind::default();
+    }
+}
+
+///
+/// Records the player's moves. Changes caused by a move are folded into its step after this.
+///
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RecordMovesSet;
+
+///
+/// The file written for a bug report.
+///
+#[derive(Serialize)]
+struct HistoryReport<'a> {
+    level: &'a str,
+    history: &'a History,
+}
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rewind>();
+        app.add_systems(
+            Update,
+            clear_history
+                .in_set(MapSet::Prepare)
+                .run_if(in_state(GameState::InGame).and_then(on_event::<MapLoaded>())),
+        );
+        app.add_systems(
+            Update,
+            (
+                rewind_input.before(PlayerMovementSet),
+                record_moves.in_set(RecordMovesSet).after(PlayerMovementSet),
+                write_report,
+            )
+                .run_if(in_state(InGameScreen::Playing)),
+        );
+        app.add_systems(OnExit(GameState::InGame), clear_history);
+    }
+}
+
+pub fn reports_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("secrets-of-the-path"))
+        .unwrap_or_default()
+        .join("reports")
+}
+
+fn clear_history(mut rewind: ResMut<Rewind>) {
+    rewind.clear();
+}
+
+fn record_moves(players: Query<&GridPosition, (With<Player>, Changed<GridPosition>)>, mut rewind: ResMut<Rewind>) {
+    for grid_position in players.iter() {
+        match rewind.last_position {
+            Some(last) if last != grid_position.0 => rewind.history.record_move(last, grid_position.0),
+            _ => {}
+        }
+        rewind.last_position = Some(grid_position.0);
+    }
+}
+
+///
+/// Whether the player can be put back on `position`. A timed gate they walked through may have closed since,
+/// and putting them back would shut them inside it.
+///
+fn can_return_to(map: &TileMap, position: Option<IVec2>) -> bool {
+    position.is_none_or(|position| map.is_walkable(position))
+}
+
+fn rewind_input(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    current_map: Res<CurrentMap>,
+    mut rewind: ResMut<Rewind>,
+    mut game_data: ResMut<GameData>,
+    mut players: Query<(&mut GridPosition, &mut TargetPosition), With<Player>>,
+) {
+    let returning_to = if keys.just_pressed(KeyCode::KeyZ) {
+        rewind.history.undo_position()
+    } else if keys.just_pressed(KeyCode::KeyR) {
+        rewind.history.reset_position()
+    } else {
+        return;
+    };
+    if !can_return_to(&current_map.map, returning_to) {
+        if let Some((grid_position, _)) = players.iter().next() {
+            spawn_speech(&mut commands, grid_position.0, "The way back is shut.".to_string());
+        }
+        return;
+    }
+
+    let position = if keys.just_pressed(KeyCode::KeyZ) {
+        rewind.history.undo(&mut game_data).flatten()
+    } else if keys.just_pressed(KeyCode::KeyR) {
+        rewind.history.reset(&mut game_data)
+    } else {
+        return;
+    };
+
+    if let Some(position) = position {
+        rewind.last_position = Some(position);
+        game_data.position = Some(position.into());
+        for (mut grid_position, mut target) in players.iter_mut() {
+            grid_position.0 = position;
+            target.0 = tile_to_world(position).extend(PLAYER_Z);
+        }
+    }
+}
+
+fn write_report(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    rewind: Res<Rewind>,
+    game_data: Res<GameData>,
+    players: Query<&GridPosition, With<Player>>,
+) {
+    if !keys.just_pressed(KeyCode::F8) {
+        return;
+    }
+    let report = HistoryReport {
+        level: &game_data.level,
+        history: &rewind.history,
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let path = reports_dir().join(format!("history_{timestamp}.ron"));
+
+    let result = ron::ser::to_string_pretty(&report, PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|contents| write_atomic(&path, contents.as_bytes()).map_err(|e| e.to_string()));
+    let text = match result {
+        Ok(()) => {
+            info!("Wrote move history to {}", path.display());
+            "Move history saved.".to_string()
+        }
+        Err(e) => {
+            error!("Could not write move history to {}: {e}", path.display());
+            "Could not save the move history.".to_string()
+        }
+    };
+    if let Some(grid_position) = players.iter().next() {
+        spawn_speech(&mut commands, grid_position.0, text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::Tile;
+
+    #[test]
+    fn undo_is_refused_onto_a_closed_gate() {
+        let gate = IVec2::new(1, 0);
+        let mut map = TileMap::new();
+        map.set(IVec2::ZERO, Tile::Path);
+        map.set(gate, Tile::Path);
+        map.set(IVec2::new(2, 0), Tile::Path);
+        let mut history = History::default();
+        history.record_move(IVec2::ZERO, gate);
+        history.record_move(gate, IVec2::new(2, 0));
+        assert!(can_return_to(&map, history.undo_position()));
+
+        // The gate closes behind the player.
+        map.set(gate, Tile::Wall);
+        assert!(!can_return_to(&map, history.undo_position()));
+        assert!(can_return_to(&map, history.reset_position()));
+        // Undoing something which doesn't move the player is always fine.
+        assert!(can_return_to(&map, None));
+    }
+}
@@ -0,0 +1,57 @@
+use super::{SaveError, SAVE_VERSION};
+use ron::Value;
+
+///
+/// Upgrades a save by one version. `MIGRATIONS[i]` takes a version `i + 1` save to version `i + 2`.
+///
+type Migration = fn(Value) -> Result<Value, String>;
+
+const MIGRATIONS: [Migration; (SAVE_VERSION - 1) as usize] = [];
+
+///
+/// Runs every migration needed to bring a `version` save up to [SAVE_VERSION].
+///
+pub fn migrate(version: u32, mut value: Value) -> Result<Value, SaveError> {
+    if version == 0 {
+        return Err(SaveError::Migration {
+            from: version,
+            message: "version 0 does not exist".to_string(),
+        });
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+        value = migration(value).map_err(|message| SaveError::Migration {
+            from: from as u32 + 1,
+            message,
+        })?;
+    }
+    set_field(&mut value, "version", Value::Number(i64::from(SAVE_VERSION).into()))
+        .map_err(|message| SaveError::Migration { from: version, message })?;
+    Ok(value)
+}
+
+///
+/// Takes the world seed out of a save's metadata and [GameData](crate::game::primary_logic::GameData), leaving
+/// them to default. Seeds are never migrated, they're carried over separately.
+///
+pub fn remove_seeds(value: &mut Value) {
+    let Value::Map(save) = value else {
+        return;
+    };
+    for (key, part) in save.iter_mut() {
+        let is_part = matches!(key, Value::String(name) if name == "metadata" || name == "data");
+        if let (true, Value::Map(part)) = (is_part, part) {
+            part.remove(&Value::String("procedural_seed".to_string()));
+        }
+    }
+}
+
+fn set_field(value: &mut Value, name: &str, field: Value) -> Result<(), String> {
+    match value {
+        Value::Map(map) => {
+            map.insert(Value::String(name.to_string()), field);
+            Ok(())
+        }
+        _ => Err(format!("expected a struct to set `{name}` on")),
+    }
+}
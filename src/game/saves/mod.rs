@@ -0,0 +1,732 @@
+use crate::game::outro::ExitFlush;
+use crate::game::primary_logic::{GameData, GameState};
+use crate::utils::files::write_atomic;
+use bevy::prelude::*;
+use bevy::tasks::{block_on, futures_lite::future, IoTaskPool, Task};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+mod migrations;
+
+///
+/// Reads and writes [GameData] to numbered save slots.
+///
+#[derive(Default)]
+pub struct SavesPlugin;
+
+pub const SLOT_COUNT: u32 = 3;
+
+///
+/// Version of the save format written by this build. Bump this and add a migration in
+/// [migrations::MIGRATIONS] whenever [GameData] changes in a way `serde(default)` can't handle.
+///
+pub const SAVE_VERSION: u32 = 1;
+
+///
+/// Least real time between two autosaves, in seconds. Autosaves asked for sooner are put off until then.
+///
+pub const AUTOSAVE_INTERVAL: f64 = 10.0;
+
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error("could not access save file {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("save file {path} is malformed: {source}")]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: ron::error::SpannedError,
+    },
+    #[error("could not serialize save: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("save file {path} has version {found}, but this build only supports up to {SAVE_VERSION}")]
+    UnsupportedVersion { path: PathBuf, found: u32 },
+    #[error("could not migrate save from version {from}: {message}")]
+    Migration { from: u32, message: String },
+    #[error("save slot {0} is empty")]
+    EmptySlot(u32),
+    #[error("no save slot is selected")]
+    NoSlotSelected,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveMetadata {
+    /// Seconds since the unix epoch when the save was written.
+    pub timestamp: u64,
+    pub play_time: f64,
+    pub location_name: String,
+    /// The seed of a generated world, so it can be told apart from others and grown again.
+    #[serde(default)]
+    pub procedural_seed: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    pub metadata: SaveMetadata,
+    pub data: GameData,
+}
+
+///
+/// The directory save slots live in.
+///
+#[derive(Resource)]
+pub struct SaveSlots {
+    pub dir: PathBuf,
+}
+
+///
+/// The slot [GameData] is saved into. `None` while no game is being played.
+///
+#[derive(Resource, Default, Debug)]
+pub struct CurrentSlot(pub Option<u32>);
+
+///
+/// Send to write the current [GameData] into the [CurrentSlot].
+///
+#[derive(Event, Default)]
+pub struct SaveGame;
+
+///
+/// Send to save into the [CurrentSlot] in the background, e.g. on reaching a checkpoint. Autosaves are
+/// throttled by [AUTOSAVE_INTERVAL], so several in quick succession only write once.
+///
+#[derive(Event, Default)]
+pub struct Autosave;
+
+///
+/// The autosave being written, and when the last one was.
+///
+/// Only one write to a slot ever happens at a time: an autosave doesn't start while the last is still
+/// being written, and every other save waits for it to finish first.
+///
+#[derive(Resource, Default)]
+pub struct Autosaver {
+    task: Option<Task<Result<u32, SaveError>>>,
+    /// Real time in seconds when the last autosave started.
+    last_started: Option<f64>,
+    /// Whether an autosave has been asked for which hasn't started yet.
+    pending: bool,
+}
+
+///
+/// Send to start a fresh game in a slot, overwriting whatever was saved there.
+///
+#[derive(Event)]
+pub struct NewGame {
+    pub slot: u32,
+    /// Seed of the world to generate, or `None` to play the hand-made levels.
+    pub procedural_seed: Option<u64>,
+}
+
+///
+/// Send to replace [GameData] with the contents of a slot and continue playing in it.
+///
+#[derive(Event)]
+pub struct LoadGame(pub u32);
+
+///
+/// Sent once a [LoadGame] has replaced [GameData], so the game can start.
+///
+#[derive(Event, Default)]
+pub struct GameLoaded;
+
+///
+/// Sent when a [LoadGame] couldn't read its slot. [GameData] and the [CurrentSlot] are left as they were.
+///
+#[derive(Event)]
+pub struct LoadFailed {
+    pub slot: u32,
+    pub error: String,
+}
+
+#[derive(Event)]
+pub struct DeleteSave(pub u32);
+
+///
+/// The systems handling save events. Systems sending those events should run before this set to
+/// have them handled in the same frame.
+///
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SavesSet;
+
+impl Plugin for SavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveSlots {
+            dir: default_saves_dir(),
+        });
+        app.init_resource::<CurrentSlot>();
+        app.init_resource::<Autosaver>();
+        app.add_event::<SaveGame>();
+        app.add_event::<Autosave>();
+        app.add_event::<NewGame>();
+        app.add_event::<LoadGame>();
+        app.add_event::<GameLoaded>();
+        app.add_event::<LoadFailed>();
+        app.add_event::<DeleteSave>();
+        app.add_systems(
+            Update,
+            (
+                delete_requested,
+                new_game_requested,
+                load_requested,
+                track_play_time,
+                save_requested,
+                autosave_requested,
+                finish_autosave,
+                start_autosave,
+            )
+                .chain()
+                .in_set(SavesSet),
+        );
+        app.add_systems(Update, save_current_slot.in_set(ExitFlush));
+        app.add_systems(OnExit(GameState::InGame), close_current_slot);
+    }
+}
+
+pub fn default_saves_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("secrets-of-the-path"))
+        .unwrap_or_default()
+        .join("saves")
+}
+
+impl SaveMetadata {
+    pub fn now(data: &GameData) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            play_time: data.play_time,
+            location_name: data.location_name.clone(),
+            procedural_seed: data.procedural_seed,
+        }
+    }
+}
+
+impl SaveSlots {
+    pub fn slot_path(&self, slot: u32) -> PathBuf {
+        self.dir.join(format!("slot_{slot}.ron"))
+    }
+
+    ///
+    /// Metadata for every slot, or `None` for empty slots. Only the metadata of each save is read.
+    ///
+    pub fn list(&self) -> Vec<(u32, Result<Option<SaveMetadata>, SaveError>)> {
+        (0..SLOT_COUNT)
+            .map(|slot| {
+                let metadata = match self.metadata(slot) {
+                    Ok(metadata) => Ok(Some(metadata)),
+                    Err(SaveError::EmptySlot(_)) => Ok(None),
+                    Err(e) => Err(e),
+                };
+                (slot, metadata)
+            })
+            .collect()
+    }
+
+    ///
+    /// The slot which was saved most recently, if any slot holds a readable save.
+    ///
+    pub fn most_recent(&self) -> Option<u32> {
+        self.list()
+            .into_iter()
+            .filter_map(|(slot, metadata)| Some((slot, metadata.ok()??.timestamp)))
+            .max_by_key(|(_, timestamp)| *timestamp)
+            .map(|(slot, _)| slot)
+    }
+
+    pub fn load(&self, slot: u32) -> Result<SaveFile, SaveError> {
+        let path = self.slot_path(slot);
+        parse_save(&path, &self.read(slot)?)
+    }
+
+    pub fn metadata(&self, slot: u32) -> Result<SaveMetadata, SaveError> {
+        let path = self.slot_path(slot);
+        parse_metadata(&path, &self.read(slot)?)
+    }
+
+    fn read(&self, slot: u32) -> Result<String, SaveError> {
+        let path = self.slot_path(slot);
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(SaveError::EmptySlot(slot)),
+            Err(source) => Err(SaveError::Io { path, source }),
+        }
+    }
+
+    pub fn save(&self, slot: u32, data: &GameData) -> Result<(), SaveError> {
+        write_save(self.slot_path(slot), data.clone())
+    }
+
+    pub fn delete(&self, slot: u32) -> Result<(), SaveError> {
+        let path = self.slot_path(slot);
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(source) => Err(SaveError::Io { path, source }),
+        }
+    }
+}
+
+fn write_save(path: PathBuf, data: GameData) -> Result<(), SaveError> {
+    let save = SaveFile {
+        version: SAVE_VERSION,
+        metadata: SaveMetadata::now(&data),
+        data,
+    };
+    let contents = ron::ser::to_string_pretty(&save, PrettyConfig::default())?;
+    write_atomic(&path, contents.as_bytes()).map_err(|source| SaveError::Io { path, source })
+}
+
+impl Autosaver {
+    ///
+    /// Whether an autosave is being written right now.
+    ///
+    pub fn writing(&self) -> bool {
+        self.task.is_some()
+    }
+
+    ///
+    /// Whether an autosave asked for should start at real time `now`.
+    ///
+    fn due(&self, now: f64) -> bool {
+        self.pending
+            && self.task.is_none()
+            && self.last_started.is_none_or(|last| now - last >= AUTOSAVE_INTERVAL)
+    }
+
+    ///
+    /// Waits for the autosave being written, if any, and forgets one which hasn't started. Done before
+    /// every other write, which saves everything the autosave would have.
+    ///
+    fn settle(&mut self) {
+        self.pending = false;
+        if let Some(task) = self.task.take() {
+            log_autosave(block_on(task));
+        }
+    }
+}
+
+fn log_autosave(result: Result<u32, SaveError>) {
+    match result {
+        Ok(slot) => info!("Autosaved slot {slot}"),
+        Err(e) => error!("Autosave failed: {e}"),
+    }
+}
+
+///
+/// Parses a save, migrating it to [SAVE_VERSION] first if it was written by an older build.
+///
+pub fn parse_save(path: &Path, contents: &str) -> Result<SaveFile, SaveError> {
+    #[derive(Deserialize)]
+    struct VersionOnly {
+        version: u32,
+    }
+
+    let parse_error = |source| SaveError::Parse {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let version = ron::from_str::<VersionOnly>(contents).map_err(parse_error)?.version;
+    check_version(path, version)?;
+    match version {
+        SAVE_VERSION => ron::from_str(contents).map_err(parse_error),
+        v => migrate_save(path, v, contents),
+    }
+}
+
+///
+/// Parses a save written by an older build through [migrations::migrate].
+///
+fn migrate_save(path: &Path, version: u32, contents: &str) -> Result<SaveFile, SaveError> {
+    #[derive(Deserialize)]
+    struct Seeds {
+        metadata: Seed,
+        data: Seed,
+    }
+    #[derive(Deserialize)]
+    struct Seed {
+        procedural_seed: Option<u64>,
+    }
+
+    let parse_error = |source| SaveError::Parse {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    // ron::Value holds integers as i64, so seeds above i64::MAX don't survive it. The seeds are read
+    // straight from the save instead, and left out of what is migrated.
+    let seeds = ron::from_str::<Seeds>(contents).map_err(parse_error)?;
+    let mut value: ron::Value = ron::from_str(contents).map_err(parse_error)?;
+    migrations::remove_seeds(&mut value);
+
+    let mut save: SaveFile = migrations::migrate(version, value)?
+        .into_rust()
+        .map_err(|e| SaveError::Migration {
+            from: version,
+            message: e.to_string(),
+        })?;
+    save.metadata.procedural_seed = seeds.metadata.procedural_seed;
+    save.data.procedural_seed = seeds.data.procedural_seed;
+    Ok(save)
+}
+
+///
+/// Reads only the version and metadata of a save, skipping over its [GameData]. The metadata is the same in
+/// every version, so older saves don't need migrating for this.
+///
+pub fn parse_metadata(path: &Path, contents: &str) -> Result<SaveMetadata, SaveError> {
+    #[derive(Deserialize)]
+    struct Header {
+        version: u32,
+        metadata: SaveMetadata,
+    }
+
+    let header = ron::from_str::<Header>(contents).map_err(|source| SaveError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    check_version(path, header.version)?;
+    Ok(header.metadata)
+}
+
+///
+/// Fails for saves with a version this build can't read.
+///
+fn check_version(path: &Path, version: u32) -> Result<(), SaveError> {
+    match version {
+        0 => Err(SaveError::Migration {
+            from: version,
+            message: "version 0 does not exist".to_string(),
+        }),
+        v if v > SAVE_VERSION => Err(SaveError::UnsupportedVersion {
+            path: path.to_path_buf(),
+            found: v,
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn track_play_time(current_slot: Res<CurrentSlot>, mut game_data: ResMut<GameData>, time: Res<Time>) {
+    if current_slot.0.is_some() {
+        game_data.play_time += time.delta_seconds_f64();
+    }
+}
+
+fn write_current_slot(
+    slots: &SaveSlots,
+    current_slot: &CurrentSlot,
+    game_data: &GameData,
+    autosaver: &mut Autosaver,
+) -> Result<(), SaveError> {
+    let slot = current_slot.0.ok_or(SaveError::NoSlotSelected)?;
+    autosaver.settle();
+    slots.save(slot, game_data)?;
+    info!("Saved slot {slot}");
+    Ok(())
+}
+
+fn save_requested(
+    mut events: EventReader<SaveGame>,
+    slots: Res<SaveSlots>,
+    current_slot: Res<CurrentSlot>,
+    game_data: Res<GameData>,
+    mut autosaver: ResMut<Autosaver>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+    if let Err(e) = write_current_slot(&slots, &current_slot, &game_data, &mut autosaver) {
+        error!("{e}");
+    }
+}
+
+fn autosave_requested(mut events: EventReader<Autosave>, current_slot: Res<CurrentSlot>, mut autosaver: ResMut<Autosaver>) {
+    if events.read().count() > 0 && current_slot.0.is_some() {
+        autosaver.pending = true;
+    }
+}
+
+fn finish_autosave(mut autosaver: ResMut<Autosaver>) {
+    let Some(task) = autosaver.task.as_mut() else {
+        return;
+    };
+    if let Some(result) = block_on(future::poll_once(task)) {
+        autosaver.task = None;
+        log_autosave(result);
+    }
+}
+
+fn start_autosave(
+    time: Res<Time<Real>>,
+    slots: Res<SaveSlots>,
+    current_slot: Res<CurrentSlot>,
+    game_data: Res<GameData>,
+    mut autosaver: ResMut<Autosaver>,
+) {
+    let now = time.elapsed_seconds_f64();
+    if !autosaver.due(now) {
+        return;
+    }
+    let Some(slot) = current_slot.0 else {
+        autosaver.pending = false;
+        return;
+    };
+    let path = slots.slot_path(slot);
+    let data = game_data.clone();
+    autosaver.task = Some(IoTaskPool::get().spawn(async move { write_save(path, data).map(|()| slot) }));
+    autosaver.last_started = Some(now);
+    autosaver.pending = false;
+}
+
+fn new_game_requested(
+    mut events: EventReader<NewGame>,
+    slots: Res<SaveSlots>,
+    mut current_slot: ResMut<CurrentSlot>,
+    mut game_data: ResMut<GameData>,
+    mut autosaver: ResMut<Autosaver>,
+) {
+    for event in events.read() {
+        autosaver.settle();
+        *game_data = GameData {
+            procedural_seed: event.procedural_seed,
+            ..default()
+        };
+        current_slot.0 = Some(event.slot);
+        if let Err(e) = slots.save(event.slot, &game_data) {
+            error!("{e}");
+        }
+    }
+}
+
+fn load_requested(
+    mut events: EventReader<LoadGame>,
+    slots: Res<SaveSlots>,
+    mut current_slot: ResMut<CurrentSlot>,
+    mut game_data: ResMut<GameData>,
+    mut autosaver: ResMut<Autosaver>,
+    mut loaded: EventWriter<GameLoaded>,
+    mut failed: EventWriter<LoadFailed>,
+) {
+    for LoadGame(slot) in events.read() {
+        autosaver.settle();
+        match slots.load(*slot) {
+            Ok(save) => {
+                *game_data = save.data;
+                current_slot.0 = Some(*slot);
+                info!("Loaded slot {slot}");
+                loaded.send(GameLoaded);
+            }
+            Err(e) => {
+                error!("{e}");
+                failed.send(LoadFailed {
+                    slot: *slot,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+}
+
+fn delete_requested(
+    mut events: EventReader<DeleteSave>,
+    slots: Res<SaveSlots>,
+    mut current_slot: ResMut<CurrentSlot>,
+    mut autosaver: ResMut<Autosaver>,
+) {
+    for DeleteSave(slot) in events.read() {
+        // An autosave finishing afterwards would bring the slot back.
+        autosaver.settle();
+        if let Err(e) = slots.delete(*slot) {
+            error!("{e}");
+        }
+        if current_slot.0 == Some(*slot) {
+            current_slot.0 = None;
+        }
+    }
+}
+
+fn save_current_slot(
+    slots: Res<SaveSlots>,
+    current_slot: Res<CurrentSlot>,
+    game_data: Res<GameData>,
+    mut autosaver: ResMut<Autosaver>,
+) {
+    if current_slot.0.is_none() {
+        return;
+    }
+    if let Err(e) = write_current_slot(&slots, &current_slot, &game_data, &mut autosaver) {
+        error!("{e}");
+    }
+}
+
+fn close_current_slot(
+    slots: Res<SaveSlots>,
+    mut current_slot: ResMut<CurrentSlot>,
+    game_data: Res<GameData>,
+    mut autosaver: ResMut<Autosaver>,
+) {
+    if let Err(e) = write_current_slot(&slots, &current_slot, &game_data, &mut autosaver) {
+        error!("{e}");
+    }
+    current_slot.0 = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::quests::QuestState;
+    use bevy::tasks::TaskPool;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    ///
+    /// A save with every field of [GameData] filled in.
+    ///
+    fn full_save() -> SaveFile {
+        let data = GameData {
+            level: "meadow".to_string(),
+            location_name: "The Meadow".to_string(),
+            position: Some([4, -2]),
+            discovered_secrets: BTreeSet::from(["old_well".to_string()]),
+            inventory: BTreeMap::from([("rusty_key".to_string(), 2)]),
+            play_time: 123.5,
+            level_entered: BTreeMap::from([("meadow".to_string(), 12.0)]),
+            flags: BTreeSet::from(["hermit_advice".to_string()]),
+            unread_secrets: BTreeSet::from(["old_well".to_string()]),
+            procedural_seed: Some(u64::MAX - 7),
+            explored: BTreeMap::from([("meadow".to_string(), BTreeSet::from([[0, 0], [1, -1]]))]),
+            quests: BTreeMap::from([(
+                "find_the_well".to_string(),
+                QuestState {
+                    progress: vec![1, 0],
+                    completed: false,
+                },
+            )]),
+            hints_used: BTreeMap::from([("meadow".to_string(), 1)]),
+            hints_taken: BTreeMap::from([("old_well".to_string(), 1)]),
+            last_hint: Some(99.25),
+            respawn: None,
+        };
+        SaveFile {
+            version: SAVE_VERSION,
+            metadata: SaveMetadata::now(&data),
+            data,
+        }
+    }
+
+    fn write(save: &SaveFile) -> String {
+        ron::ser::to_string_pretty(save, PrettyConfig::default()).unwrap()
+    }
+
+    fn path() -> PathBuf {
+        PathBuf::from("slot_0.ron")
+    }
+
+    #[test]
+    fn saves_read_back_what_was_written() {
+        let save = full_save();
+        let read = parse_save(&path(), &write(&save)).unwrap();
+        assert_eq!(read.version, SAVE_VERSION);
+        assert_eq!(read.metadata, save.metadata);
+        assert_eq!(read.data, save.data);
+    }
+
+    #[test]
+    fn migrating_a_current_save_changes_nothing() {
+        let save = full_save();
+        let migrated = migrate_save(&path(), SAVE_VERSION, &write(&save)).unwrap();
+        assert_eq!(migrated.version, SAVE_VERSION);
+        assert_eq!(migrated.metadata, save.metadata);
+        assert_eq!(migrated.data, save.data);
+    }
+
+    #[test]
+    fn missing_and_future_versions_are_rejected() {
+        let mut save = full_save();
+        save.version = 0;
+        assert!(matches!(parse_save(&path(), &write(&save)), Err(SaveError::Migration { from: 0, .. })));
+        assert!(matches!(parse_metadata(&path(), &write(&save)), Err(SaveError::Migration { from: 0, .. })));
+
+        save.version = SAVE_VERSION + 1;
+        let unsupported = |result: Result<(), SaveError>| {
+            matches!(result, Err(SaveError::UnsupportedVersion { found, .. }) if found == SAVE_VERSION + 1)
+        };
+        assert!(unsupported(parse_save(&path(), &write(&save)).map(drop)));
+        assert!(unsupported(parse_metadata(&path(), &write(&save)).map(drop)));
+    }
+
+    #[test]
+    fn malformed_saves_are_parse_errors() {
+        assert!(matches!(parse_save(&path(), "(version: 1, metadata: "), Err(SaveError::Parse { .. })));
+        assert!(matches!(parse_save(&path(), "(metadata: ())"), Err(SaveError::Parse { .. })));
+    }
+
+    #[test]
+    fn metadata_is_read_without_the_game_data() {
+        let save = full_save();
+        assert_eq!(parse_metadata(&path(), &write(&save)).unwrap(), save.metadata);
+
+        // Data this build can't make sense of doesn't stop the slot being listed.
+        let contents = write(&save).replace("level: \"meadow\"", "level: (1, 2)");
+        assert!(parse_save(&path(), &contents).is_err());
+        assert_eq!(parse_metadata(&path(), &contents).unwrap(), save.metadata);
+    }
+
+    ///
+    /// An autosave still being written, which finishes writing slot 1.
+    ///
+    fn writing_task() -> Task<Result<u32, SaveError>> {
+        IoTaskPool::get_or_init(TaskPool::new).spawn(async { Ok(1) })
+    }
+
+    #[test]
+    fn autosaves_wait_for_the_interval() {
+        let mut autosaver = Autosaver::default();
+        assert!(!autosaver.due(0.0), "nothing has been asked for");
+
+        autosaver.pending = true;
+        assert!(autosaver.due(0.0), "the first autosave doesn't wait");
+
+        autosaver.last_started = Some(100.0);
+        assert!(!autosaver.due(100.0 + AUTOSAVE_INTERVAL - 0.5));
+        assert!(autosaver.due(100.0 + AUTOSAVE_INTERVAL));
+    }
+
+    #[test]
+    fn autosaves_wait_for_the_last_one_to_be_written() {
+        let mut autosaver = Autosaver {
+            task: Some(writing_task()),
+            last_started: Some(0.0),
+            pending: true,
+        };
+        assert!(autosaver.writing());
+        assert!(!autosaver.due(AUTOSAVE_INTERVAL * 2.0));
+
+        autosaver.task = None;
+        assert!(autosaver.due(AUTOSAVE_INTERVAL * 2.0));
+    }
+
+    #[test]
+    fn settling_finishes_the_write_and_forgets_what_is_pending() {
+        let mut autosaver = Autosaver {
+            task: Some(writing_task()),
+            last_started: Some(0.0),
+            pending: true,
+        };
+        autosaver.settle();
+        assert!(!autosaver.writing());
+        assert!(!autosaver.pending);
+        assert!(!autosaver.due(AUTOSAVE_INTERVAL * 2.0));
+    }
+}
@@ -0,0 +1,509 @@
+use crate::game::camera::CameraImpulse;
+use crate::game::items::{ItemCatalog, ItemSet, ItemUsed};
+use crate::game::level::{CurrentLevel, Level, LevelSecret, SecretCondition, SecretKind};
+use crate::game::player::{GridPosition, Player, PlayerInteract, PlayerMovementSet};
+use crate::game::primary_logic::{GameData, GameState, InGameScreen};
+use crate::game::puzzles::Signals;
+use crate::game::saves::SaveGame;
+use crate::game::settings::Settings;
+use crate::game::textcolor_smoothing::{TargetTextColor, TextColorSmoothing};
+use crate::game::world::{tile_to_world, CurrentMap, MapLoaded, MapSet, Tile, TileMap, TileSprite, TILE_SIZE};
+use crate::utils::position_smoothing::{PSmoothing, PositionSmoothing, TargetPosition};
+use bevy::prelude::*;
+
+///
+/// Hidden passages, buried items and lore fragments placed in levels, and the conditions for finding them.
+///
+#[derive(Default)]
+pub struct SecretsPlugin;
+
+///
+/// A secret in the current level which hasn't been discovered yet.
+///
+#[derive(Component)]
+pub struct Secret(pub LevelSecret);
+
+///
+/// How many steps of a [SecretCondition::Sequence] have been walked in order.
+///
+#[derive(Component, Default)]
+struct SequenceProgress(usize);
+
+///
+/// Sent once when the player discovers a secret, after it has been recorded in [GameData].
+///
+#[derive(Event, Clone, Debug)]
+pub struct SecretDiscovered {
+    pub id: String,
+    pub name: String,
+    pub kind: SecretKind,
+}
+
+///
+/// Sent by the condition checks when a secret's condition is met. The same secret may be sent more than once.
+///
+#[derive(Event)]
+struct DiscoverSecret(Entity);
+
+///
+/// The text floating up from a discovered secret, despawned when the timer runs out.
+///
+#[derive(Component)]
+struct RevealPopup(Timer);
+
+const REVEAL_FLASH_COLOR: Color = Color::srgb(3.0, 2.7, 1.8);
+const POPUP_COLOR: Color = Color::srgb(2.4, 2.1, 1.4);
+const POPUP_DURATION: f32 = 3.0;
+const POPUP_Z: f32 = 10.0;
+
+impl Plugin for SecretsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SecretDiscovered>();
+        app.add_event::<DiscoverSecret>();
+        app.add_systems(
+            Update,
+            spawn_secrets
+                .in_set(MapSet::Prepare)
+                .run_if(in_state(GameState::InGame).and_then(on_event::<MapLoaded>())),
+        );
+        app.add_systems(
+            Update,
+            (
+                (
+                    check_proximity,
+                    check_interactions,
+                    check_sequences,
+                    check_triggers,
+                    check_signals,
+                )
+                    .run_if(in_state(InGameScreen::Playing)),
+                // Items are used from the inventory screen, so this runs there too.
+                check_item_use,
+                discover_secrets,
+                save_discoveries,
+                update_popups,
+            )
+                .chain()
+                .after(PlayerMovementSet)
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+///
+/// Turns every hidden tile connected to `start` into path, returning the tiles which changed.
+///
+pub fn reveal_passage(map: &mut TileMap, start: IVec2) -> Vec<IVec2> {
+    let mut revealed = Vec::new();
+    let mut open = vec![start];
+    while let Some(position) = open.pop() {
+        if map.get(position) != Some(Tile::Hidden) {
+            continue;
+        }
+        map.set(position, Tile::Path);
+        revealed.push(position);
+        open.extend([IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y].map(|direction| position + direction));
+    }
+    revealed
+}
+
+///
+/// Whether `player` is within `radius` tiles of `position`, counted along the grid.
+///
+fn within_radius(position: IVec2, player: IVec2, radius: i32) -> bool {
+    let distance = (position - player).abs();
+    distance.x + distance.y <= radius
+}
+
+///
+/// Whether `position` is the tile the player at `player` is standing on or facing.
+///
+fn in_reach(position: IVec2, player: IVec2, facing: IVec2) -> bool {
+    player == position || player + facing == position
+}
+
+///
+/// Whether interacting from `player` while facing `facing` meets `condition` for a secret at `position`.
+///
+fn interaction_finds(condition: &SecretCondition, position: IVec2, player: IVec2, facing: IVec2) -> bool {
+    match condition {
+        SecretCondition::Interact { facing: None } => in_reach(position, player, facing),
+        SecretCondition::Interact { facing: Some(side) } => player + facing == position && facing == side.to_ivec2(),
+        _ => false,
+    }
+}
+
+///
+/// Whether using `item` from `player` while facing `facing` meets `condition` for a secret at `position`.
+///
+fn item_use_finds(condition: &SecretCondition, position: IVec2, item: &str, player: IVec2, facing: IVec2) -> bool {
+    match condition {
+        SecretCondition::UseItem { item: needed } => needed == item && in_reach(position, player, facing),
+        _ => false,
+    }
+}
+
+///
+/// How many of `steps` have been walked in order after the player steps onto `player`, given the `progress` before.
+///
+fn sequence_progress(steps: &[[i32; 2]], progress: usize, player: IVec2) -> usize {
+    let on_step = |i: usize| steps.get(i).is_some_and(|step| IVec2::from(*step) == player);
+    if on_step(progress) {
+        progress + 1
+    } else if steps.iter().any(|step| IVec2::from(*step) == player) {
+        // Out of order, which starts over. That step may be the first one again.
+        if on_step(0) {
+            1
+        } else {
+            0
+        }
+    } else {
+        progress
+    }
+}
+
+fn spawn_secrets(
+    mut commands: Commands,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    game_data: Res<GameData>,
+    mut current_map: ResMut<CurrentMap>,
+    old_secrets: Query<Entity, With<Secret>>,
+) {
+    for entity in old_secrets.iter() {
+        commands.entity(entity).despawn();
+    }
+    let Some(level) = levels.get(&current_level.handle) else {
+        return;
+    };
+
+    for secret in &level.file.secrets {
+        if !game_data.discovered_secrets.contains(&secret.id) {
+            commands.spawn((StateScoped(GameState::InGame), Secret(secret.clone()), SequenceProgress::default()));
+        } else if secret.kind == SecretKind::Passage {
+            // The map is rebuilt from the level file, so passages found earlier have to be opened again.
+            reveal_passage(&mut current_map.map, IVec2::from(secret.position));
+        }
+    }
+}
+
+fn check_proximity(
+    players: Query<&GridPosition, With<Player>>,
+    secrets: Query<(Entity, &Secret)>,
+    mut discover: EventWriter<DiscoverSecret>,
+) {
+    for player in players.iter() {
+        for (entity, Secret(secret)) in secrets.iter() {
+            if let SecretCondition::Proximity { radius } = secret.condition {
+                if within_radius(IVec2::from(secret.position), player.0, radius) {
+                    discover.send(DiscoverSecret(entity));
+                }
+            }
+        }
+    }
+}
+
+fn check_interactions(
+    mut interactions: EventReader<PlayerInteract>,
+    secrets: Query<(Entity, &Secret)>,
+    mut discover: EventWriter<DiscoverSecret>,
+) {
+    for interaction in interactions.read() {
+        for (entity, Secret(secret)) in secrets.iter() {
+            let position = IVec2::from(secret.position);
+            if interaction_finds(&secret.condition, position, interaction.position, interaction.facing) {
+                discover.send(DiscoverSecret(entity));
+            }
+        }
+    }
+}
+
+fn check_item_use(
+    mut used: EventReader<ItemUsed>,
+    secrets: Query<(Entity, &Secret)>,
+    mut discover: EventWriter<DiscoverSecret>,
+) {
+    for used in used.read() {
+        for (entity, Secret(secret)) in secrets.iter() {
+            let position = IVec2::from(secret.position);
+            if item_use_finds(&secret.condition, position, &used.item, used.position, used.facing) {
+                discover.send(DiscoverSecret(entity));
+            }
+        }
+    }
+}
+
+fn check_sequences(
+    players: Query<&GridPosition, (With<Player>, Changed<GridPosition>)>,
+    mut secrets: Query<(Entity, &Secret, &mut SequenceProgress)>,
+    mut discover: EventWriter<DiscoverSecret>,
+) {
+    for player in players.iter() {
+        for (entity, Secret(secret), mut progress) in secrets.iter_mut() {
+            let SecretCondition::Sequence { steps } = &secret.condition else {
+                continue;
+            };
+            progress.0 = sequence_progress(steps, progress.0, player.0);
+            if progress.0 == steps.len() {
+                discover.send(DiscoverSecret(entity));
+            }
+        }
+    }
+}
+
+fn check_triggers(
+    players: Query<&GridPosition, (With<Player>, Changed<GridPosition>)>,
+    secrets: Query<(Entity, &Secret)>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    mut discover: EventWriter<DiscoverSecret>,
+) {
+    let Some(level) = levels.get(&current_level.handle) else {
+        return;
+    };
+    for player in players.iter() {
+        let entered = level.file.triggers.iter().filter(|trigger| {
+            player.0.cmpge(IVec2::from(trigger.min)).all() && player.0.cmple(IVec2::from(trigger.max)).all()
+        });
+        for trigger in entered {
+            for (entity, Secret(secret)) in secrets.iter() {
+                if trigger.targets.contains(&secret.id) {
+                    discover.send(DiscoverSecret(entity));
+                }
+            }
+        }
+    }
+}
+
+fn check_signals(signals: Res<Signals>, secrets: Query<(Entity, &Secret)>, mut discover: EventWriter<DiscoverSecret>) {
+    for (entity, Secret(secret)) in secrets.iter() {
+        if let SecretCondition::Signal { signal } = &secret.condition {
+            if signals.0.is_on(signal) {
+                discover.send(DiscoverSecret(entity));
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn discover_secrets(
+    mut commands: Commands,
+    mut events: EventReader<DiscoverSecret>,
+    secrets: Query<&Secret>,
+    mut game_data: ResMut<GameData>,
+    mut current_map: ResMut<CurrentMap>,
+    mut tile_sprites: Query<(&TileSprite, &mut Sprite)>,
+    settings: Res<Settings>,
+    item_sets: Res<Assets<ItemSet>>,
+    mut discovered: EventWriter<SecretDiscovered>,
+    mut impulses: EventWriter<CameraImpulse>,
+) {
+    for DiscoverSecret(entity) in events.read() {
+        let Ok(Secret(secret)) = secrets.get(*entity) else {
+            continue;
+        };
+        if !game_data.discovered_secrets.insert(secret.id.clone()) {
+            continue;
+        }
+        game_data.unread_secrets.insert(secret.id.clone());
+        commands.entity(*entity).despawn();
+
+        let position = IVec2::from(secret.position);
+        if secret.kind == SecretKind::Passage {
+            let revealed = reveal_passage(&mut current_map.map, position);
+            // Flash the new path, which then fades to its normal color.
+            for (tile_sprite, mut sprite) in tile_sprites.iter_mut() {
+                if revealed.contains(&tile_sprite.0) {
+                    sprite.color = REVEAL_FLASH_COLOR;
+                }
+            }
+        }
+        if let Some(item) = &secret.item {
+            game_data.give_item(item);
+        }
+
+        let item_name = secret.item.as_ref().map(|item| ItemCatalog::name(&item_sets, item));
+        spawn_popup(&mut commands, secret, item_name, position, settings.accessibility.reduced_motion);
+        if secret.kind == SecretKind::Passage {
+            // The ground giving way.
+            impulses.send(CameraImpulse::Trauma(0.4));
+        } else {
+            impulses.send(CameraImpulse::Zoom(-0.05));
+        }
+        discovered.send(SecretDiscovered {
+            id: secret.id.clone(),
+            name: secret.name.clone(),
+            kind: secret.kind,
+        });
+    }
+}
+
+///
+/// Saves straight away so a discovery can't be lost.
+///
+fn save_discoveries(mut events: EventReader<SecretDiscovered>, mut save: EventWriter<SaveGame>) {
+    let mut any = false;
+    for event in events.read() {
+        info!("Discovered {:?} secret \"{}\" ({})", event.kind, event.name, event.id);
+        any = true;
+    }
+    if any {
+        save.send(SaveGame);
+    }
+}
+
+fn spawn_popup(
+    commands: &mut Commands,
+    secret: &LevelSecret,
+    item_name: Option<String>,
+    position: IVec2,
+    reduced_motion: bool,
+) {
+    let section = |value: String, font_size: f32| {
+        TextSection::new(
+            value,
+            TextStyle {
+                color: POPUP_COLOR,
+                font_size,
+                ..default()
+            },
+        )
+    };
+    let mut sections = vec![section(format!("Secret found: {}", secret.name), 24.0)];
+    if !secret.text.is_empty() {
+        sections.push(section(format!("\n{}", secret.text), 18.0));
+    }
+    if let Some(item) = item_name {
+        sections.push(section(format!("\nGot {item}"), 18.0));
+    }
+
+    let start = (tile_to_world(position) + Vec2::Y * TILE_SIZE).extend(POPUP_Z);
+    let (end, smoothing) = if reduced_motion {
+        (start, PositionSmoothing::None)
+    } else {
+        (
+            start + Vec3::Y * TILE_SIZE * 1.5,
+            PositionSmoothing::P(PSmoothing {
+                modifier: 1.5,
+                end_threshold: 0.5,
+            }),
+        )
+    };
+
+    commands.spawn((
+        StateScoped(GameState::InGame),
+        Text2dBundle {
+            text: Text::from_sections(sections).with_justify(JustifyText::Center),
+            transform: Transform::from_translation(start),
+            ..default()
+        },
+        TargetPosition(end),
+        smoothing,
+        TargetTextColor(Color::NONE),
+        TextColorSmoothing {
+            flat: 0.4,
+            proportional: 0.3,
+        },
+        RevealPopup(Timer::from_seconds(POPUP_DURATION, TimerMode::Once)),
+    ));
+}
+
+fn update_popups(mut commands: Commands, mut query: Query<(Entity, &mut RevealPopup)>, time: Res<Time>) {
+    for (entity, mut popup) in query.iter_mut() {
+        if popup.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::level::FacingDirection;
+
+    ///
+    /// A map from rows of [Tile::from_char] characters, with the first row at the top.
+    ///
+    fn map(rows: &[&str]) -> TileMap {
+        let mut map = TileMap::new();
+        for (row, line) in rows.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let y = (rows.len() - 1 - row) as i32;
+                map.set(IVec2::new(x as i32, y), Tile::from_char(c).unwrap());
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn proximity_counts_along_the_grid() {
+        let secret = IVec2::new(5, 5);
+        assert!(within_radius(secret, IVec2::new(3, 5), 2));
+        assert!(within_radius(secret, IVec2::new(5, 5), 0));
+        assert!(!within_radius(secret, IVec2::new(4, 4), 1));
+    }
+
+    #[test]
+    fn interacting_without_a_side_works_on_or_facing_the_secret() {
+        let condition = SecretCondition::Interact { facing: None };
+        let secret = IVec2::new(2, 2);
+        assert!(interaction_finds(&condition, secret, secret, IVec2::X));
+        assert!(interaction_finds(&condition, secret, IVec2::new(1, 2), IVec2::X));
+        assert!(!interaction_finds(&condition, secret, IVec2::new(1, 2), IVec2::Y));
+        assert!(!interaction_finds(&SecretCondition::Triggered, secret, secret, IVec2::X));
+    }
+
+    #[test]
+    fn interacting_from_a_side_needs_that_facing() {
+        let condition = SecretCondition::Interact {
+            facing: Some(FacingDirection::Up),
+        };
+        let secret = IVec2::new(2, 2);
+        assert!(interaction_finds(&condition, secret, IVec2::new(2, 1), IVec2::Y));
+        assert!(!interaction_finds(&condition, secret, IVec2::new(2, 3), IVec2::NEG_Y));
+        // Standing on the secret isn't facing it from any side.
+        assert!(!interaction_finds(&condition, secret, secret, IVec2::Y));
+    }
+
+    #[test]
+    fn only_the_right_item_in_reach_finds_the_secret() {
+        let condition = SecretCondition::UseItem {
+            item: "rusty_key".to_string(),
+        };
+        let secret = IVec2::new(2, 2);
+        assert!(item_use_finds(&condition, secret, "rusty_key", IVec2::new(2, 1), IVec2::Y));
+        assert!(item_use_finds(&condition, secret, "rusty_key", secret, IVec2::X));
+        assert!(!item_use_finds(&condition, secret, "glowberry", secret, IVec2::X));
+        assert!(!item_use_finds(&condition, secret, "rusty_key", IVec2::new(2, 0), IVec2::Y));
+    }
+
+    #[test]
+    fn sequences_advance_in_order_and_start_over_out_of_order() {
+        let steps = [[0, 0], [1, 0], [2, 0]];
+        assert_eq!(sequence_progress(&steps, 0, IVec2::new(0, 0)), 1);
+        assert_eq!(sequence_progress(&steps, 1, IVec2::new(1, 0)), 2);
+        // Tiles which aren't steps don't change anything.
+        assert_eq!(sequence_progress(&steps, 2, IVec2::new(5, 5)), 2);
+        assert_eq!(sequence_progress(&steps, 2, IVec2::new(1, 0)), 0);
+        assert_eq!(sequence_progress(&steps, 2, IVec2::new(0, 0)), 1);
+        assert_eq!(sequence_progress(&steps, 2, IVec2::new(2, 0)), 3);
+    }
+
+    #[test]
+    fn revealing_opens_only_the_connected_hidden_tiles() {
+        let mut map = map(&["#??#?", "#?###", "#.#??"]);
+        let mut revealed = reveal_passage(&mut map, IVec2::new(1, 2));
+        revealed.sort_by_key(|position| (position.x, position.y));
+        assert_eq!(revealed, vec![IVec2::new(1, 1), IVec2::new(1, 2), IVec2::new(2, 2)]);
+        assert_eq!(map.get(IVec2::new(4, 2)), Some(Tile::Hidden));
+        assert_eq!(map.get(IVec2::new(3, 0)), Some(Tile::Hidden));
+        assert_eq!(map.get(IVec2::new(1, 0)), Some(Tile::Path));
+    }
+
+    #[test]
+    fn revealing_from_a_tile_which_is_not_hidden_does_nothing() {
+        let mut map = map(&["?.?"]);
+        assert!(reveal_passage(&mut map, IVec2::new(1, 0)).is_empty());
+        assert_eq!(map.get(IVec2::ZERO), Some(Tile::Hidden));
+    }
+}
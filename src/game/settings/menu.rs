@@ -0,0 +1,222 @@
+use super::*;
+use crate::game::internal::{menu_button, menu_text, ScriptControlled};
+use crate::game::primary_logic::GameState;
+use crate::utils::buttoning::{ButtonClickEvent, ButtonClickType, Clickable, Hovered, Stepper};
+use bevy::sprite::Anchor;
+
+pub fn configure_app(app: &mut App) {
+    app.add_systems(OnEnter(GameState::Settings), on_enter);
+    app.add_systems(OnExit(GameState::Settings), on_exit);
+    app.add_systems(
+        Update,
+        (settings_button_clicks, back_on_escape, update_value_texts)
+            .chain()
+            .run_if(in_state(GameState::Settings)),
+    );
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SettingKind {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    WindowMode,
+    Resolution,
+    VSync,
+    Bloom,
+    Tonemapping,
+    TextSize,
+    ReducedMotion,
+    Movement,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Component)]
+pub enum SettingsButton {
+    Adjust(SettingKind),
+    Apply,
+    Revert,
+    Back,
+}
+
+const LABEL_COLOR: Color = Color::srgb(1.4, 1.4, 1.4);
+const VALUE_COLOR: Color = Color::srgb(1.4, 2.1, 2.4);
+
+impl SettingKind {
+    pub const ALL: [SettingKind; 11] = [
+        SettingKind::MasterVolume,
+        SettingKind::MusicVolume,
+        SettingKind::SfxVolume,
+        SettingKind::WindowMode,
+        SettingKind::Resolution,
+        SettingKind::VSync,
+        SettingKind::Bloom,
+        SettingKind::Tonemapping,
+        SettingKind::TextSize,
+        SettingKind::ReducedMotion,
+        SettingKind::Movement,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingKind::MasterVolume => "Master Volume",
+            SettingKind::MusicVolume => "Music Volume",
+            SettingKind::SfxVolume => "Effects Volume",
+            SettingKind::WindowMode => "Window Mode",
+            SettingKind::Resolution => "Resolution",
+            SettingKind::VSync => "VSync",
+            SettingKind::Bloom => "Bloom",
+            SettingKind::Tonemapping => "Tonemapping",
+            SettingKind::TextSize => "Text Size",
+            SettingKind::ReducedMotion => "Reduced Motion",
+            SettingKind::Movement => "Movement",
+        }
+    }
+
+    pub fn value_text(self, settings: &Settings) -> String {
+        fn on_off(value: bool) -> String {
+            if value { "On" } else { "Off" }.to_string()
+        }
+
+        match self {
+            SettingKind::MasterVolume => format!("{:.0}%", settings.audio.master_volume * 100.0),
+            SettingKind::MusicVolume => format!("{:.0}%", settings.audio.music_volume * 100.0),
+            SettingKind::SfxVolume => format!("{:.0}%", settings.audio.sfx_volume * 100.0),
+            SettingKind::WindowMode => format!("{:?}", settings.video.window_mode),
+            SettingKind::Resolution => format!("{} x {}", settings.video.resolution[0], settings.video.resolution[1]),
+            SettingKind::VSync => on_off(settings.video.vsync),
+            SettingKind::Bloom => on_off(settings.video.bloom),
+            SettingKind::Tonemapping => on_off(settings.video.tonemapping),
+            SettingKind::TextSize => format!("{:.0}%", settings.accessibility.text_scale * 100.0),
+            SettingKind::ReducedMotion => on_off(settings.accessibility.reduced_motion),
+            SettingKind::Movement => format!("{:?}", settings.gameplay.movement),
+        }
+    }
+
+    ///
+    /// Steps this setting forwards (`step > 0`) or backwards (`step < 0`), wrapping around for choices.
+    ///
+    pub fn adjust(self, settings: &mut Settings, step: i32) {
+        fn volume(value: &mut f32, step: i32) {
+            *value = ((*value * 10.0).round() + step as f32).clamp(0.0, 10.0) / 10.0;
+        }
+
+        fn cycle<T: Copy + PartialEq>(options: &[T], current: T, step: i32) -> T {
+            let index = options.iter().position(|o| *o == current).unwrap_or(0) as i32;
+            options[(index + step).rem_euclid(options.len() as i32) as usize]
+        }
+
+        match self {
+            SettingKind::MasterVolume => volume(&mut settings.audio.master_volume, step),
+            SettingKind::MusicVolume => volume(&mut settings.audio.music_volume, step),
+            SettingKind::SfxVolume => volume(&mut settings.audio.sfx_volume, step),
+            SettingKind::WindowMode => {
+                settings.video.window_mode = cycle(
+                    &[WindowModeSetting::Windowed, WindowModeSetting::Borderless, WindowModeSetting::Fullscreen],
+                    settings.video.window_mode,
+                    step,
+                )
+            }
+            SettingKind::Resolution => settings.video.resolution = cycle(&RESOLUTIONS, settings.video.resolution, step),
+            SettingKind::VSync => settings.video.vsync = !settings.video.vsync,
+            SettingKind::Bloom => settings.video.bloom = !settings.video.bloom,
+            SettingKind::Tonemapping => settings.video.tonemapping = !settings.video.tonemapping,
+            SettingKind::TextSize => {
+                settings.accessibility.text_scale = cycle(&[0.75, 1.0, 1.25, 1.5], settings.accessibility.text_scale, step)
+            }
+            SettingKind::ReducedMotion => settings.accessibility.reduced_motion = !settings.accessibility.reduced_motion,
+            SettingKind::Movement => {
+                settings.gameplay.movement = cycle(&[MovementMode::Grid, MovementMode::Free], settings.gameplay.movement, step)
+            }
+        }
+    }
+}
+
+fn on_enter(mut commands: Commands, settings: Res<Settings>) {
+    commands.insert_resource(SettingsPreview(settings.clone()));
+
+    commands.spawn((
+        StateScoped(GameState::Settings),
+        menu_text("Settings", Color::srgb(1.4, 2.1, 2.4), 48.0, 50.0, 92.0, Anchor::Center),
+    ));
+
+    for (i, kind) in SettingKind::ALL.into_iter().enumerate() {
+        let y = 81.0 - i as f32 * 5.5;
+        commands.spawn((
+            StateScoped(GameState::Settings),
+            menu_text(kind.label(), LABEL_COLOR, 26.0, 47.0, y, Anchor::CenterRight),
+        ));
+        commands.spawn((
+            StateScoped(GameState::Settings),
+            menu_button(kind.value_text(&settings), VALUE_COLOR, 26.0, 53.0, y, Anchor::CenterLeft),
+            SettingsButton::Adjust(kind),
+            Stepper,
+        ));
+    }
+
+    for (label, color, x, button) in [
+        ("Apply", Color::srgb(1.4, 2.1, 1.4), 35.0, SettingsButton::Apply),
+        ("Revert", Color::srgb(2.1, 2.1, 1.4), 50.0, SettingsButton::Revert),
+        ("Back", Color::srgb(2.1, 1.4, 1.4), 65.0, SettingsButton::Back),
+    ] {
+        commands.spawn((
+            StateScoped(GameState::Settings),
+            menu_button(label, color, 30.0, x, 12.0, Anchor::Center),
+            button,
+        ));
+    }
+}
+
+fn on_exit(mut commands: Commands) {
+    // Anything not applied is reverted by dropping the preview.
+    commands.remove_resource::<SettingsPreview>();
+}
+
+#[allow(clippy::type_complexity)]
+fn settings_button_clicks(
+    query: Query<&SettingsButton, (With<Hovered>, With<Clickable>, Without<ScriptControlled>)>,
+    mut event_reader: EventReader<ButtonClickEvent>,
+    mut settings: ResMut<Settings>,
+    mut preview: ResMut<SettingsPreview>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in event_reader.read() {
+        if event.click_type != ButtonClickType::Pressed {
+            continue;
+        }
+        let step = match event.mouse_button {
+            MouseButton::Left => 1,
+            MouseButton::Right => -1,
+            _ => continue,
+        };
+
+        if let Ok(button) = query.get(event.entity) {
+            match button {
+                SettingsButton::Adjust(kind) => kind.adjust(&mut preview.0, step),
+                SettingsButton::Apply => {
+                    if *settings != preview.0 {
+                        *settings = preview.0.clone();
+                    }
+                }
+                SettingsButton::Revert => preview.0 = settings.clone(),
+                SettingsButton::Back => next_state.set(GameState::MainMenu),
+            }
+        }
+    }
+}
+
+fn back_on_escape(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn update_value_texts(preview: Res<SettingsPreview>, mut query: Query<(&SettingsButton, &mut Text)>) {
+    if !preview.is_changed() {
+        return;
+    }
+    for (button, mut text) in query.iter_mut() {
+        if let SettingsButton::Adjust(kind) = button {
+            text.sections[0].value = kind.value_text(&preview.0);
+        }
+    }
+}
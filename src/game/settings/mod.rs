@@ -0,0 +1,244 @@
+use crate::game::primary_logic::MainCamera;
+use bevy::audio::GlobalVolume;
+use bevy::core_pipeline::bloom::BloomSettings;
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+mod menu;
+mod persistence;
+
+///
+/// Owns the player's [Settings] and keeps the window, camera, audio and text in sync with them.
+///
+pub struct SettingsPlugin {
+    /// The file settings are loaded from and saved to.
+    pub path: PathBuf,
+}
+
+#[derive(Resource, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub audio: AudioSettings,
+    pub video: VideoSettings,
+    pub accessibility: AccessibilitySettings,
+    pub gameplay: GameplaySettings,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master_volume: f32,
+    /// Kept for when the game has music, only the master volume is applied for now.
+    pub music_volume: f32,
+    /// Kept for when the game has sound effects, only the master volume is applied for now.
+    pub sfx_volume: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoSettings {
+    pub window_mode: WindowModeSetting,
+    pub resolution: [u32; 2],
+    pub vsync: bool,
+    pub bloom: bool,
+    pub tonemapping: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    /// Multiplier applied to every font size.
+    pub text_scale: f32,
+    /// Disables camera shake and other large or sudden movement.
+    pub reduced_motion: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameplaySettings {
+    pub movement: MovementMode,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default, Serialize, Deserialize)]
+pub enum MovementMode {
+    /// The player steps from tile to tile.
+    #[default]
+    Grid,
+    /// The player walks freely, sliding along anything they can't walk through.
+    Free,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default, Serialize, Deserialize)]
+pub enum WindowModeSetting {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+///
+/// Settings being edited on the settings screen. While this exists it is applied instead of
+/// [Settings] so changes can be previewed before they are applied.
+///
+#[derive(Resource, Clone, Debug)]
+pub struct SettingsPreview(pub Settings);
+
+///
+/// The font size a text was spawned with, before [AccessibilitySettings::text_scale] is applied.
+/// Inserted automatically on every [Text].
+///
+#[derive(Component)]
+pub struct BaseFontSize(pub Vec<f32>);
+
+pub const RESOLUTIONS: [[u32; 2]; 4] = [[1280, 720], [1600, 900], [1920, 1080], [2560, 1440]];
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 0.8,
+            sfx_volume: 0.8,
+        }
+    }
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            window_mode: WindowModeSetting::Windowed,
+            resolution: RESOLUTIONS[0],
+            vsync: true,
+            bloom: true,
+            tonemapping: true,
+        }
+    }
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            text_scale: 1.0,
+            reduced_motion: false,
+        }
+    }
+}
+
+impl From<WindowModeSetting> for WindowMode {
+    fn from(value: WindowModeSetting) -> Self {
+        match value {
+            WindowModeSetting::Windowed => WindowMode::Windowed,
+            WindowModeSetting::Borderless => WindowMode::BorderlessFullscreen,
+            WindowModeSetting::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+impl Settings {
+    ///
+    /// The settings which should currently be in effect: the preview while on the settings screen,
+    /// otherwise the saved settings.
+    ///
+    pub fn active<'a>(&'a self, preview: Option<&'a SettingsPreview>) -> &'a Settings {
+        preview.map(|p| &p.0).unwrap_or(self)
+    }
+}
+
+impl Default for SettingsPlugin {
+    fn default() -> Self {
+        Self {
+            path: persistence::default_settings_path(),
+        }
+    }
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        persistence::configure_app(app, self.path.clone());
+        app.add_systems(
+            PostUpdate,
+            (apply_settings, capture_base_font_sizes, apply_text_scale.after(capture_base_font_sizes)),
+        );
+        menu::configure_app(app);
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    preview: Option<Res<SettingsPreview>>,
+    mut last_applied: Local<Option<Settings>>,
+    mut commands: Commands,
+    mut windowq: Query<&mut Window, With<PrimaryWindow>>,
+    mut cameraq: Query<(Entity, &mut Tonemapping, Has<BloomSettings>), With<MainCamera>>,
+    mut global_volume: Option<ResMut<GlobalVolume>>,
+) {
+    let active = settings.active(preview.as_deref());
+    if last_applied.as_ref() == Some(active) {
+        return;
+    }
+
+    if let Ok(mut window) = windowq.get_single_mut() {
+        let mode = active.video.window_mode.into();
+        if window.mode != mode {
+            window.mode = mode;
+        }
+        let [width, height] = active.video.resolution;
+        if window.resolution.width() != width as f32 || window.resolution.height() != height as f32 {
+            window.resolution.set(width as f32, height as f32);
+        }
+        window.present_mode = if active.video.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+    }
+
+    for (entity, mut tonemapping, has_bloom) in cameraq.iter_mut() {
+        *tonemapping = if active.video.tonemapping {
+            Tonemapping::TonyMcMapface
+        } else {
+            Tonemapping::None
+        };
+        if active.video.bloom && !has_bloom {
+            commands.entity(entity).insert(BloomSettings::default());
+        } else if !active.video.bloom && has_bloom {
+            commands.entity(entity).remove::<BloomSettings>();
+        }
+    }
+
+    if let Some(global_volume) = global_volume.as_mut() {
+        **global_volume = GlobalVolume::new(active.audio.master_volume);
+    }
+
+    *last_applied = Some(active.clone());
+}
+
+fn capture_base_font_sizes(mut commands: Commands, query: Query<(Entity, &Text), Without<BaseFontSize>>) {
+    for (entity, text) in query.iter() {
+        commands
+            .entity(entity)
+            .insert(BaseFontSize(text.sections.iter().map(|s| s.style.font_size).collect()));
+    }
+}
+
+fn apply_text_scale(
+    settings: Res<Settings>,
+    preview: Option<Res<SettingsPreview>>,
+    mut query: Query<(Ref<BaseFontSize>, &mut Text)>,
+    mut last_scale: Local<f32>,
+) {
+    let scale = settings.active(preview.as_deref()).accessibility.text_scale;
+    let scale_changed = *last_scale != scale;
+    *last_scale = scale;
+
+    for (base, mut text) in query.iter_mut() {
+        if !scale_changed && !base.is_added() {
+            continue;
+        }
+        for (section, base_size) in text.sections.iter_mut().zip(base.0.iter()) {
+            section.style.font_size = base_size * scale;
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use super::*;
+use crate::game::outro::ExitFlush;
+use crate::utils::files::write_atomic;
+use ron::ser::PrettyConfig;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use thiserror::Error;
+
+const SETTINGS_FILE_NAME: &str = "settings.ron";
+const HOT_RELOAD_INTERVAL: f32 = 1.0;
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("could not access settings file {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("settings file {path} is malformed: {source}")]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: ron::error::SpannedError,
+    },
+    #[error("could not serialize settings: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("{name} must be between {min} and {max}, but is {value}")]
+    OutOfRange {
+        name: &'static str,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    #[error("resolution {0}x{1} is invalid")]
+    InvalidResolution(u32, u32),
+}
+
+///
+/// Where [Settings] are stored, and when that file was last seen so edits to it can be hot reloaded.
+///
+#[derive(Resource)]
+pub struct SettingsFile {
+    pub path: PathBuf,
+    last_modified: Option<SystemTime>,
+    /// What the file is known to contain, so unchanged settings are never rewritten.
+    last_saved: Option<Settings>,
+    reload_timer: Timer,
+}
+
+pub fn configure_app(app: &mut App, path: PathBuf) {
+    app.insert_resource(SettingsFile {
+        path,
+        last_modified: None,
+        last_saved: None,
+        reload_timer: Timer::from_seconds(HOT_RELOAD_INTERVAL, TimerMode::Repeating),
+    });
+    app.init_resource::<Settings>();
+    app.add_systems(PreStartup, load_settings_file);
+    app.add_systems(Update, (save_changed_settings, hot_reload_settings).chain());
+    app.add_systems(Update, flush_settings.in_set(ExitFlush));
+}
+
+///
+/// Replaces the default [Settings] with those in the [SettingsFile], writing the defaults there on first run.
+///
+fn load_settings_file(mut settings: ResMut<Settings>, mut file: ResMut<SettingsFile>) {
+    let loaded = match load_settings(&file.path) {
+        Ok(Some(loaded)) => loaded,
+        Ok(None) => {
+            let defaults = Settings::default();
+            if let Err(e) = save_settings(&file.path, &defaults) {
+                error!("{e}");
+            }
+            defaults
+        }
+        Err(e) => {
+            error!("{e}, using default settings");
+            Settings::default()
+        }
+    };
+
+    file.last_modified = modified_time(&file.path);
+    file.last_saved = Some(loaded.clone());
+    *settings = loaded;
+}
+
+///
+/// The platform config directory (e.g. `~/.config/secrets-of-the-path/settings.ron` on Linux), or the
+/// working directory if there isn't one.
+///
+pub fn default_settings_path() -> PathBuf {
+    dirs::config_dir()
+        .map(|dir| dir.join("secrets-of-the-path"))
+        .unwrap_or_default()
+        .join(SETTINGS_FILE_NAME)
+}
+
+///
+/// Reads and validates the settings at `path`. Returns `Ok(None)` if the file does not exist yet.
+///
+/// Missing fields fall back to their defaults and unknown fields are ignored, so files written by
+/// older or newer versions of the game still load.
+///
+pub fn load_settings(path: &Path) -> Result<Option<Settings>, SettingsError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => {
+            return Err(SettingsError::Io {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    let settings: Settings = ron::from_str(&contents).map_err(|source| SettingsError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    settings.validate()?;
+    Ok(Some(settings))
+}
+
+pub fn save_settings(path: &Path, settings: &Settings) -> Result<(), SettingsError> {
+    let contents = ron::ser::to_string_pretty(settings, PrettyConfig::default())?;
+    write_atomic(path, contents.as_bytes()).map_err(|source| SettingsError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        fn range(name: &'static str, value: f32, min: f32, max: f32) -> Result<(), SettingsError> {
+            if (min..=max).contains(&value) {
+                Ok(())
+            } else {
+                Err(SettingsError::OutOfRange { name, value, min, max })
+            }
+        }
+
+        range("audio.master_volume", self.audio.master_volume, 0.0, 1.0)?;
+        range("audio.music_volume", self.audio.music_volume, 0.0, 1.0)?;
+        range("audio.sfx_volume", self.audio.sfx_volume, 0.0, 1.0)?;
+        range("accessibility.text_scale", self.accessibility.text_scale, 0.5, 2.0)?;
+
+        let [width, height] = self.video.resolution;
+        if width == 0 || height == 0 {
+            return Err(SettingsError::InvalidResolution(width, height));
+        }
+        Ok(())
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn write_settings(file: &mut SettingsFile, settings: &Settings) {
+    if file.last_saved.as_ref() == Some(settings) {
+        return;
+    }
+    match save_settings(&file.path, settings) {
+        Ok(()) => {
+            file.last_modified = modified_time(&file.path);
+            file.last_saved = Some(settings.clone());
+        }
+        Err(e) => error!("{e}"),
+    }
+}
+
+fn save_changed_settings(settings: Res<Settings>, mut file: ResMut<SettingsFile>) {
+    if settings.is_changed() {
+        write_settings(&mut file, &settings);
+    }
+}
+
+fn hot_reload_settings(mut settings: ResMut<Settings>, mut file: ResMut<SettingsFile>, time: Res<Time>) {
+    if !file.reload_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let modified = modified_time(&file.path);
+    if modified.is_none() || modified == file.last_modified {
+        return;
+    }
+    file.last_modified = modified;
+
+    match load_settings(&file.path) {
+        Ok(Some(loaded)) => {
+            info!("Reloaded settings from {}", file.path.display());
+            file.last_saved = Some(loaded.clone());
+            *settings = loaded;
+        }
+        Ok(None) => {}
+        Err(e) => error!("{e}, keeping current settings"),
+    }
+}
+
+fn flush_settings(settings: Res<Settings>, mut file: ResMut<SettingsFile>) {
+    write_settings(&mut file, &settings);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    ///
+    /// A settings path in a fresh directory of its own, removed when dropped.
+    ///
+    struct TempSettings(PathBuf);
+
+    impl TempSettings {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("sotp-settings-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            Self(dir.join(SETTINGS_FILE_NAME))
+        }
+    }
+
+    impl Drop for TempSettings {
+        fn drop(&mut self) {
+            if let Some(dir) = self.0.parent() {
+                let _ = fs::remove_dir_all(dir);
+            }
+        }
+    }
+
+    #[test]
+    fn missing_files_load_as_none() {
+        let path = TempSettings::new("missing");
+        assert!(load_settings(&path.0).unwrap().is_none());
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let path = TempSettings::new("round-trip");
+        let mut settings = Settings::default();
+        settings.audio.master_volume = 0.25;
+        settings.video.window_mode = WindowModeSetting::Borderless;
+        settings.accessibility.reduced_motion = true;
+        settings.gameplay.movement = MovementMode::Free;
+        save_settings(&path.0, &settings).unwrap();
+        assert_eq!(load_settings(&path.0).unwrap(), Some(settings));
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored_and_missing_ones_default() {
+        let path = TempSettings::new("unknown-fields");
+        fs::create_dir_all(path.0.parent().unwrap()).unwrap();
+        fs::write(&path.0, "(audio: (master_volume: 0.5, music: 0.1), haptics: (rumble: true))").unwrap();
+
+        let settings = load_settings(&path.0).unwrap().unwrap();
+        assert_eq!(settings.audio.master_volume, 0.5);
+        assert_eq!(settings.video, VideoSettings::default());
+    }
+
+    #[test]
+    fn out_of_range_settings_are_rejected() {
+        let mut settings = Settings::default();
+        settings.accessibility.text_scale = 4.0;
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsError::OutOfRange {
+                name: "accessibility.text_scale",
+                ..
+            })
+        ));
+
+        let mut settings = Settings::default();
+        settings.video.resolution = [0, 720];
+        assert!(matches!(settings.validate(), Err(SettingsError::InvalidResolution(0, 720))));
+
+        let path = TempSettings::new("out-of-range");
+        fs::create_dir_all(path.0.parent().unwrap()).unwrap();
+        fs::write(&path.0, "(audio: (master_volume: 1.5))").unwrap();
+        assert!(matches!(load_settings(&path.0), Err(SettingsError::OutOfRange { .. })));
+    }
+
+    #[test]
+    fn startup_writes_defaults_to_the_given_path() {
+        let path = TempSettings::new("startup");
+        let mut world = World::new();
+        world.init_resource::<Settings>();
+        world.insert_resource(SettingsFile {
+            path: path.0.clone(),
+            last_modified: None,
+            last_saved: None,
+            reload_timer: Timer::from_seconds(HOT_RELOAD_INTERVAL, TimerMode::Repeating),
+        });
+        world.run_system_once(load_settings_file);
+
+        assert_eq!(load_settings(&path.0).unwrap(), Some(Settings::default()));
+        assert_eq!(world.resource::<SettingsFile>().last_saved, Some(Settings::default()));
+    }
+}
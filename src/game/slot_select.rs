@@ -0,0 +1,328 @@
+use crate::game::internal::{menu_button, menu_text, ScriptControlled};
+use crate::game::primary_logic::GameState;
+use crate::game::saves::{
+    DeleteSave, GameLoaded, LoadFailed, LoadGame, NewGame, SaveError, SaveMetadata, SaveSlots, SavesSet,
+};
+use crate::utils::buttoning::{ButtonClickEvent, ButtonClickType, Clickable, Hovered};
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::Text2dBounds;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+///
+/// The save slot picker shown after pressing Play on the main menu.
+///
+#[derive(Default)]
+pub struct SlotSelectPlugin;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Component)]
+pub enum SlotButton {
+    NewGame(u32),
+    Wander(u32),
+    Continue(u32),
+    Delete(u32),
+    ConfirmDelete(u32),
+    CancelDelete,
+    Back,
+}
+
+///
+/// Marks everything which is rebuilt when the slots change.
+///
+#[derive(Default, Component)]
+struct SlotRow;
+
+///
+/// The slot waiting for the player to confirm deleting it.
+///
+#[derive(Resource, Default)]
+struct PendingDelete(Option<u32>);
+
+///
+/// Why the last slot the player tried to continue couldn't be loaded, shown until they leave the picker.
+///
+#[derive(Resource, Default)]
+struct LoadError(Option<(u32, String)>);
+
+const INFO_COLOR: Color = Color::srgb(1.4, 1.4, 1.4);
+const CONTINUE_COLOR: Color = Color::srgb(1.4, 2.1, 1.4);
+const NEW_GAME_COLOR: Color = Color::srgb(1.4, 2.1, 2.4);
+const DELETE_COLOR: Color = Color::srgb(2.1, 1.4, 1.4);
+const ERROR_COLOR: Color = Color::srgb(2.4, 0.9, 0.8);
+const SEED_COLOR: Color = Color::srgb(0.9, 0.9, 0.95);
+
+impl Plugin for SlotSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingDelete>();
+        app.init_resource::<LoadError>();
+        app.add_systems(OnEnter(GameState::SlotSelect), on_enter);
+        app.add_systems(OnExit(GameState::SlotSelect), clear_load_error);
+        // Continuing from the main menu loads too, so this runs outside the picker as well.
+        app.add_systems(Update, finish_loading.after(SavesSet).before(rebuild_rows));
+        app.add_systems(
+            Update,
+            (
+                (slot_button_clicks, back_on_escape).before(SavesSet),
+                rebuild_rows.after(SavesSet),
+            )
+                .run_if(in_state(GameState::SlotSelect)),
+        );
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GameStart {
+    /// Continue from the slot's save.
+    Continue,
+    /// Start over on the hand-made levels.
+    New,
+    /// Start over in a world generated from this seed.
+    Procedural(u64),
+}
+
+///
+/// Starts playing in `slot`, either from its save or from scratch. Continuing only enters the game once
+/// the save has loaded, and goes to the slot picker to show why if it couldn't be.
+///
+pub fn start_game(slot: u32, start: GameStart, commands: &mut Commands) {
+    info!("Starting slot {slot} ({start:?})");
+    commands.add(move |world: &mut World| {
+        let procedural_seed = match start {
+            GameStart::Procedural(seed) => Some(seed),
+            _ => None,
+        };
+        if start == GameStart::Continue {
+            world.send_event(LoadGame(slot));
+        } else {
+            world.send_event(NewGame { slot, procedural_seed });
+            world.resource_mut::<NextState<GameState>>().set(GameState::InGame);
+        }
+    });
+}
+
+pub fn format_play_time(seconds: f64) -> String {
+    let minutes = (seconds / 60.0) as u64;
+    if minutes >= 60 {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+pub fn format_age(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let age = now.saturating_sub(timestamp);
+    let plural = |n: u64, unit: &str| format!("{n} {unit}{} ago", if n == 1 { "" } else { "s" });
+    match age {
+        0..60 => "just now".to_string(),
+        60..3600 => plural(age / 60, "minute"),
+        3600..86400 => plural(age / 3600, "hour"),
+        _ => plural(age / 86400, "day"),
+    }
+}
+
+fn on_enter(
+    mut commands: Commands,
+    slots: Res<SaveSlots>,
+    mut pending_delete: ResMut<PendingDelete>,
+    load_error: Res<LoadError>,
+) {
+    if pending_delete.0.is_some() {
+        pending_delete.0 = None;
+    }
+
+    commands.spawn((
+        StateScoped(GameState::SlotSelect),
+        menu_text("Choose a Path", Color::srgb(1.4, 2.1, 2.4), 48.0, 50.0, 88.0, Anchor::Center),
+    ));
+    commands.spawn((
+        StateScoped(GameState::SlotSelect),
+        menu_button("Back", Color::srgb(2.1, 1.4, 1.4), 30.0, 50.0, 15.0, Anchor::Center),
+        SlotButton::Back,
+    ));
+
+    spawn_rows(&mut commands, &slots, None, &load_error);
+}
+
+fn clear_load_error(mut load_error: ResMut<LoadError>) {
+    load_error.0 = None;
+}
+
+fn spawn_rows(commands: &mut Commands, slots: &SaveSlots, pending_delete: Option<u32>, load_error: &LoadError) {
+    for (slot, metadata) in slots.list() {
+        let y = 68.0 - slot as f32 * 15.0;
+        commands.spawn((
+            StateScoped(GameState::SlotSelect),
+            SlotRow,
+            menu_text(slot_info(slot, &metadata), INFO_COLOR, 26.0, 20.0, y, Anchor::CenterLeft),
+        ));
+        let mut detail_y = y - 5.0;
+        if let Some(seed) = metadata.as_ref().ok().and_then(|metadata| metadata.as_ref()?.procedural_seed) {
+            commands.spawn((
+                StateScoped(GameState::SlotSelect),
+                SlotRow,
+                menu_text(format!("Seed {seed}"), SEED_COLOR, 14.0, 20.0, detail_y, Anchor::TopLeft),
+            ));
+            detail_y -= 3.5;
+        }
+        if let Some((_, error)) = load_error.0.as_ref().filter(|(failed, _)| *failed == slot) {
+            commands
+                .spawn((
+                    StateScoped(GameState::SlotSelect),
+                    SlotRow,
+                    menu_text(format!("Couldn't load: {error}"), ERROR_COLOR, 14.0, 20.0, detail_y, Anchor::TopLeft),
+                ))
+                .insert(Text2dBounds {
+                    size: Vec2::new(700.0, f32::INFINITY),
+                });
+        }
+
+        let mut spawn = |bundle: (_, SlotButton)| {
+            commands.spawn((StateScoped(GameState::SlotSelect), SlotRow, bundle));
+        };
+
+        if pending_delete == Some(slot) {
+            spawn((
+                menu_button("Delete?", DELETE_COLOR, 26.0, 66.0, y, Anchor::Center),
+                SlotButton::ConfirmDelete(slot),
+            ));
+            spawn((
+                menu_button("Keep", CONTINUE_COLOR, 26.0, 78.0, y, Anchor::Center),
+                SlotButton::CancelDelete,
+            ));
+            continue;
+        }
+
+        match metadata {
+            Ok(None) => {
+                spawn((
+                    menu_button("New Game", NEW_GAME_COLOR, 26.0, 66.0, y, Anchor::Center),
+                    SlotButton::NewGame(slot),
+                ));
+                spawn((
+                    menu_button("Wander", NEW_GAME_COLOR, 26.0, 78.0, y, Anchor::Center),
+                    SlotButton::Wander(slot),
+                ));
+            }
+            Ok(Some(_)) => {
+                spawn((
+                    menu_button("Continue", CONTINUE_COLOR, 26.0, 66.0, y, Anchor::Center),
+                    SlotButton::Continue(slot),
+                ));
+                spawn((
+                    menu_button("Delete", DELETE_COLOR, 26.0, 78.0, y, Anchor::Center),
+                    SlotButton::Delete(slot),
+                ));
+            }
+            // An unreadable save can only be deleted.
+            Err(_) => spawn((
+                menu_button("Delete", DELETE_COLOR, 26.0, 78.0, y, Anchor::Center),
+                SlotButton::Delete(slot),
+            )),
+        }
+    }
+}
+
+fn slot_info(slot: u32, metadata: &Result<Option<SaveMetadata>, SaveError>) -> String {
+    match metadata {
+        Ok(Some(metadata)) => {
+            let location = if metadata.location_name.is_empty() {
+                "The Path"
+            } else {
+                &metadata.location_name
+            };
+            format!(
+                "Slot {}   {}   {}   saved {}",
+                slot + 1,
+                location,
+                format_play_time(metadata.play_time),
+                format_age(metadata.timestamp)
+            )
+        }
+        Ok(None) => format!("Slot {}   Empty", slot + 1),
+        Err(e) => {
+            warn!("{e}");
+            format!("Slot {}   Unreadable save", slot + 1)
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn slot_button_clicks(
+    query: Query<&SlotButton, (With<Hovered>, With<Clickable>, Without<ScriptControlled>)>,
+    mut event_reader: EventReader<ButtonClickEvent>,
+    mut pending_delete: ResMut<PendingDelete>,
+    mut delete_events: EventWriter<DeleteSave>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    for event in event_reader.read() {
+        if event.mouse_button != MouseButton::Left || event.click_type != ButtonClickType::Pressed {
+            continue;
+        }
+
+        if let Ok(button) = query.get(event.entity) {
+            match *button {
+                SlotButton::NewGame(slot) => start_game(slot, GameStart::New, &mut commands),
+                SlotButton::Wander(slot) => start_game(slot, GameStart::Procedural(rand::random()), &mut commands),
+                SlotButton::Continue(slot) => start_game(slot, GameStart::Continue, &mut commands),
+                SlotButton::Delete(slot) => pending_delete.0 = Some(slot),
+                SlotButton::ConfirmDelete(slot) => {
+                    delete_events.send(DeleteSave(slot));
+                    pending_delete.0 = None;
+                }
+                SlotButton::CancelDelete => pending_delete.0 = None,
+                SlotButton::Back => next_state.set(GameState::MainMenu),
+            }
+        }
+    }
+}
+
+fn back_on_escape(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+///
+/// Enters the game once a save has loaded, or keeps the player on the slot picker with the error.
+///
+fn finish_loading(
+    mut loaded: EventReader<GameLoaded>,
+    mut failed: EventReader<LoadFailed>,
+    mut load_error: ResMut<LoadError>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if let Some(LoadFailed { slot, error }) = failed.read().last() {
+        load_error.0 = Some((*slot, error.clone()));
+        next_state.set(GameState::SlotSelect);
+    } else if loaded.read().count() > 0 {
+        next_state.set(GameState::InGame);
+    }
+    loaded.clear();
+}
+
+fn rebuild_rows(
+    mut commands: Commands,
+    pending_delete: Res<PendingDelete>,
+    load_error: Res<LoadError>,
+    mut delete_events: EventReader<DeleteSave>,
+    slots: Res<SaveSlots>,
+    rows: Query<Entity, With<SlotRow>>,
+) {
+    let deleted = delete_events.read().count() > 0;
+    let changed = |changed: bool, added: bool| changed && !added;
+    if !deleted
+        && !changed(pending_delete.is_changed(), pending_delete.is_added())
+        && !changed(load_error.is_changed(), load_error.is_added())
+    {
+        return;
+    }
+    for entity in rows.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_rows(&mut commands, &slots, pending_delete.0, &load_error);
+}
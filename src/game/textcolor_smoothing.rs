@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use bevy::render::camera::ClearColorConfig;
 
 #[derive(Default)]
 pub struct TextColorSmoothingPlugin;
@@ -6,6 +7,18 @@ pub struct TextColorSmoothingPlugin;
 #[derive(Component)]
 pub struct TargetTextColor(pub Color);
 
+///
+/// Smooths a camera's clear color towards this color, using the [TextColorSmoothing] on the same entity.
+///
+#[derive(Component)]
+pub struct TargetClearColor(pub Color);
+
+///
+/// Smooths a sprite's color towards this color, using the [TextColorSmoothing] on the same entity.
+///
+#[derive(Component)]
+pub struct TargetSpriteColor(pub Color);
+
 #[derive(Component)]
 pub struct TextColorSmoothing {
     pub flat: f32,
@@ -21,9 +34,22 @@ impl Default for TextColorSmoothing {
     }
 }
 
+impl TextColorSmoothing {
+    pub fn smooth_color(&self, color: Color, target: Color, delta_time: f32) -> Color {
+        let orig = color.to_srgba();
+        let targ = target.to_srgba();
+        Color::srgba(
+            smoothto(orig.red, targ.red, (self.flat + (targ.red - orig.red).abs() * self.proportional) * delta_time),
+            smoothto(orig.green, targ.green, (self.flat + (targ.green - orig.green).abs() * self.proportional) * delta_time),
+            smoothto(orig.blue, targ.blue, (self.flat + (targ.blue - orig.blue).abs() * self.proportional) * delta_time),
+            smoothto(orig.alpha, targ.alpha, (self.flat + (targ.alpha - orig.alpha).abs() * self.proportional) * delta_time),
+        )
+    }
+}
+
 impl Plugin for TextColorSmoothingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PostUpdate, update_text_colors);
+        app.add_systems(PostUpdate, (update_text_colors, update_sprite_colors, update_clear_colors));
     }
 }
 
@@ -42,14 +68,29 @@ fn update_text_colors(
 ) {
     for (smoothing, target_color, mut text) in query.iter_mut() {
         for section in text.sections.iter_mut() {
-            let orig = section.style.color.to_srgba();
-            let targ = target_color.0.to_srgba();
-            section.style.color = Color::srgba(
-                smoothto(orig.red, targ.red, (smoothing.flat + (targ.red - orig.red).abs() * smoothing.proportional) * time.delta_seconds()),
-                smoothto(orig.green, targ.green, (smoothing.flat + (targ.green - orig.green).abs() * smoothing.proportional) * time.delta_seconds()),
-                smoothto(orig.blue, targ.blue, (smoothing.flat + (targ.blue - orig.blue).abs() * smoothing.proportional) * time.delta_seconds()),
-                smoothto(orig.alpha, targ.alpha, (smoothing.flat + (targ.alpha - orig.alpha).abs() * smoothing.proportional) * time.delta_seconds()),
-            );
+            section.style.color = smoothing.smooth_color(section.style.color, target_color.0, time.delta_seconds());
+        }
+    }
+}
+
+fn update_sprite_colors(mut query: Query<(&TextColorSmoothing, &TargetSpriteColor, &mut Sprite)>, time: Res<Time>) {
+    for (smoothing, target_color, mut sprite) in query.iter_mut() {
+        if sprite.color != target_color.0 {
+            sprite.color = smoothing.smooth_color(sprite.color, target_color.0, time.delta_seconds());
         }
     }
 }
+
+fn update_clear_colors(
+    mut query: Query<(&TextColorSmoothing, &TargetClearColor, &mut Camera)>,
+    clear_color: Res<ClearColor>,
+    time: Res<Time>,
+) {
+    for (smoothing, target_color, mut camera) in query.iter_mut() {
+        let current = match camera.clear_color {
+            ClearColorConfig::Custom(color) => color,
+            _ => clear_color.0,
+        };
+        camera.clear_color = ClearColorConfig::Custom(smoothing.smooth_color(current, target_color.0, time.delta_seconds()));
+    }
+}
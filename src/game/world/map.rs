@@ -0,0 +1,205 @@
+use bevy::math::IVec2;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+///
+/// Width and height of a [Chunk] in tiles.
+///
+pub const CHUNK_SIZE: i32 = 16;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default, Serialize, Deserialize)]
+pub enum Tile {
+    Path,
+    #[default]
+    Wall,
+    Water,
+    Grass,
+    /// Looks like a wall until it is discovered.
+    Hidden,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chunk {
+    tiles: [Tile; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+}
+
+///
+/// The tiles of a level, stored in [CHUNK_SIZE] square chunks so maps can grow in any direction.
+/// Positions outside of every chunk have no tile.
+///
+/// This knows nothing about rendering or the ECS.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TileMap {
+    chunks: HashMap<IVec2, Chunk>,
+}
+
+impl Tile {
+    pub fn from_char(c: char) -> Option<Tile> {
+        match c {
+            '.' => Some(Tile::Path),
+            '#' => Some(Tile::Wall),
+            '~' => Some(Tile::Water),
+            ',' => Some(Tile::Grass),
+            '?' => Some(Tile::Hidden),
+            _ => None,
+        }
+    }
+
+    ///
+    /// The character [Tile::from_char] reads as this tile.
+    ///
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Path => '.',
+            Tile::Wall => '#',
+            Tile::Water => '~',
+            Tile::Grass => ',',
+            Tile::Hidden => '?',
+        }
+    }
+
+    pub fn is_walkable(self) -> bool {
+        matches!(self, Tile::Path | Tile::Grass)
+    }
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self {
+            tiles: [Tile::default(); (CHUNK_SIZE * CHUNK_SIZE) as usize],
+        }
+    }
+}
+
+impl Chunk {
+    fn index(local: IVec2) -> usize {
+        (local.y * CHUNK_SIZE + local.x) as usize
+    }
+
+    pub fn get(&self, local: IVec2) -> Tile {
+        self.tiles[Self::index(local)]
+    }
+
+    pub fn set(&mut self, local: IVec2, tile: Tile) {
+        self.tiles[Self::index(local)] = tile;
+    }
+}
+
+impl TileMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn chunk_position(position: IVec2) -> IVec2 {
+        position.div_euclid(IVec2::splat(CHUNK_SIZE))
+    }
+
+    pub fn local_position(position: IVec2) -> IVec2 {
+        position.rem_euclid(IVec2::splat(CHUNK_SIZE))
+    }
+
+    pub fn get(&self, position: IVec2) -> Option<Tile> {
+        self.chunks
+            .get(&Self::chunk_position(position))
+            .map(|chunk| chunk.get(Self::local_position(position)))
+    }
+
+    ///
+    /// Whether the player can stand on `position`. Anything outside the map is not walkable.
+    ///
+    pub fn is_walkable(&self, position: IVec2) -> bool {
+        self.get(position).is_some_and(Tile::is_walkable)
+    }
+
+    pub fn set(&mut self, position: IVec2, tile: Tile) {
+        self.chunks
+            .entry(Self::chunk_position(position))
+            .or_default()
+            .set(Self::local_position(position), tile);
+    }
+
+    pub fn chunk_positions(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    ///
+    /// Every tile in a chunk, by world tile position.
+    ///
+    pub fn chunk_tiles(&self, chunk: IVec2) -> impl Iterator<Item = (IVec2, Tile)> + '_ {
+        let origin = chunk * CHUNK_SIZE;
+        self.chunks.get(&chunk).into_iter().flat_map(move |c| {
+            (0..CHUNK_SIZE).flat_map(move |y| {
+                (0..CHUNK_SIZE).map(move |x| {
+                    let local = IVec2::new(x, y);
+                    (origin + local, c.get(local))
+                })
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_positions_round_down_to_their_chunk() {
+        assert_eq!(TileMap::chunk_position(IVec2::new(-1, -1)), IVec2::new(-1, -1));
+        assert_eq!(TileMap::local_position(IVec2::new(-1, -1)), IVec2::new(15, 15));
+        assert_eq!(TileMap::chunk_position(IVec2::new(-16, -17)), IVec2::new(-1, -2));
+        assert_eq!(TileMap::local_position(IVec2::new(-16, -17)), IVec2::new(0, 15));
+    }
+
+    #[test]
+    fn chunk_edges_are_in_neighbouring_chunks() {
+        assert_eq!(TileMap::chunk_position(IVec2::new(15, 0)), IVec2::ZERO);
+        assert_eq!(TileMap::chunk_position(IVec2::new(16, 0)), IVec2::X);
+        assert_eq!(TileMap::local_position(IVec2::new(16, 0)), IVec2::ZERO);
+
+        let mut map = TileMap::new();
+        map.set(IVec2::new(15, 15), Tile::Path);
+        map.set(IVec2::new(16, 16), Tile::Water);
+        assert_eq!(map.chunk_positions().count(), 2);
+        assert_eq!(map.get(IVec2::new(15, 15)), Some(Tile::Path));
+        assert_eq!(map.get(IVec2::new(16, 16)), Some(Tile::Water));
+        // The rest of a touched chunk is wall, anything else is off the map.
+        assert_eq!(map.get(IVec2::new(15, 14)), Some(Tile::Wall));
+        assert_eq!(map.get(IVec2::new(16, 15)), None);
+    }
+
+    #[test]
+    fn set_and_get_negative_positions() {
+        let mut map = TileMap::new();
+        map.set(IVec2::new(-1, 0), Tile::Grass);
+        map.set(IVec2::new(0, -1), Tile::Path);
+        map.set(IVec2::new(-17, -33), Tile::Hidden);
+        assert_eq!(map.get(IVec2::new(-1, 0)), Some(Tile::Grass));
+        assert_eq!(map.get(IVec2::new(0, -1)), Some(Tile::Path));
+        assert_eq!(map.get(IVec2::new(-17, -33)), Some(Tile::Hidden));
+        assert_eq!(map.get(IVec2::new(0, 0)), None);
+
+        let tiles: Vec<_> = map.chunk_tiles(IVec2::new(-2, -3)).collect();
+        assert_eq!(tiles.len(), (CHUNK_SIZE * CHUNK_SIZE) as usize);
+        assert!(tiles.contains(&(IVec2::new(-17, -33), Tile::Hidden)));
+    }
+
+    #[test]
+    fn only_path_and_grass_are_walkable() {
+        let mut map = TileMap::new();
+        for (x, tile) in [Tile::Path, Tile::Wall, Tile::Water, Tile::Grass, Tile::Hidden].into_iter().enumerate() {
+            map.set(IVec2::new(x as i32, 0), tile);
+        }
+        let walkable: Vec<_> = (0..5).map(|x| map.is_walkable(IVec2::new(x, 0))).collect();
+        assert_eq!(walkable, [true, false, false, true, false]);
+        assert!(!map.is_walkable(IVec2::new(-1, 0)));
+    }
+
+    #[test]
+    fn chars_round_trip() {
+        for tile in [Tile::Path, Tile::Wall, Tile::Water, Tile::Grass, Tile::Hidden] {
+            assert_eq!(Tile::from_char(tile.to_char()), Some(tile));
+        }
+        assert_eq!(Tile::from_char('x'), None);
+    }
+}
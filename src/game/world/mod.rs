@@ -0,0 +1,93 @@
+use crate::game::primary_logic::{GameState, InGameScreen};
+use bevy::prelude::*;
+
+pub mod map;
+pub mod pathfinding;
+mod render;
+pub mod visibility;
+
+pub use map::{Tile, TileMap};
+pub use pathfinding::find_path;
+pub use render::TileSprite;
+pub use visibility::{FogOfWar, TileVisibility};
+
+///
+/// The tile world the game is played in, active during [GameState::InGame].
+///
+#[derive(Default)]
+pub struct WorldPlugin;
+
+///
+/// Size of a tile in world units.
+///
+pub const TILE_SIZE: f32 = 32.0;
+
+///
+/// The map of the level being played.
+///
+#[derive(Resource, Default, Clone, Debug)]
+pub struct CurrentMap {
+    pub map: TileMap,
+    /// Where the player starts when they have no saved position.
+    pub spawn: IVec2,
+    /// The tiles the level covers. The camera is kept inside these.
+    pub bounds: IRect,
+}
+
+///
+/// Sent whenever [CurrentMap] is replaced by a new map, so everything built from it can be rebuilt.
+///
+#[derive(Event, Default)]
+pub struct MapLoaded;
+
+///
+/// The order the [CurrentMap] is worked on each frame.
+///
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapSet {
+    /// Replacing the map, which sends [MapLoaded].
+    Load,
+    /// Changing a freshly loaded map before anything is built from it, e.g. re-revealing found secrets.
+    Prepare,
+    /// Building and updating the tile sprites.
+    Render,
+}
+
+impl Plugin for WorldPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentMap>();
+        app.init_resource::<FogOfWar>();
+        app.add_event::<MapLoaded>();
+        app.configure_sets(Update, (MapSet::Load, MapSet::Prepare, MapSet::Render).chain());
+        app.add_systems(
+            Update,
+            (
+                (
+                    render::spawn_chunks.run_if(on_event::<MapLoaded>()),
+                    render::update_tile_sprites,
+                )
+                    .chain()
+                    .in_set(MapSet::Render),
+                leave_on_escape.run_if(in_state(InGameScreen::Playing)),
+            )
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+///
+/// Centre of a tile in world space.
+///
+pub fn tile_to_world(tile: IVec2) -> Vec2 {
+    (tile.as_vec2() + 0.5) * TILE_SIZE
+}
+
+pub fn world_to_tile(position: Vec2) -> IVec2 {
+    (position / TILE_SIZE).floor().as_ivec2()
+}
+
+fn leave_on_escape(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::MainMenu);
+    }
+}
@@ -0,0 +1,146 @@
+use super::TileMap;
+use bevy::math::IVec2;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+const DIRECTIONS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+///
+/// The shortest walk from `start` to `goal` over walkable tiles, found with A*. Tiles for which `blocked`
+/// returns true are walked around, e.g. where the player stands.
+///
+/// The path starts with the first step and ends on `goal`, so it is empty if `start` is `goal`. Returns
+/// `None` if `goal` can't be reached.
+///
+pub fn find_path(map: &TileMap, start: IVec2, goal: IVec2, blocked: impl Fn(IVec2) -> bool) -> Option<Vec<IVec2>> {
+    if start == goal {
+        return Some(Vec::new());
+    }
+    if !map.is_walkable(goal) || blocked(goal) {
+        return None;
+    }
+
+    let heuristic = |position: IVec2| (goal - position).abs().element_sum();
+    // Ties go to the tile nearer the goal, which keeps A* from spreading sideways across open ground.
+    let mut open = BinaryHeap::from([Reverse((heuristic(start), heuristic(start), start.to_array()))]);
+    let mut costs = HashMap::from([(start, 0)]);
+    let mut came_from = HashMap::new();
+
+    while let Some(Reverse((_, _, position))) = open.pop() {
+        let position = IVec2::from(position);
+        if position == goal {
+            let mut path = vec![goal];
+            while let Some(previous) = came_from.get(path.last()?).copied() {
+                if previous == start {
+                    break;
+                }
+                path.push(previous);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let cost = costs[&position] + 1;
+        for next in DIRECTIONS.map(|direction| position + direction) {
+            if !map.is_walkable(next) || blocked(next) || costs.get(&next).is_some_and(|known| *known <= cost) {
+                continue;
+            }
+            costs.insert(next, cost);
+            came_from.insert(next, position);
+            open.push(Reverse((cost + heuristic(next), heuristic(next), next.to_array())));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::Tile;
+
+    ///
+    /// A map from rows of [Tile::from_char] characters, with the first row at the top.
+    ///
+    fn map(rows: &[&str]) -> TileMap {
+        let mut map = TileMap::new();
+        for (row, line) in rows.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let y = (rows.len() - 1 - row) as i32;
+                map.set(IVec2::new(x as i32, y), Tile::from_char(c).unwrap());
+            }
+        }
+        map
+    }
+
+    ///
+    /// Whether every step of `path` is a walkable neighbour of the one before it.
+    ///
+    fn is_walk(map: &TileMap, start: IVec2, path: &[IVec2]) -> bool {
+        let mut previous = start;
+        path.iter().all(|step| {
+            let adjacent = (*step - previous).abs().element_sum() == 1;
+            previous = *step;
+            adjacent && map.is_walkable(*step)
+        })
+    }
+
+    #[test]
+    fn walks_straight_along_an_open_row() {
+        let map = map(&["....."]);
+        let path = find_path(&map, IVec2::ZERO, IVec2::new(4, 0), |_| false).unwrap();
+        assert_eq!(path, (1..=4).map(|x| IVec2::new(x, 0)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn detours_around_walls() {
+        let map = map(&[
+            ".....", //
+            ".###.",
+            "..#..",
+        ]);
+        let (start, goal) = (IVec2::ZERO, IVec2::new(4, 0));
+        let path = find_path(&map, start, goal, |_| false).unwrap();
+        assert!(is_walk(&map, start, &path));
+        assert_eq!(path.last(), Some(&goal));
+        // Up the left side, across the top and down the right side.
+        assert_eq!(path.len(), 8);
+    }
+
+    #[test]
+    fn unreachable_goals_have_no_path() {
+        let map = map(&["..#.."]);
+        assert_eq!(find_path(&map, IVec2::ZERO, IVec2::new(4, 0), |_| false), None);
+        // Off the map entirely.
+        assert_eq!(find_path(&map, IVec2::ZERO, IVec2::new(9, 9), |_| false), None);
+    }
+
+    #[test]
+    fn goals_in_walls_have_no_path() {
+        let map = map(&["..#.."]);
+        assert_eq!(find_path(&map, IVec2::ZERO, IVec2::new(2, 0), |_| false), None);
+    }
+
+    #[test]
+    fn start_on_the_goal_is_an_empty_path() {
+        let map = map(&["..."]);
+        assert_eq!(find_path(&map, IVec2::X, IVec2::X, |_| false), Some(Vec::new()));
+    }
+
+    #[test]
+    fn blocked_tiles_are_walked_around() {
+        let map = map(&[
+            "...", //
+            "...",
+        ]);
+        let (start, goal) = (IVec2::ZERO, IVec2::new(2, 0));
+        let blocker = IVec2::X;
+        let path = find_path(&map, start, goal, |tile| tile == blocker).unwrap();
+        assert!(is_walk(&map, start, &path));
+        assert!(!path.contains(&blocker));
+        assert_eq!(path.len(), 4);
+
+        // A blocked goal can't be reached, and neither can anything cut off by blocked tiles.
+        assert_eq!(find_path(&map, start, goal, |tile| tile == goal), None);
+        assert_eq!(find_path(&map, start, goal, |tile| tile.x == 1), None);
+    }
+}
@@ -0,0 +1,98 @@
+use super::*;
+use crate::game::internal::brighten;
+use crate::game::textcolor_smoothing::{TargetSpriteColor, TextColorSmoothing};
+use crate::game::world::map::CHUNK_SIZE;
+
+///
+/// A rendered chunk of the [CurrentMap]. Its tiles are spawned as children.
+///
+#[derive(Default, Component)]
+pub struct ChunkEntity;
+
+#[derive(Component)]
+pub struct TileSprite(pub IVec2);
+
+pub fn tile_color(tile: Tile) -> Color {
+    match tile {
+        Tile::Path => Color::srgb(0.55, 0.45, 0.3),
+        Tile::Wall | Tile::Hidden => Color::srgb(0.2, 0.2, 0.22),
+        Tile::Water => Color::srgb(0.15, 0.3, 0.6),
+        Tile::Grass => Color::srgb(0.2, 0.45, 0.2),
+    }
+}
+
+///
+/// How a tile is drawn under the [FogOfWar]: hidden until seen, and darkened once out of sight.
+///
+pub fn fogged_tile_color(tile: Tile, visibility: TileVisibility) -> Color {
+    match visibility {
+        TileVisibility::Unseen => Color::NONE,
+        TileVisibility::Seen => brighten(tile_color(tile), 0.35),
+        TileVisibility::Visible => tile_color(tile),
+    }
+}
+
+pub fn spawn_chunks(
+    mut commands: Commands,
+    current_map: Res<CurrentMap>,
+    fog: Res<FogOfWar>,
+    old_chunks: Query<Entity, With<ChunkEntity>>,
+) {
+    for entity in old_chunks.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for chunk in current_map.map.chunk_positions() {
+        let origin = (chunk * CHUNK_SIZE).as_vec2() * TILE_SIZE;
+        commands
+            .spawn((
+                StateScoped(GameState::InGame),
+                SpatialBundle::from_transform(Transform::from_translation(origin.extend(0.0))),
+                ChunkEntity,
+            ))
+            .with_children(|parent| {
+                for (tile_position, tile) in current_map.map.chunk_tiles(chunk) {
+                    let color = fogged_tile_color(tile, fog.get(tile_position));
+                    parent.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color,
+                                custom_size: Some(Vec2::splat(TILE_SIZE)),
+                                ..default()
+                            },
+                            transform: Transform::from_translation((tile_to_world(tile_position) - origin).extend(0.0)),
+                            ..default()
+                        },
+                        TileSprite(tile_position),
+                        TargetSpriteColor(color),
+                        TextColorSmoothing {
+                            flat: 0.5,
+                            proportional: 2.0,
+                        },
+                    ));
+                }
+            });
+    }
+}
+
+///
+/// Fades tile colors to match when the map is edited, e.g. when a hidden tile is revealed, or when the
+/// [FogOfWar] moves.
+///
+pub fn update_tile_sprites(
+    current_map: Res<CurrentMap>,
+    fog: Res<FogOfWar>,
+    mut query: Query<(&TileSprite, &mut TargetSpriteColor)>,
+) {
+    if !current_map.is_changed() && !fog.is_changed() {
+        return;
+    }
+    for (tile_sprite, mut target) in query.iter_mut() {
+        if let Some(tile) = current_map.map.get(tile_sprite.0) {
+            let color = fogged_tile_color(tile, fog.get(tile_sprite.0));
+            if target.0 != color {
+                target.0 = color;
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::game::world::{Tile, TileMap};
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+///
+/// How much of a tile the player knows about.
+///
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub enum TileVisibility {
+    /// Never been in sight. Not drawn at all.
+    #[default]
+    Unseen,
+    /// Seen before but out of sight now. Drawn darkened.
+    Seen,
+    /// In sight of the player right now.
+    Visible,
+}
+
+///
+/// What the player has seen of the [CurrentMap](super::CurrentMap).
+///
+#[derive(Resource, Default, Clone, Debug)]
+pub struct FogOfWar {
+    pub seen: HashSet<IVec2>,
+    pub visible: HashSet<IVec2>,
+}
+
+impl FogOfWar {
+    pub fn get(&self, position: IVec2) -> TileVisibility {
+        if self.visible.contains(&position) {
+            TileVisibility::Visible
+        } else if self.seen.contains(&position) {
+            TileVisibility::Seen
+        } else {
+            TileVisibility::Unseen
+        }
+    }
+}
+
+///
+/// Whether a tile can be seen past. Hidden tiles look like walls, so they block sight like walls.
+///
+pub fn is_transparent(tile: Tile) -> bool {
+    !matches!(tile, Tile::Wall | Tile::Hidden)
+}
+
+///
+/// The tiles on the line from `from` to `to`, both included, without diagonal gaps wider than a corner.
+/// A line crosses the same tiles whichever end it is drawn from.
+///
+pub fn line(from: IVec2, to: IVec2) -> Vec<IVec2> {
+    if (to.x, to.y) < (from.x, from.y) {
+        let mut tiles = line(to, from);
+        tiles.reverse();
+        return tiles;
+    }
+
+    let delta = (to - from).abs();
+    let step = (to - from).signum();
+    let mut error = delta.x - delta.y;
+    let mut position = from;
+    let mut tiles = vec![position];
+    while position != to {
+        let doubled = error * 2;
+        if doubled > -delta.y {
+            error -= delta.y;
+            position.x += step.x;
+        }
+        if doubled < delta.x {
+            error += delta.x;
+            position.y += step.y;
+        }
+        tiles.push(position);
+    }
+    tiles
+}
+
+///
+/// Whether `to` can be seen from `from`. Every tile between them has to be see-through,
+/// but the ends don't, so walls can be seen without seeing through them.
+/// Anything outside the map blocks sight, and so do two solid tiles touching at a corner.
+///
+pub fn line_of_sight(map: &TileMap, from: IVec2, to: IVec2) -> bool {
+    let transparent = |position: IVec2| map.get(position).is_some_and(is_transparent);
+    let tiles = line(from, to);
+    let between = tiles.len().saturating_sub(2);
+    tiles.iter().skip(1).take(between).all(|position| transparent(*position))
+        && tiles.windows(2).all(|step| {
+            let (a, b) = (step[0], step[1]);
+            a.x == b.x || a.y == b.y || transparent(IVec2::new(b.x, a.y)) || transparent(IVec2::new(a.x, b.y))
+        })
+}
+
+///
+/// Every tile on the map within `radius` tiles of `origin` which is in [line_of_sight] of it.
+///
+pub fn visible_tiles(map: &TileMap, origin: IVec2, radius: i32) -> HashSet<IVec2> {
+    let mut visible = HashSet::new();
+    for y in -radius..=radius {
+        for x in -radius..=radius {
+            let offset = IVec2::new(x, y);
+            // The extra `radius` rounds the circle out so it doesn't have single tiles poking out at the sides.
+            if offset.length_squared() > radius * radius + radius {
+                continue;
+            }
+            let position = origin + offset;
+            if map.get(position).is_some() && line_of_sight(map, origin, position) {
+                visible.insert(position);
+            }
+        }
+    }
+    visible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///
+    /// A map from rows of [Tile::from_char] characters, with the first row at the top.
+    ///
+    fn map(rows: &[&str]) -> TileMap {
+        let mut map = TileMap::new();
+        for (row, line) in rows.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let y = (rows.len() - 1 - row) as i32;
+                map.set(IVec2::new(x as i32, y), Tile::from_char(c).unwrap());
+            }
+        }
+        map
+    }
+
+    fn offsets() -> impl Iterator<Item = IVec2> {
+        (-6..=6).flat_map(|y| (-6..=6).map(move |x| IVec2::new(x, y)))
+    }
+
+    #[test]
+    fn lines_include_both_ends_without_gaps() {
+        let from = IVec2::new(2, -1);
+        for offset in offsets() {
+            let tiles = line(from, from + offset);
+            assert_eq!(tiles.first(), Some(&from));
+            assert_eq!(tiles.last(), Some(&(from + offset)));
+            assert_eq!(tiles.len() as i32, offset.abs().max_element() + 1);
+            assert!(tiles.windows(2).all(|step| (step[1] - step[0]).abs().max_element() == 1));
+        }
+        assert_eq!(line(from, from), [from]);
+    }
+
+    #[test]
+    fn lines_are_the_same_from_either_end() {
+        for offset in offsets() {
+            let mut back = line(offset, IVec2::ZERO);
+            back.reverse();
+            assert_eq!(line(IVec2::ZERO, offset), back, "line to {offset}");
+        }
+    }
+
+    #[test]
+    fn walls_are_seen_but_hide_whats_behind_them() {
+        let map = map(&["..#.."]);
+        let visible = visible_tiles(&map, IVec2::ZERO, 5);
+        assert!(visible.contains(&IVec2::new(1, 0)));
+        assert!(visible.contains(&IVec2::new(2, 0)));
+        assert!(!visible.contains(&IVec2::new(3, 0)));
+        assert!(!line_of_sight(&map, IVec2::new(4, 0), IVec2::ZERO));
+    }
+
+    #[test]
+    fn sight_stops_at_the_radius() {
+        let row = ".".repeat(13);
+        let map = map(&vec![row.as_str(); 13]);
+        let origin = IVec2::splat(6);
+        let visible = visible_tiles(&map, origin, 3);
+        for (offset, seen) in [((3, 0), true), ((4, 0), false), ((2, 2), true), ((3, 1), true), ((3, 2), false)] {
+            assert_eq!(visible.contains(&(origin + IVec2::from(offset))), seen, "{offset:?}");
+        }
+        // Nothing off the map is visible.
+        assert!(visible_tiles(&map, IVec2::ZERO, 3).iter().all(|tile| tile.min_element() >= 0));
+    }
+
+    #[test]
+    fn sight_doesnt_squeeze_between_touching_corners() {
+        let closed = map(&[
+            "...", //
+            "#..",
+            ".#.",
+        ]);
+        assert!(!line_of_sight(&closed, IVec2::ZERO, IVec2::new(1, 1)));
+        assert!(!line_of_sight(&closed, IVec2::ZERO, IVec2::new(2, 2)));
+        assert!(!line_of_sight(&closed, IVec2::new(2, 2), IVec2::ZERO));
+
+        let open = map(&[
+            "...", //
+            "#..",
+            "...",
+        ]);
+        assert!(line_of_sight(&open, IVec2::ZERO, IVec2::new(1, 1)));
+        assert!(line_of_sight(&open, IVec2::ZERO, IVec2::new(2, 2)));
+    }
+}
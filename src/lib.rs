@@ -0,0 +1,5 @@
+//!
+//! The game as a library, so benchmarks can use it. `main.rs` only starts it.
+//!
+pub mod game;
+pub mod utils;
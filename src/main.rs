@@ -1,8 +1,5 @@
-mod game;
-mod utils;
-
-use crate::game::GamePlugins;
 use bevy::prelude::*;
+use secrets_of_the_path::game::GamePlugins;
 
 #[bevy_main]
 fn main() {
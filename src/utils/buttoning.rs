@@ -2,13 +2,19 @@ use bevy::prelude::*;
 use bevy::sprite::Anchor;
 use bevy::text::TextLayoutInfo;
 use bevy::window::PrimaryWindow;
-use std::marker::PhantomData;
 
 pub struct ButtoningPlugin;
 
 #[derive(Default, Component)]
 pub struct TextButton;
 
+///
+/// A button drawn as a sprite in the world, like a chest or a signpost. Its bounding box follows the
+/// sprite's size and anchor. These are left out of keyboard focus, since the keys move the player.
+///
+#[derive(Default, Component)]
+pub struct SpriteButton;
+
 #[derive(Default, Component)]
 pub struct EnableHoverTest;
 
@@ -23,9 +29,35 @@ pub struct GenericBoundingBox {
     rect: Rect,
 }
 
+impl GenericBoundingBox {
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.rect.contains(point)
+    }
+}
+
 #[derive(Default, Component)]
 pub struct Hovered;
 
+///
+/// Makes a button count as [Hovered] whatever the cursor is doing, e.g. a world object the player is standing next to.
+///
+#[derive(Default, Component)]
+pub struct HoverOverride;
+
+///
+/// The button picked with the keyboard. It counts as [Hovered] until the mouse is moved or clicked, and
+/// pressing Enter or Space clicks it with [MouseButton::Left].
+///
+#[derive(Default, Component)]
+pub struct Focused;
+
+///
+/// A button which steps a value, like a setting. While it is [Focused], Right clicks it with [MouseButton::Left]
+/// and Left with [MouseButton::Right], instead of moving the focus sideways.
+///
+#[derive(Default, Component)]
+pub struct Stepper;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum ButtonClickType {
     Pressed,
@@ -46,39 +78,186 @@ impl Plugin for ButtoningPlugin {
             PreUpdate,
             (
                 setup_bounding_boxes,
-                (TextButton::update_bounding_boxes)
+                (TextButton::update_bounding_boxes, SpriteButton::update_bounding_boxes)
                     .before(update_hoverers)
                     .after(setup_bounding_boxes),
+                (clear_focus_on_pointer, navigate_focus)
+                    .chain()
+                    .after(TextButton::update_bounding_boxes)
+                    .before(update_hoverers),
                 update_hoverers,
             ),
         );
-        app.add_systems(Update, update_clicks);
+        app.add_systems(Update, (update_clicks, click_focused, step_focused));
         app.add_event::<ButtonClickEvent>();
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn update_hoverers(
     mut commands: Commands,
-    query: Query<(Entity, Has<Hovered>, &GenericBoundingBox), With<EnableHoverTest>>,
+    query: Query<(Entity, Has<Hovered>, Has<Focused>, Has<HoverOverride>, &GenericBoundingBox), With<EnableHoverTest>>,
     windowq: Query<&Window, With<PrimaryWindow>>,
     cameraq: Query<(&Camera, &GlobalTransform)>,
 ) {
-    if windowq.is_empty() || cameraq.is_empty() {
+    // Focused and overridden buttons are hovered even without a window or camera.
+    let cursor_position = windowq.get_single().ok().and_then(Window::cursor_position).and_then(|cursor_position| {
+        let (camera, ctransform) = cameraq.get_single().ok()?;
+        Some(
+            camera
+                .viewport_to_world_2d(ctransform, cursor_position)
+                .unwrap_or(cursor_position),
+        )
+    });
+
+    for (entity, was_hovered, focused, overridden, bounding_box) in query.iter() {
+        let hovered = focused || overridden || cursor_position.is_some_and(|cursor_position| bounding_box.rect.contains(cursor_position));
+        if hovered && !was_hovered {
+            commands.entity(entity).insert(Hovered);
+        } else if !hovered && was_hovered {
+            commands.entity(entity).remove::<Hovered>();
+        }
+    }
+}
+
+fn clear_focus_on_pointer(
+    mut commands: Commands,
+    windowq: Query<&Window, With<PrimaryWindow>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut last_cursor_position: Local<Option<Vec2>>,
+    query: Query<Entity, With<Focused>>,
+) {
+    let cursor_position = windowq.get_single().ok().and_then(Window::cursor_position);
+    let moved = cursor_position != *last_cursor_position;
+    *last_cursor_position = cursor_position;
+    if !moved && mouse_buttons.get_just_pressed().next().is_none() {
         return;
     }
-    if let Some(cursor_position) = windowq.single().cursor_position() {
-        let (camera, ctransform) = cameraq.single();
-        let cursor_position = camera
-            .viewport_to_world_2d(ctransform, cursor_position)
-            .unwrap_or(cursor_position);
-
-        for (entity, was_hovered, bounding_box) in query.iter() {
-            let hovered = bounding_box.rect.contains(cursor_position);
-            if hovered && !was_hovered {
-                commands.entity(entity).insert(Hovered);
-            } else if !hovered && was_hovered {
-                commands.entity(entity).remove::<Hovered>();
-            }
+    for entity in query.iter() {
+        commands.entity(entity).remove::<Focused>();
+    }
+}
+
+///
+/// Moves [Focused] with the arrow keys to the nearest button in that direction, or with Tab through
+/// the buttons in reading order.
+///
+#[allow(clippy::type_complexity)]
+fn navigate_focus(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    query: Query<(Entity, &GenericBoundingBox, Has<Focused>), (With<EnableHoverTest>, With<Clickable>, Without<SpriteButton>)>,
+    steppers: Query<(), (With<Focused>, With<Stepper>)>,
+) {
+    let direction = [
+        (KeyCode::ArrowUp, Vec2::Y),
+        (KeyCode::ArrowDown, Vec2::NEG_Y),
+        (KeyCode::ArrowLeft, Vec2::NEG_X),
+        (KeyCode::ArrowRight, Vec2::X),
+    ]
+    .into_iter()
+    .find(|(key, _)| keys.just_pressed(*key))
+    .map(|(_, direction)| direction);
+    let tab = keys.just_pressed(KeyCode::Tab);
+    if direction.is_none() && !tab {
+        return;
+    }
+    // Left and Right belong to a focused stepper, see step_focused.
+    if direction.is_some_and(|direction| direction.y == 0.0) && !steppers.is_empty() {
+        return;
+    }
+
+    let mut buttons: Vec<(Entity, Vec2)> = query
+        .iter()
+        .filter(|(_, bounding_box, _)| !bounding_box.rect.is_empty())
+        .map(|(entity, bounding_box, _)| (entity, bounding_box.rect.center()))
+        .collect();
+    // Reading order: top to bottom, then left to right.
+    buttons.sort_by(|(_, a), (_, b)| b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x)));
+    let current = query.iter().find(|(_, _, focused)| *focused).map(|(entity, _, _)| entity);
+    let current_index = current.and_then(|current| buttons.iter().position(|(entity, _)| *entity == current));
+
+    let next = match (current_index, direction) {
+        (None, _) => buttons.first().map(|(entity, _)| *entity),
+        (Some(index), Some(direction)) => {
+            let from = buttons[index].1;
+            buttons
+                .iter()
+                .filter_map(|(entity, center)| {
+                    let offset = *center - from;
+                    let along = offset.dot(direction);
+                    // Prefer buttons straight ahead over ones which are closer but off to the side.
+                    (along > 1.0).then(|| (*entity, along + (offset - direction * along).length() * 2.0))
+                })
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(entity, _)| entity)
+        }
+        (Some(index), None) => {
+            let step = if keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight) {
+                buttons.len() - 1
+            } else {
+                1
+            };
+            Some(buttons[(index + step) % buttons.len()].0)
+        }
+    };
+
+    if let Some(next) = next.filter(|next| Some(*next) != current) {
+        if let Some(current) = current {
+            commands.entity(current).remove::<Focused>();
+        }
+        commands.entity(next).insert(Focused);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn click_focused(
+    query: Query<(Entity, &GenericBoundingBox), (With<Focused>, With<Clickable>)>,
+    mut event_writer: EventWriter<ButtonClickEvent>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    const CLICK_KEYS: [KeyCode; 3] = [KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space];
+    for (entity, bounding_box) in query.iter() {
+        if keys.any_just_pressed(CLICK_KEYS) {
+            event_writer.send(ButtonClickEvent {
+                entity,
+                mouse_position: bounding_box.rect.center(),
+                mouse_button: MouseButton::Left,
+                click_type: ButtonClickType::Pressed,
+            });
+        }
+        if keys.any_just_released(CLICK_KEYS) {
+            event_writer.send(ButtonClickEvent {
+                entity,
+                mouse_position: bounding_box.rect.center(),
+                mouse_button: MouseButton::Left,
+                click_type: ButtonClickType::Released,
+            });
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn step_focused(
+    query: Query<(Entity, &GenericBoundingBox), (With<Focused>, With<Stepper>, With<Clickable>)>,
+    mut event_writer: EventWriter<ButtonClickEvent>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    for (entity, bounding_box) in query.iter() {
+        for (key, mouse_button) in [(KeyCode::ArrowRight, MouseButton::Left), (KeyCode::ArrowLeft, MouseButton::Right)] {
+            let click_type = if keys.just_pressed(key) {
+                ButtonClickType::Pressed
+            } else if keys.just_released(key) {
+                ButtonClickType::Released
+            } else {
+                continue;
+            };
+            event_writer.send(ButtonClickEvent {
+                entity,
+                mouse_position: bounding_box.rect.center(),
+                mouse_button,
+                click_type,
+            });
         }
     }
 }
@@ -121,9 +300,10 @@ fn update_clicks(
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn setup_bounding_boxes(
     mut commands: Commands,
-    query: Query<Entity, (With<TextButton>, Without<GenericBoundingBox>)>,
+    query: Query<Entity, (Or<(With<TextButton>, With<SpriteButton>)>, Without<GenericBoundingBox>)>,
 ) {
     for entity in query.iter() {
         commands
@@ -153,3 +333,13 @@ impl TextButton {
         }
     }
 }
+
+impl SpriteButton {
+    fn update_bounding_boxes(mut query: Query<(&mut GenericBoundingBox, &Sprite, &Transform), With<SpriteButton>>) {
+        for (mut bounding_box, sprite, transform) in query.iter_mut() {
+            let size = sprite.custom_size.unwrap_or_default() * transform.scale.xy();
+            let center = transform.translation.xy() - size * sprite.anchor.as_vec();
+            bounding_box.rect = Rect::from_center_size(center, size);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+///
+/// Writes `contents` to `path` without ever leaving a half written file behind.
+///
+/// The data is written and synced to a temporary file next to `path`, which is then renamed over
+/// it. Parent directories are created if needed.
+///
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temp_path, path)
+}
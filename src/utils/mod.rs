@@ -1,3 +1,5 @@
 pub mod position_smoothing;
 pub mod responsive;
-pub mod buttoning;
\ No newline at end of file
+pub mod buttoning;
+pub mod files;
+pub mod ron_asset;
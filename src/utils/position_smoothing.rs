@@ -1,4 +1,3 @@
-use bevy::math::NormedVectorSpace;
 use bevy::prelude::*;
 
 pub struct PositionSmoothingPlugin;
@@ -45,7 +44,7 @@ impl Default for PFPSmoothing {
             far_modifier: DEFAULT_FAR_MODIFIER,
             near_slowdown: true,
             near_threshold: None,
-            near_modifier: DEFAULT_FAR_MODIFIER,
+            near_modifier: DEFAULT_NEAR_MODIFIER,
             end_threshold: DEFAULT_END_THRESHOLD,
         }
     }
@@ -65,8 +64,10 @@ impl PFPSmoothing {
     /// # Examples
     ///
     /// ```
+    /// # use bevy::math::Vec3;
+    /// # use secrets_of_the_path::utils::position_smoothing::PFPSmoothing;
     /// // Normally you would be placing this on an entity and allowing the system to use it, but for the purpose of this example we will fabricate some values.
-    /// let smooth_component = SmoothPosition::default();
+    /// let smooth_component = PFPSmoothing::default();
     /// let current = Vec3::new(0.0, 0.0, 0.0);
     /// let target = Vec3::new(512.0, 0.0, 0.0);
     /// let delta_time = 1.0 / 50.0;
@@ -75,7 +76,7 @@ impl PFPSmoothing {
     /// ```
     pub fn smooth_position(&self, position: Vec3, target: Vec3, delta_time: f32) -> Vec3 {
         let error = position.distance(target);
-        let new_pos = if error < self.end_threshold {
+        if error < self.end_threshold {
             target // teleport to target when we are this close
         } else {
             let near_threshold = self.near_threshold.unwrap_or(self.smoothing_speed);
@@ -90,9 +91,7 @@ impl PFPSmoothing {
             } else {
                 position + (delta_time * self.smoothing_speed).min(error) * direction
             }
-        };
-
-        new_pos
+        }
     }
 }
 
@@ -108,6 +107,7 @@ impl PSmoothing {
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Component, Default)]
 pub enum PositionSmoothing {
     #[default]
@@ -1,6 +1,5 @@
 use bevy::prelude::*;
-use bevy::window::{PrimaryWindow, WindowResolution};
-use crate::utils::position_smoothing::{PositionSmoothing, TargetPosition};
+use crate::utils::position_smoothing::TargetPosition;
 
 ///
 /// Plugin which provides an easy way to do 2d responsive elements, based on using the
@@ -14,12 +13,6 @@ pub enum ResponsiveValue {
     Percentage(f32),
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct ResponsiveVec2 {
-    pub x: ResponsiveValue,
-    pub y: ResponsiveValue,
-}
-
 #[derive(Copy, Clone, Debug)]
 pub struct ResponsiveVec3 {
     pub x: ResponsiveValue,
@@ -27,12 +20,6 @@ pub struct ResponsiveVec3 {
     pub z: ResponsiveValue,
 }
 
-impl ResponsiveVec2 {
-    pub fn resolve(&self, area: Rect) -> Vec2 {
-        Vec2::new(self.x.resolve(area.min.x, area.max.y), self.y.resolve(area.min.y, area.max.y))
-    }
-}
-
 impl ResponsiveVec3 {
     pub fn resolve(&self, area: Rect) -> Vec3 {
         Vec3::new(self.x.resolve(area.min.x, area.max.x), self.y.resolve(area.min.y, area.max.y), self.z.resolve(0.0, 1.0))
@@ -67,21 +54,32 @@ impl Plugin for ResponsivePlugin {
     }
 }
 
-fn update_positions(mut query: Query<(&mut Transform, &ResponsivePosition), (Without<TargetPosition>)>, cameraq: Query<&OrthographicProjection>) {
+///
+/// The area the camera sees in world space, which responsive positions are relative to.
+///
+fn view_area(projection: &OrthographicProjection, transform: &Transform) -> Rect {
+    let offset = transform.translation.xy();
+    Rect::from_corners(projection.area.min + offset, projection.area.max + offset)
+}
+
+#[allow(clippy::type_complexity)]
+fn update_positions(mut query: Query<(&mut Transform, &ResponsivePosition), (Without<TargetPosition>, Without<OrthographicProjection>)>, cameraq: Query<(&OrthographicProjection, &Transform)>) {
     if cameraq.is_empty() { return; }
-    let camera = cameraq.single();
+    let (projection, transform) = cameraq.single();
+    let area = view_area(projection, transform);
 
     for (mut target, pos) in query.iter_mut() {
-        target.translation = pos.position.resolve(camera.area);
+        target.translation = pos.position.resolve(area);
     }
 }
 
-fn update_smoothed_positions(mut query: Query<(&mut TargetPosition, &ResponsivePosition)>, cameraq: Query<&OrthographicProjection>) {
+fn update_smoothed_positions(mut query: Query<(&mut TargetPosition, &ResponsivePosition)>, cameraq: Query<(&OrthographicProjection, &Transform)>) {
     if cameraq.is_empty() { return; }
-    let camera = cameraq.single();
+    let (projection, transform) = cameraq.single();
+    let area = view_area(projection, transform);
 
     for (mut target, pos) in query.iter_mut() {
-        target.0 = pos.position.resolve(camera.area);
+        target.0 = pos.position.resolve(area);
     }
 }
 
@@ -0,0 +1,76 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{Asset, AssetLoader, AsyncReadExt, LoadContext};
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use thiserror::Error;
+
+///
+/// A RON file which could not be parsed, with the position of the problem.
+///
+#[derive(Debug, Error)]
+#[error("line {line}, column {column}: {message}")]
+pub struct RonParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl From<ron::error::SpannedError> for RonParseError {
+    fn from(e: ron::error::SpannedError) -> RonParseError {
+        RonParseError {
+            line: e.position.line,
+            column: e.position.col,
+            message: e.code.to_string(),
+        }
+    }
+}
+
+pub fn parse_ron<T: DeserializeOwned>(text: &str) -> Result<T, RonParseError> {
+    Ok(ron::from_str(text)?)
+}
+
+///
+/// An asset read from a RON file, which is checked as it is turned into the asset.
+///
+pub trait RonAsset: Asset + Sized {
+    /// What the file deserializes to.
+    type File: DeserializeOwned;
+    type Error: From<RonParseError> + From<std::io::Error> + std::error::Error + Send + Sync + 'static;
+
+    /// The extension of files holding this asset, without the leading dot.
+    const EXTENSION: &'static str;
+
+    fn from_file(file: Self::File) -> Result<Self, Self::Error>;
+}
+
+///
+/// Loads any [RonAsset] from files with its extension.
+///
+pub struct RonLoader<T>(PhantomData<fn() -> T>);
+
+impl<T> Default for RonLoader<T> {
+    fn default() -> Self {
+        RonLoader(PhantomData)
+    }
+}
+
+impl<T: RonAsset> AssetLoader for RonLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = T::Error;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<T, T::Error> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).await?;
+        T::from_file(parse_ron(&text)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        std::slice::from_ref(&T::EXTENSION)
+    }
+}